@@ -0,0 +1,63 @@
+extern crate rendust;
+
+use std::env;
+
+use rendust::Context;
+use rendust::math::{self, Vec3, Mat4};
+use rendust::window::Window;
+use rendust::camera::Camera;
+use rendust::lighting::{Ambient, Directional};
+use rendust::gltf::{Gltf, GltfModel};
+use rendust::postprocess::{PostProcess, ToneMapping};
+
+fn main() {
+    let mut window = Window::new("Rendust example - glTF scene", 1280, 720, true).unwrap();
+    let ctx = Context::new().unwrap();
+
+    // Usage: gltf [scene.gltf | scene.glb]
+    let path = env::args().nth(1).unwrap_or("examples/models/cubes.gltf".to_string());
+    let gltf = Gltf::load(path).unwrap();
+
+    // Use the first camera of the scene, if any
+    match gltf.scene_cameras().into_iter().next() {
+        Some((camera, placed)) => {
+            ctx.set_projection(camera.projection_matrix(1280.0 / 720.0));
+            ctx.set_camera(&placed);
+        },
+        None => {
+            ctx.set_projection(math::mat4_perspective(60.0, 1280.0 / 720.0, 0.1, 1000.0));
+            ctx.set_camera(&Camera::new(Vec3::new(0.0, 1.0, 4.0)));
+        }
+    }
+
+    // Light the scene with its own lights, or a default one
+    let mut lights = gltf.scene_lights();
+
+    if lights.is_empty() {
+        lights.push(Directional::new(Vec3::new(-0.3, -0.5, -1.0), [1.0, 1.0, 1.0], 3.0).into());
+    }
+
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_lights(&lights);
+
+    let model = GltfModel::new(&gltf).unwrap();
+
+    let post = PostProcess::hdr(1280, 720, ToneMapping::Aces, 1.0).unwrap();
+    post.tone_mapping().unwrap().set_gamma(2.2).unwrap();
+
+    let mut angle = 0.0f32;
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+        angle += 0.5;
+
+        post.render(|| {
+            rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+            rendust::clear();
+
+            model.render(&ctx, Mat4::from_angle_y(math::Deg(angle)));
+        });
+
+        window.swap_buffers();
+    }
+}
@@ -0,0 +1,79 @@
+extern crate rendust;
+extern crate glutin;
+
+use rendust::Context;
+use rendust::math::{self, Vec3};
+use rendust::window::Window;
+use rendust::mesh::{Vertex, PrimitiveType, Mesh};
+use rendust::camera::Camera;
+use rendust::lighting::{Ambient, Point};
+use rendust::postprocess::{PostProcess, Effect, ToneMapping};
+
+fn main() {
+    let mut window = Window::new("Rendust example - HDR", 1280, 720, true).unwrap();
+
+    let ctx = Context::new().unwrap();
+    ctx.set_projection(math::mat4_perspective(90.0, 1280.0 / 720.0, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.05));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 2.0, 4.0)));
+
+    // Lights much brighter than white, which would clip without HDR
+    ctx.set_lights(&[
+        Point::new(Vec3::new(-2.0, 1.0, 0.0), [1.0, 0.5, 0.2], 20.0).into(),
+        Point::new(Vec3::new( 2.0, 1.0, 0.0), [0.2, 0.5, 1.0], 5.0).into(),
+    ]);
+
+    let mut exposure = 1.0;
+    let mut operator = ToneMapping::Aces;
+
+    let mut post = PostProcess::hdr(1280, 720, operator, exposure).unwrap();
+    post.push(Effect::bloom(1.0, 0.5).unwrap());
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    while !window.should_exit {
+        let mut key = None;
+
+        // Up and down change the exposure, T switches the tone mapping
+        window.handle_events(|evt| match evt {
+            glutin::WindowEvent::KeyboardInput { input, .. } => {
+                if input.state == glutin::ElementState::Pressed {
+                    key = input.virtual_keycode;
+                }
+            },
+            _ => ()
+        });
+
+        if let Some(tone_mapping) = post.tone_mapping() {
+            match key {
+                Some(glutin::VirtualKeyCode::Up) => exposure *= 1.25,
+                Some(glutin::VirtualKeyCode::Down) => exposure /= 1.25,
+                Some(glutin::VirtualKeyCode::T) => {
+                    operator = match operator {
+                        ToneMapping::Reinhard => ToneMapping::Aces,
+                        ToneMapping::Aces => ToneMapping::Reinhard
+                    };
+                },
+                _ => ()
+            }
+
+            tone_mapping.set_exposure(exposure).unwrap();
+            tone_mapping.set_tone_mapping(operator).unwrap();
+        }
+
+        post.render(|| {
+            rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+            rendust::clear();
+
+            ctx.set_model(math::mat4_identity());
+            floor.render();
+        });
+
+        window.swap_buffers();
+    }
+}
@@ -0,0 +1,24 @@
+extern crate rendust;
+
+use rendust::Context;
+use rendust::window::Headless;
+use rendust::mesh::{Vertex, PrimitiveType, Mesh};
+
+fn main() {
+    let surface = Headless::new(640, 480).unwrap();
+    let _ = Context::new().unwrap();
+
+    let triangle = Mesh::new(PrimitiveType::Triangles, &[
+        Vertex::new(-1.0, -1.0, 0.0).color(1.0, 0.0, 0.0, 1.0),
+        Vertex::new( 0.0,  1.0, 0.0).color(0.0, 1.0, 0.0, 1.0),
+        Vertex::new( 1.0, -1.0, 0.0).color(0.0, 0.0, 1.0, 1.0),
+    ], None);
+
+    rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+    rendust::clear();
+
+    triangle.render();
+
+    // Save the rendered frame without ever opening a window
+    surface.capture_frame().save("headless.png").unwrap();
+}
@@ -0,0 +1,36 @@
+extern crate rendust;
+
+use rendust::window::Window;
+use rendust::shaders::Program;
+use rendust::mesh::{Vertex, PrimitiveType, Mesh};
+
+fn main() {
+    let mut window = Window::new("Rendust example - Hot reload", 1280, 720, true).unwrap();
+
+    // Edit the shader files while the example is running
+    let mut program = Program::from_files("examples/shaders/hot_reload.vert", "examples/shaders/hot_reload.frag").unwrap();
+    program.bind();
+
+    let triangle = Mesh::new(PrimitiveType::Triangles, &[
+        Vertex::new(-1.0, -1.0, 0.0).color(1.0, 0.0, 0.0, 1.0),
+        Vertex::new( 0.0,  1.0, 0.0).color(0.0, 1.0, 0.0, 1.0),
+        Vertex::new( 1.0, -1.0, 0.0).color(0.0, 0.0, 1.0, 1.0),
+    ], None);
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+
+        match program.reload_if_changed() {
+            Ok(true) => println!("Shaders reloaded"),
+            Ok(false) => (),
+            Err(err) => println!("{}", err)
+        }
+
+        rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+        rendust::clear();
+
+        triangle.render();
+
+        window.swap_buffers();
+    }
+}
@@ -0,0 +1,137 @@
+extern crate rendust;
+
+use std::env;
+use std::f32::consts::PI;
+use std::time::Instant;
+
+use rendust::Context;
+use rendust::math::{self, Vec3, Mat4};
+use rendust::window::Window;
+use rendust::mesh::{Vertex, PrimitiveType, Mesh};
+use rendust::camera::Camera;
+use rendust::image::HdrImage;
+use rendust::material::Material;
+use rendust::environment::Environment;
+use rendust::skybox::Skybox;
+use rendust::postprocess::{PostProcess, ToneMapping};
+
+// Build a UV sphere of radius 0.5
+fn sphere() -> Mesh {
+    let (rings, sectors) = (32, 64);
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for r in 0..rings + 1 {
+        let theta = r as f32 / rings as f32 * PI;
+
+        for s in 0..sectors + 1 {
+            let phi = s as f32 / sectors as f32 * PI * 2.0;
+            let (x, y, z) = (theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+
+            vertices.push(Vertex::new(x * 0.5, y * 0.5, z * 0.5)
+                .normal(x, y, z)
+                .texcoords(s as f32 / sectors as f32, 1.0 - r as f32 / rings as f32));
+        }
+    }
+
+    for r in 0..rings {
+        for s in 0..sectors {
+            let a = r * (sectors + 1) + s;
+            let b = a + sectors + 1;
+
+            indices.extend_from_slice(&[a, b, a + 1, a + 1, b, b + 1]);
+        }
+    }
+
+    Mesh::new(PrimitiveType::Triangles, &vertices, Some(&indices))
+}
+
+// Equirectangular sky used when no image is given: a blue
+// gradient above a brown ground, with a bright sun
+fn sky() -> HdrImage {
+    let (width, height) = (256, 128);
+    let mut data = Vec::with_capacity((width * height * 3) as usize);
+
+    for y in 0..height {
+        for x in 0..width {
+            let elevation = 0.5 - (y as f32 + 0.5) / height as f32;
+            let (dx, dy) = (x as f32 - 160.0, y as f32 - 32.0);
+
+            let color = if dx * dx + dy * dy < 9.0 {
+                [100.0, 90.0, 80.0]
+            }
+            else if elevation > 0.0 {
+                [0.3 + 0.4 * elevation, 0.5 + 0.5 * elevation, 1.0]
+            }
+            else {
+                [0.3, 0.2, 0.1]
+            };
+
+            data.extend_from_slice(&color);
+        }
+    }
+
+    HdrImage::new(width, height, data)
+}
+
+fn main() {
+    let mut window = Window::new("Rendust example - Image-based lighting", 1280, 720, true).unwrap();
+
+    let ctx = Context::new().unwrap();
+    let projection = math::mat4_perspective(60.0, 1280.0 / 720.0, 0.1, 1000.0);
+    ctx.set_projection(projection);
+
+    // Usage: ibl [environment.hdr]
+    let environment = match env::args().nth(1) {
+        Some(path) => Environment::load(path).unwrap(),
+        None => Environment::from_image(&sky()).unwrap()
+    };
+
+    ctx.set_environment(Some(&environment));
+
+    // The environment is also drawn behind the spheres
+    let skybox = Skybox::new(environment.cube_map().clone()).unwrap();
+
+    let post = PostProcess::hdr(1280, 720, ToneMapping::Aces, 1.0).unwrap();
+    post.tone_mapping().unwrap().set_gamma(2.2).unwrap();
+
+    let sphere = sphere();
+    let start = Instant::now();
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+
+        // Slowly orbit around the spheres
+        let elapsed = start.elapsed();
+        let angle = (elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 / 1_000_000_000.0) * 0.2;
+
+        let mut camera = Camera::new(Vec3::new(angle.sin() * 8.0, 0.0, angle.cos() * 8.0));
+        camera.target = Vec3::new(-angle.sin(), 0.0, -angle.cos());
+        ctx.set_camera(&camera);
+
+        post.render(|| {
+            rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+            rendust::clear();
+
+            // Metalness increases upwards, roughness to the right
+            for row in 0..5 {
+                for col in 0..7 {
+                    let metallic = row as f32 / 4.0;
+                    let roughness = col as f32 / 6.0;
+
+                    ctx.set_material(&Material::new()
+                        .base_color(0.9, 0.9, 0.9, 1.0)
+                        .metallic_roughness(metallic, roughness));
+
+                    ctx.set_model(Mat4::from_translation(Vec3::new((col as f32 - 3.0) * 1.2, (row as f32 - 2.0) * 1.2, 0.0)));
+                    sphere.render();
+                }
+            }
+
+            ctx.clear_material();
+            skybox.render(&camera, projection);
+        });
+
+        window.swap_buffers();
+    }
+}
@@ -0,0 +1,73 @@
+extern crate rendust;
+
+use rendust::Context;
+use rendust::math::{self, Vec3};
+use rendust::window::Window;
+use rendust::mesh::{Vertex, PrimitiveType, Mesh};
+use rendust::camera::Camera;
+use rendust::lighting::{Ambient, Directional, Point, Spot};
+
+// Build a unit cube with one normal per face
+fn cube() -> Mesh {
+    let faces = [
+        ([ 0.0,  0.0,  1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([ 0.0,  0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([ 1.0,  0.0,  0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0,  0.0,  0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([ 0.0,  1.0,  0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([ 0.0, -1.0,  0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ];
+
+    let mut vertices = Vec::new();
+
+    for &(n, u, v) in faces.iter() {
+        for &(a, b) in [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)].iter() {
+            vertices.push(Vertex::new(
+                n[0] * 0.5 + u[0] * a + v[0] * b,
+                n[1] * 0.5 + u[1] * a + v[1] * b,
+                n[2] * 0.5 + u[2] * a + v[2] * b
+            ).normal(n[0], n[1], n[2]).color(0.9, 0.6, 0.3, 1.0));
+        }
+    }
+
+    Mesh::new(PrimitiveType::Quads, &vertices, None)
+}
+
+fn main() {
+    let mut window = Window::new("Rendust example - Lighting", 1280, 720, true).unwrap();
+
+    let ctx = Context::new().unwrap();
+    ctx.set_projection(math::mat4_perspective(90.0, 1280.0 / 720.0, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.3, -1.0, -0.5), [1.0, 1.0, 0.9], 0.6).into(),
+        Point::new(Vec3::new(-1.5, 1.0, 1.0), [0.2, 0.4, 1.0], 1.0).into(),
+        Spot::new(Vec3::new(1.5, 2.0, 1.5), Vec3::new(-1.0, -1.5, -1.0), [1.0, 0.2, 0.2], 2.0).cone(15.0, 25.0).into(),
+    ]);
+
+    let camera = Camera::new(Vec3::new(1.0, 1.0, 3.0));
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, -0.5,  25.0).color(0.4, 0.4, 0.4, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-25.0, -0.5, -25.0).color(0.4, 0.4, 0.4, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, -0.5, -25.0).color(0.4, 0.4, 0.4, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, -0.5,  25.0).color(0.4, 0.4, 0.4, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    let cube = cube();
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+
+        rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+        rendust::clear();
+
+        ctx.set_camera(&camera);
+
+        floor.render();
+        cube.render();
+
+        window.swap_buffers();
+    }
+}
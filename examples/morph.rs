@@ -0,0 +1,58 @@
+extern crate rendust;
+
+use std::env;
+
+use rendust::Context;
+use rendust::math::{self, Vec3};
+use rendust::window::Window;
+use rendust::camera::Camera;
+use rendust::lighting::{Ambient, Directional, Shadow};
+use rendust::gltf::{Gltf, GltfModel};
+use rendust::postprocess::{PostProcess, ToneMapping};
+
+fn main() {
+    let mut window = Window::new("Rendust example - Morph targets", 1280, 720, true).unwrap();
+
+    let ctx = Context::new().unwrap();
+    ctx.set_projection(math::mat4_perspective(60.0, 1280.0 / 720.0, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 1.5, 2.5)));
+
+    // Morphed meshes cast morphed shadows too
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.4, -1.0, -0.6), [1.0, 1.0, 1.0], 3.0)
+            .shadow(Shadow::new().distance(10.0).resolution(2048))
+            .into()
+    ]);
+
+    // Usage: morph [model.gltf | model.glb]
+    let path = env::args().nth(1).unwrap_or("examples/models/cloth.gltf".to_string());
+    let gltf = Gltf::load(path).unwrap();
+
+    let mut model = GltfModel::new(&gltf).unwrap();
+    let mut time = 0.0f32;
+
+    let post = PostProcess::hdr(1280, 720, ToneMapping::Aces, 1.0).unwrap();
+    post.tone_mapping().unwrap().set_gamma(2.2).unwrap();
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+        time += 1.0 / 60.0;
+
+        // Loop the first animation, which blends the targets
+        if let Some(animation) = gltf.animations.first() {
+            animation.apply(time % animation.duration().max(1e-3), &mut model.nodes);
+        }
+
+        ctx.render_shadows(|ctx| model.render(ctx, math::mat4_identity()));
+
+        post.render(|| {
+            rendust::set_clear_color(0.1, 0.1, 0.12, 1.0);
+            rendust::clear();
+
+            model.render(&ctx, math::mat4_identity());
+        });
+
+        window.swap_buffers();
+    }
+}
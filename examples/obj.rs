@@ -0,0 +1,73 @@
+extern crate rendust;
+extern crate imagefmt;
+
+use std::env;
+use std::rc::Rc;
+
+use rendust::Context;
+use rendust::math::{self, Vec3, Mat4};
+use rendust::window::Window;
+use rendust::mesh::{Texture, TextureOptions, Filter};
+use rendust::camera::Camera;
+use rendust::lighting::{Ambient, Directional};
+use rendust::obj::Obj;
+use rendust::postprocess::{PostProcess, ToneMapping};
+
+fn main() {
+    let mut window = Window::new("Rendust example - OBJ model", 1280, 720, true).unwrap();
+
+    let ctx = Context::new().unwrap();
+    ctx.set_projection(math::mat4_perspective(60.0, 1280.0 / 720.0, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 0.5, 4.0)));
+    ctx.set_lights(&[Directional::new(Vec3::new(-0.3, -0.5, -1.0), [1.0, 1.0, 1.0], 3.0).into()]);
+
+    // Usage: obj [model.obj]
+    let path = env::args().nth(1).unwrap_or("examples/models/shapes.obj".to_string());
+    let model = Obj::load(path).unwrap();
+
+    // Upload the meshes, along with their materials
+    let parts: Vec<_> = model.meshes.iter().map(|mesh| {
+        let material = mesh.material.as_ref()
+            .and_then(|name| model.material(name))
+            .map(|material| material.to_material(|path| {
+                let options = TextureOptions::new().filter(Filter::Trilinear);
+
+                imagefmt::read(path, imagefmt::ColFmt::RGBA).ok().map(|image| {
+                    Rc::new(Texture::with_options(image.w as u32, image.h as u32, &image.buf, options))
+                })
+            }));
+
+        (mesh.to_mesh(), material)
+    }).collect();
+
+    let post = PostProcess::hdr(1280, 720, ToneMapping::Aces, 1.0).unwrap();
+    post.tone_mapping().unwrap().set_gamma(2.2).unwrap();
+
+    let mut angle = 0.0f32;
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+        angle += 0.5;
+
+        post.render(|| {
+            rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+            rendust::clear();
+
+            ctx.set_model(Mat4::from_angle_y(math::Deg(angle)));
+
+            for (mesh, material) in parts.iter() {
+                match *material {
+                    Some(ref material) => ctx.set_material(material),
+                    None => ctx.clear_material()
+                }
+
+                mesh.render();
+            }
+
+            ctx.clear_material();
+        });
+
+        window.swap_buffers();
+    }
+}
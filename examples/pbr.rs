@@ -0,0 +1,58 @@
+extern crate rendust;
+
+use rendust::Context;
+use rendust::math::{self, Vec3, Mat4};
+use rendust::window::Window;
+use rendust::mesh::shapes;
+use rendust::camera::Camera;
+use rendust::lighting::{Ambient, Directional, Point};
+use rendust::material::Material;
+use rendust::postprocess::{PostProcess, ToneMapping};
+
+fn main() {
+    let mut window = Window::new("Rendust example - PBR", 1280, 720, true).unwrap();
+
+    let ctx = Context::new().unwrap();
+    ctx.set_projection(math::mat4_perspective(60.0, 1280.0 / 720.0, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.03));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 0.0, 8.0)));
+
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.3, -0.5, -1.0), [1.0, 1.0, 1.0], 2.0).into(),
+        Point::new(Vec3::new(3.0, 3.0, 3.0), [1.0, 0.8, 0.6], 30.0).into(),
+    ]);
+
+    // The colors are linear, the tone mapping converts them for display
+    let post = PostProcess::hdr(1280, 720, ToneMapping::Aces, 1.0).unwrap();
+    post.tone_mapping().unwrap().set_gamma(2.2).unwrap();
+
+    let sphere = shapes::uv_sphere(0.5, 64, 32).to_mesh();
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+
+        post.render(|| {
+            rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+            rendust::clear();
+
+            // Metalness increases upwards, roughness to the right
+            for row in 0..5 {
+                for col in 0..7 {
+                    let metallic = row as f32 / 4.0;
+                    let roughness = col as f32 / 6.0;
+
+                    ctx.set_material(&Material::new()
+                        .base_color(0.8, 0.1, 0.1, 1.0)
+                        .metallic_roughness(metallic, roughness));
+
+                    ctx.set_model(Mat4::from_translation(Vec3::new((col as f32 - 3.0) * 1.2, (row as f32 - 2.0) * 1.2, 0.0)));
+                    sphere.render();
+                }
+            }
+
+            ctx.clear_material();
+        });
+
+        window.swap_buffers();
+    }
+}
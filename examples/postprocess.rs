@@ -0,0 +1,70 @@
+extern crate rendust;
+
+use rendust::Context;
+use rendust::math::{self, Vec3, Mat4};
+use rendust::window::Window;
+use rendust::mesh::{Vertex, PrimitiveType, Mesh};
+use rendust::camera::Camera;
+use rendust::lighting::{Ambient, Directional, Point};
+use rendust::postprocess::{PostProcess, Effect, ToneMapping};
+
+fn main() {
+    let mut window = Window::new("Rendust example - Post-processing", 1280, 720, true).unwrap();
+
+    let ctx = Context::new().unwrap();
+    ctx.set_projection(math::mat4_perspective(90.0, 1280.0 / 720.0, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_camera(&Camera::new(Vec3::new(1.0, 2.0, 4.0)));
+
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.5, -1.0, -0.3), [1.0, 1.0, 0.9], 0.5).into(),
+        Point::new(Vec3::new(0.0, 1.5, 1.0), [1.0, 0.6, 0.2], 3.0).into(),
+    ]);
+
+    // Effects are applied in order
+    let mut post = PostProcess::new(1280, 720).unwrap();
+    post.push(Effect::bloom(0.8, 0.6).unwrap());
+    post.push(Effect::tone_mapping(ToneMapping::Aces, 1.2).unwrap());
+    post.push(Effect::vignette(0.6, 0.9).unwrap());
+    post.push(Effect::fxaa().unwrap());
+
+    // Custom effects are fragment shaders using the documented interface
+    post.push(Effect::new("
+        void main() {
+            vec4 color = texture(source, frag_texcoords);
+            float grain = fract(sin(dot(frag_texcoords * resolution + time, vec2(12.9898, 78.233))) * 43758.5453);
+
+            out_color = vec4(color.rgb + (grain - 0.5) * 0.03, color.a);
+        }
+    ").unwrap());
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    let triangle = Mesh::new(PrimitiveType::Triangles, &[
+        Vertex::new(-1.0, 0.0, 0.0).color(1.0, 0.2, 0.2, 1.0).normal(0.0, 0.0, 1.0),
+        Vertex::new( 0.0, 2.0, 0.0).color(0.2, 1.0, 0.2, 1.0).normal(0.0, 0.0, 1.0),
+        Vertex::new( 1.0, 0.0, 0.0).color(0.2, 0.2, 1.0, 1.0).normal(0.0, 0.0, 1.0),
+    ], None);
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+
+        post.render(|| {
+            rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+            rendust::clear();
+
+            ctx.set_model(math::mat4_identity());
+            floor.render();
+
+            ctx.set_model(Mat4::from_translation(Vec3::new(0.0, 0.0, -1.0)));
+            triangle.render();
+        });
+
+        window.swap_buffers();
+    }
+}
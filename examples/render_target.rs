@@ -0,0 +1,52 @@
+extern crate rendust;
+
+use rendust::Context;
+use rendust::window::Window;
+use rendust::mesh::{Vertex, PrimitiveType, Mesh};
+use rendust::target::{RenderTarget, DepthBuffer};
+
+fn main() {
+    let mut window = Window::new("Rendust example - Render target", 1280, 720, true).unwrap();
+    let _ = Context::new().unwrap();
+
+    // Offscreen target with a single color attachment
+    let target = RenderTarget::new(256, 256, 1, DepthBuffer::Depth).unwrap();
+
+    let triangle = Mesh::new(PrimitiveType::Triangles, &[
+        Vertex::new(-1.0, -1.0, 0.0).color(1.0, 0.0, 0.0, 1.0),
+        Vertex::new( 0.0,  1.0, 0.0).color(0.0, 1.0, 0.0, 1.0),
+        Vertex::new( 1.0, -1.0, 0.0).color(0.0, 0.0, 1.0, 1.0),
+    ], None);
+
+    // Quad displaying the content of the target
+    let quad = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-0.5, -0.5, 0.0).texcoords(0.0, 0.0),
+        Vertex::new( 0.5, -0.5, 0.0).texcoords(1.0, 0.0),
+        Vertex::new( 0.5,  0.5, 0.0).texcoords(1.0, 1.0),
+        Vertex::new(-0.5,  0.5, 0.0).texcoords(0.0, 1.0),
+    ], None);
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+
+        // Render the triangle into the target
+        target.render(|| {
+            rendust::set_clear_color(0.2, 0.2, 0.2, 1.0);
+            rendust::clear();
+
+            triangle.render();
+        });
+
+        rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+        rendust::clear();
+
+        // Then sample it on screen
+        let texture = target.color(0);
+
+        texture.bind();
+        quad.render();
+        texture.unbind();
+
+        window.swap_buffers();
+    }
+}
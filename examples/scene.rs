@@ -1,9 +1,10 @@
 extern crate rendust;
+extern crate glutin;
 
 use rendust::Context;
 use rendust::math::{self, Vec3};
 use rendust::window::Window;
-use rendust::mesh::{Vertex, PrimitiveType, Mesh};
+use rendust::mesh::shapes;
 use rendust::camera::Camera;
 use rendust::lighting::Ambient;
 
@@ -16,36 +17,21 @@ fn main() {
 
     let camera = Camera::new(Vec3::new(1.0, 1.0, 3.0));
 
-    let floor = Mesh::new(PrimitiveType::Quads, &[
-        Vertex::new(-25.0, 0.0,  25.0).color(0.4, 0.4, 0.4, 1.0),
-        Vertex::new(-25.0, 0.0, -25.0).color(0.4, 0.4, 0.4, 1.0),
-        Vertex::new( 25.0, 0.0, -25.0).color(0.4, 0.4, 0.4, 1.0),
-        Vertex::new( 25.0, 0.0,  25.0).color(0.4, 0.4, 0.4, 1.0),
-    ], None);
-
-    let cube = Mesh::new(PrimitiveType::Quads, &[
-        Vertex::new(-0.5, -0.5, 0.5).color(1.0, 1.0, 1.0, 1.0),
-        Vertex::new(-0.5,  0.5, 0.5).color(1.0, 1.0, 1.0, 1.0),
-        Vertex::new( 0.5,  0.5, 0.5).color(1.0, 1.0, 1.0, 1.0),
-        Vertex::new( 0.5, -0.5, 0.5).color(1.0, 1.0, 1.0, 1.0),
-
-        Vertex::new(-0.5, -0.5, -0.5).color(1.0, 1.0, 1.0, 1.0),
-        Vertex::new(-0.5,  0.5, -0.5).color(1.0, 1.0, 1.0, 1.0),
-        Vertex::new( 0.5,  0.5, -0.5).color(1.0, 1.0, 1.0, 1.0),
-        Vertex::new( 0.5, -0.5, -0.5).color(1.0, 1.0, 1.0, 1.0),
-    ], Some(&[
-        0, 1, 2, 3,
-        4, 5, 6, 7,
-
-        0, 1, 5, 4,
-        3, 2, 6, 7,
-
-        0, 4, 7, 3,
-        1, 5, 6, 2
-    ]));
+    let floor = shapes::plane(50.0, 50.0, 1, 1).color(0.4, 0.4, 0.4, 1.0).to_mesh();
+    let cube = shapes::cuboid(1.0, 1.0, 1.0, 1).to_mesh();
 
     while !window.should_exit {
-        window.handle_events(|_| ());
+        let mut screenshot = false;
+
+        // Press F12 to save a screenshot
+        window.handle_events(|evt| match evt {
+            glutin::WindowEvent::KeyboardInput { input, .. } => {
+                if let (glutin::ElementState::Pressed, Some(glutin::VirtualKeyCode::F12)) = (input.state, input.virtual_keycode) {
+                    screenshot = true;
+                }
+            },
+            _ => ()
+        });
 
         rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
         rendust::clear();
@@ -55,6 +41,10 @@ fn main() {
         floor.render();
         cube.render();
 
+        if screenshot {
+            window.capture_frame().save("screenshot.png").unwrap();
+        }
+
         window.swap_buffers();
     }
 }
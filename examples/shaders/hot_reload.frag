@@ -0,0 +1,10 @@
+#version 140
+
+in vec4 frag_color;
+
+out vec4 out_color;
+
+// Edit this file while the example is running
+void main() {
+    out_color = frag_color;
+}
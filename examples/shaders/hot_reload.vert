@@ -0,0 +1,11 @@
+#version 140
+
+in vec3 position;
+in vec4 color;
+
+out vec4 frag_color;
+
+void main() {
+    gl_Position = vec4(position, 1.0);
+    frag_color = color;
+}
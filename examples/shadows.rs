@@ -0,0 +1,103 @@
+extern crate rendust;
+
+use rendust::Context;
+use rendust::math::{self, Vec3, Mat4};
+use rendust::window::Window;
+use rendust::mesh::{Vertex, PrimitiveType, Mesh};
+use rendust::camera::Camera;
+use rendust::lighting::{Ambient, Directional, Point, Spot, Shadow};
+
+// Build a unit cube with one normal per face
+fn cube() -> Mesh {
+    let faces = [
+        ([ 0.0,  0.0,  1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([ 0.0,  0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([ 1.0,  0.0,  0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0,  0.0,  0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([ 0.0,  1.0,  0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([ 0.0, -1.0,  0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ];
+
+    let mut vertices = Vec::new();
+
+    for &(n, u, v) in faces.iter() {
+        for &(a, b) in [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)].iter() {
+            vertices.push(Vertex::new(
+                n[0] * 0.5 + u[0] * a + v[0] * b,
+                n[1] * 0.5 + u[1] * a + v[1] * b,
+                n[2] * 0.5 + u[2] * a + v[2] * b
+            ).normal(n[0], n[1], n[2]));
+        }
+    }
+
+    Mesh::new(PrimitiveType::Quads, &vertices, None)
+}
+
+fn main() {
+    let mut window = Window::new("Rendust example - Shadows", 1280, 720, true).unwrap();
+
+    let ctx = Context::new().unwrap();
+    ctx.set_projection(math::mat4_perspective(90.0, 1280.0 / 720.0, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.2));
+
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.5, -1.0, -0.3), [1.0, 1.0, 0.9], 0.6)
+            .shadow(Shadow::new().distance(50.0).resolution(2048).cascades(3))
+            .into(),
+        Spot::new(Vec3::new(2.0, 3.0, 2.0), Vec3::new(-1.0, -1.5, -1.0), [0.3, 0.5, 1.0], 2.0)
+            .cone(20.0, 30.0)
+            .shadow(Shadow::new().distance(20.0).bias(0.0005))
+            .into(),
+        Point::new(Vec3::new(-3.0, 2.0, 1.0), [1.0, 0.5, 0.2], 1.5)
+            .shadow(Shadow::new().distance(20.0).resolution(512).bias(0.002))
+            .into(),
+    ]);
+
+    let camera = Camera::new(Vec3::new(1.0, 2.0, 4.0));
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    let cube = cube();
+
+    // Render the objects of the scene, both for
+    // the shadow maps and for the screen
+    let render = |ctx: &Context| {
+        ctx.set_model(math::mat4_identity());
+        floor.render();
+
+        ctx.set_model(Mat4::from_translation(Vec3::new(0.0, 0.5, 0.0)));
+        cube.render();
+
+        ctx.set_model(Mat4::from_translation(Vec3::new(-1.5, 1.5, -1.0)) * Mat4::from_scale(0.5));
+        cube.render();
+
+        // Cubes further away, in the farther cascades
+        for i in 0..8 {
+            let x = (i % 2) as f32 * 8.0 - 4.0;
+            let z = -(i / 2) as f32 * 6.0 - 4.0;
+
+            ctx.set_model(Mat4::from_translation(Vec3::new(x, 1.0, z)) * Mat4::from_scale(2.0));
+            cube.render();
+        }
+    };
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+
+        // The cascades follow the camera, set it first
+        ctx.set_camera(&camera);
+        ctx.render_shadows(&render);
+
+        rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+        rendust::clear();
+
+        render(&ctx);
+
+        window.swap_buffers();
+    }
+}
@@ -0,0 +1,81 @@
+extern crate rendust;
+
+use rendust::Context;
+use rendust::math::{self, Vec3, Mat4};
+use rendust::window::Window;
+use rendust::mesh::shapes;
+use rendust::camera::Camera;
+use rendust::lighting::{Ambient, Directional, Shadow};
+use rendust::material::Material;
+use rendust::postprocess::{PostProcess, ToneMapping};
+
+fn main() {
+    let mut window = Window::new("Rendust example - Shapes", 1280, 720, true).unwrap();
+
+    let ctx = Context::new().unwrap();
+    ctx.set_projection(math::mat4_perspective(60.0, 1280.0 / 720.0, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 3.0, 6.0)));
+
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.4, -1.0, -0.6), [1.0, 1.0, 1.0], 3.0)
+            .shadow(Shadow::new().distance(20.0).resolution(2048))
+            .into()
+    ]);
+
+    // Rolling hills under the shapes
+    let ground = shapes::grid(12.0, 12.0, 64, 64, |x, z| 0.15 * (x * 1.3).sin() * (z * 1.1).cos() - 0.6).to_mesh();
+
+    // Every kind of shape, in a grid
+    let meshes = [
+        shapes::plane(1.0, 1.0, 4, 4),
+        shapes::cuboid(0.9, 0.9, 0.9, 1),
+        shapes::uv_sphere(0.5, 32, 16),
+        shapes::icosphere(0.5, 2),
+        shapes::cylinder(0.4, 1.0, 32, 1),
+        shapes::cone(0.45, 1.0, 32, 1),
+        shapes::capsule(0.3, 0.5, 32, 8),
+        shapes::torus(0.35, 0.12, 48, 16)
+    ].iter().map(|shape| shape.to_mesh()).collect::<Vec<_>>();
+
+    let material = Material::new().base_color(0.8, 0.5, 0.3, 1.0).metallic_roughness(0.0, 0.5);
+    let ground_material = Material::new().base_color(0.3, 0.5, 0.3, 1.0).metallic_roughness(0.0, 0.9);
+
+    let post = PostProcess::hdr(1280, 720, ToneMapping::Aces, 1.0).unwrap();
+    post.tone_mapping().unwrap().set_gamma(2.2).unwrap();
+
+    let mut time = 0.0f32;
+
+    while !window.should_exit {
+        window.handle_events(|_| ());
+        time += 1.0 / 60.0;
+
+        let render = |ctx: &Context| {
+            ctx.set_material(&ground_material);
+            ctx.set_model(math::mat4_identity());
+            ground.render();
+
+            ctx.set_material(&material);
+
+            for (i, mesh) in meshes.iter().enumerate() {
+                let position = Vec3::new((i % 4) as f32 * 1.5 - 2.25, 0.2, (i / 4) as f32 * 1.5 - 1.0);
+
+                ctx.set_model(Mat4::from_translation(position) * Mat4::from_angle_y(math::Rad(time * 0.5)));
+                mesh.render();
+            }
+
+            ctx.clear_material();
+        };
+
+        ctx.render_shadows(&render);
+
+        post.render(|| {
+            rendust::set_clear_color(0.1, 0.1, 0.12, 1.0);
+            rendust::clear();
+
+            render(&ctx);
+        });
+
+        window.swap_buffers();
+    }
+}
@@ -3,14 +3,17 @@ extern crate imagefmt;
 
 use rendust::Context;
 use rendust::window::Window;
-use rendust::mesh::{Vertex, Texture, PrimitiveType, Mesh};
+use rendust::mesh::{Vertex, Texture, TextureOptions, Filter, PrimitiveType, Mesh};
 
 fn main() {
     let mut window = Window::new("Rendust example - Texture", 1280, 720, true).unwrap();
     let _ = Context::new().unwrap();
 
     let image = imagefmt::read("examples/image.png", imagefmt::ColFmt::RGBA).unwrap();
-    let texture = Texture::new(image.w as u32, image.h as u32, image.buf.as_ref());
+
+    // Smooth minification, also at grazing angles
+    let options = TextureOptions::new().filter(Filter::Trilinear).anisotropy(16.0);
+    let texture = Texture::with_options(image.w as u32, image.h as u32, image.buf.as_ref(), options);
 
     let triangle = Mesh::new(PrimitiveType::Triangles, &[
         Vertex::new(-1.0, -1.0, 0.0).texcoords(0.0, 0.0),
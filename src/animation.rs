@@ -0,0 +1,233 @@
+use cgmath::{InnerSpace, SquareMatrix};
+
+use math::{self, Vec3, Mat4, Quat};
+use gltf::{GltfNode, Transform};
+
+/// Largest number of joints deforming a mesh in the built-in shaders.
+/// Their matrices fit in the 1024 uniform components guaranteed to the
+/// vertex shader, along with the other matrices
+pub const MAX_JOINTS: usize = 60;
+
+/// A skin: the joints of a skeleton deforming a mesh, each being a node.
+/// The built-in shaders render skins of at most `MAX_JOINTS` joints
+#[derive(Debug, Clone)]
+pub struct Skin {
+    pub name: Option<String>,
+    pub joints: Vec<usize>,
+
+    /// Matrices moving the vertices from the space of the mesh to the
+    /// space of each joint, in the bind pose of the skeleton
+    pub inverse_bind_matrices: Vec<Mat4>,
+
+    /// Root of the skeleton, if specified
+    pub skeleton: Option<usize>
+}
+
+impl Skin {
+    /// Compute the matrices of the joints, which move the vertices of
+    /// the mesh from its bind pose to the current pose of the skeleton.
+    /// The transforms of the nodes are relative to the scene, as
+    /// computed by `GltfModel::world_transforms`, and `mesh` is the
+    /// transform of the node holding the mesh
+    pub fn joint_matrices(&self, transforms: &[Option<Mat4>], mesh: Mat4) -> Vec<Mat4> {
+        let inverse_mesh = mesh.invert().unwrap_or(math::mat4_identity());
+
+        self.joints.iter().zip(self.inverse_bind_matrices.iter()).map(|(&joint, inverse_bind)| {
+            // Joints outside of the scene stay in their bind pose
+            match transforms.get(joint).and_then(|transform| *transform) {
+                Some(transform) => inverse_mesh * transform * inverse_bind,
+                None => math::mat4_identity()
+            }
+        }).collect()
+    }
+}
+
+/// How values are interpolated between keyframes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    /// The value of the previous keyframe is kept
+    Step,
+
+    /// Linear interpolation, spherical for rotations
+    Linear,
+
+    /// Cubic Hermite spline, whose tangents are stored along with
+    /// each value: in-tangent, value, then out-tangent
+    CubicSpline
+}
+
+/// Property of a node changed by an animation channel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Property {
+    Translation,
+
+    /// Rotation quaternion, stored as (x, y, z, w)
+    Rotation,
+
+    Scale,
+
+    /// Weights of the morph targets of the mesh of the node
+    Weights
+}
+
+/// Keyframes of a property of a node
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub node: usize,
+    pub property: Property,
+    pub interpolation: Interpolation,
+
+    /// Times of the keyframes in seconds, increasing
+    pub times: Vec<f32>,
+
+    /// Components of the values of the keyframes, one after the other,
+    /// with three values per keyframe for cubic splines. Translations and
+    /// scales have three components, rotations four, and weights one
+    /// per morph target
+    pub values: Vec<f32>
+}
+
+impl Channel {
+    /// Evaluate the property at the specified time in seconds. Outside
+    /// of the keyframes, the value of the closest one is used
+    pub fn sample(&self, time: f32) -> Vec<f32> {
+        let last = match self.times.len() {
+            0 => return Vec::new(),
+            count => count - 1
+        };
+
+        // Find the keyframes around the time
+        let next = self.times.iter().position(|&t| t > time).unwrap_or(last + 1);
+
+        if next == 0 {
+            return self.value(0).to_vec();
+        }
+
+        if next > last {
+            return self.value(last).to_vec();
+        }
+
+        let previous = next - 1;
+        let delta = self.times[next] - self.times[previous];
+        let t = if delta > 0.0 { (time - self.times[previous]) / delta } else { 0.0 };
+
+        match self.interpolation {
+            Interpolation::Step => self.value(previous).to_vec(),
+            Interpolation::Linear if self.property == Property::Rotation => {
+                let (a, b) = (to_quat(self.value(previous)), to_quat(self.value(next)));
+
+                // Go the shortest way around
+                let b = if a.dot(b) < 0.0 { -b } else { b };
+
+                from_quat(a.slerp(b, t).normalize())
+            },
+            Interpolation::Linear => {
+                self.value(previous).iter().zip(self.value(next).iter())
+                    .map(|(&a, &b)| a * (1.0 - t) + b * t)
+                    .collect()
+            },
+            Interpolation::CubicSpline => {
+                let (t2, t3) = (t * t, t * t * t);
+                let n = self.components();
+
+                let element = |i: usize| &self.values[i * n..(i + 1) * n];
+                let (v0, b0) = (element(previous * 3 + 1), element(previous * 3 + 2));
+                let (a1, v1) = (element(next * 3), element(next * 3 + 1));
+
+                let value: Vec<f32> = (0..n).map(|c| {
+                    v0[c] * (2.0 * t3 - 3.0 * t2 + 1.0)
+                        + b0[c] * (delta * (t3 - 2.0 * t2 + t))
+                        + v1[c] * (-2.0 * t3 + 3.0 * t2)
+                        + a1[c] * (delta * (t3 - t2))
+                }).collect();
+
+                if self.property == Property::Rotation { from_quat(to_quat(&value).normalize()) } else { value }
+            }
+        }
+    }
+
+    // Get the number of components of each value
+    fn components(&self) -> usize {
+        match self.property {
+            Property::Translation | Property::Scale => 3,
+            Property::Rotation => 4,
+            Property::Weights => {
+                let per_keyframe = if self.interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+                self.values.len() / (self.times.len() * per_keyframe).max(1)
+            }
+        }
+    }
+
+    // Get the value of a keyframe, skipping the tangents of splines
+    fn value(&self, keyframe: usize) -> &[f32] {
+        let n = self.components();
+        let index = match self.interpolation {
+            Interpolation::CubicSpline => keyframe * 3 + 1,
+            _ => keyframe
+        };
+
+        &self.values[index * n..(index + 1) * n]
+    }
+}
+
+/// An animation clip, made of channels animating the nodes of a scene
+#[derive(Debug, Clone)]
+pub struct Animation {
+    pub name: Option<String>,
+    pub channels: Vec<Channel>
+}
+
+impl Animation {
+    /// Get the duration of the animation in seconds, up to its last keyframe
+    pub fn duration(&self) -> f32 {
+        self.channels.iter()
+            .filter_map(|channel| channel.times.last())
+            .fold(0.0, |duration, &time| duration.max(time))
+    }
+
+    /// Pose the nodes as they are at the specified time in seconds, and
+    /// set the weights of their morph targets. The nodes whose transform
+    /// is animated get a decomposed one, the ones given by a matrix
+    /// being reset first
+    pub fn apply(&self, time: f32, nodes: &mut [GltfNode]) {
+        for channel in self.channels.iter() {
+            let node = match nodes.get_mut(channel.node) {
+                Some(node) => node,
+                None => continue
+            };
+
+            let value = channel.sample(time);
+
+            if channel.property == Property::Weights {
+                node.weights = value;
+                continue;
+            }
+
+            if let Transform::Matrix(_) = node.transform {
+                node.transform = Transform::Decomposed {
+                    translation: Vec3::new(0.0, 0.0, 0.0),
+                    rotation: Quat::new(1.0, 0.0, 0.0, 0.0),
+                    scale: Vec3::new(1.0, 1.0, 1.0)
+                };
+            }
+
+            if let Transform::Decomposed { ref mut translation, ref mut rotation, ref mut scale } = node.transform {
+                match channel.property {
+                    Property::Translation => *translation = Vec3::new(value[0], value[1], value[2]),
+                    Property::Rotation => *rotation = to_quat(&value),
+                    Property::Scale => *scale = Vec3::new(value[0], value[1], value[2]),
+                    Property::Weights => ()
+                }
+            }
+        }
+    }
+}
+
+// Quaternions are stored as (x, y, z, w)
+fn to_quat(v: &[f32]) -> Quat {
+    Quat::new(v[3], v[0], v[1], v[2])
+}
+
+fn from_quat(q: Quat) -> Vec<f32> {
+    vec![q.v.x, q.v.y, q.v.z, q.s]
+}
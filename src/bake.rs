@@ -0,0 +1,140 @@
+use gl;
+use gl::types::*;
+
+use super::{Error, Result};
+use math::{Mat3, Vec3};
+use mesh::{self, CubeMap};
+use shaders::Program;
+use target::SavedTarget;
+use resources::{self, ContextId, Resource};
+
+// Renders fullscreen passes into the faces of cube maps and into
+// textures, restoring the previous state of the pipeline once dropped
+pub struct Baker {
+    fbo: GLuint,
+    vao: GLuint,
+
+    saved: SavedTarget,
+    program: GLint,
+    depth_test: bool,
+    blend: bool,
+
+    owner: ContextId
+}
+
+impl Baker {
+    pub fn new() -> Baker {
+        let mut baker = Baker {
+            fbo: 0,
+            vao: 0,
+
+            saved: SavedTarget::save(),
+            program: 0,
+            depth_test: false,
+            blend: false,
+
+            owner: resources::current_context()
+        };
+
+        unsafe {
+            gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut baker.program);
+            baker.depth_test = gl::IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
+            baker.blend = gl::IsEnabled(gl::BLEND) == gl::TRUE;
+
+            gl::GenFramebuffers(1, &mut baker.fbo);
+            gl::GenVertexArrays(1, &mut baker.vao);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::BLEND);
+        }
+
+        baker
+    }
+
+    // Render the bound program into every face of a cube map level
+    pub fn draw_faces(&self, program: &Program, cube_map: &CubeMap, level: u32) -> Result<()> {
+        for (i, face) in face_bases().iter().enumerate() {
+            let _ = program.set_uniform("face", face);
+            try!(self.draw(gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as GLenum, cube_map.id(), level, cube_map.size));
+        }
+
+        Ok(())
+    }
+
+    // Render the bound program into a level of a texture
+    // or cube map face, whose first level has the specified size
+    pub fn draw(&self, target: GLenum, texture: GLuint, level: u32, size: u32) -> Result<()> {
+        let size = (size >> level).max(1);
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, target, texture, level as GLint);
+            gl::DrawBuffer(gl::COLOR_ATTACHMENT0);
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(Error::Framebuffer(status));
+            }
+
+            gl::Viewport(0, 0, size as GLsizei, size as GLsizei);
+
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Baker {
+    fn drop(&mut self) {
+        self.saved.restore();
+
+        unsafe {
+            gl::UseProgram(self.program as GLuint);
+
+            if self.depth_test {
+                gl::Enable(gl::DEPTH_TEST);
+            }
+
+            if self.blend {
+                gl::Enable(gl::BLEND);
+            }
+
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+
+        mesh::BLANK_TEXTURE.bind();
+
+        resources::release(self.owner, Resource::Framebuffer(self.fbo));
+        resources::release(self.owner, Resource::VertexArray(self.vao));
+    }
+}
+
+// Bases of the faces of a cube map, in the order of the GL faces. They map
+// the viewport coordinates to the direction sampling the texel rendered
+// there, following the orientation of the faces defined by OpenGL
+fn face_bases() -> [Mat3; 6] {
+    let x = Vec3::new(1.0, 0.0, 0.0);
+    let y = Vec3::new(0.0, 1.0, 0.0);
+    let z = Vec3::new(0.0, 0.0, 1.0);
+
+    [
+        Mat3::from_cols(-z, -y, x),
+        Mat3::from_cols(z, -y, -x),
+        Mat3::from_cols(x, z, y),
+        Mat3::from_cols(x, -z, -y),
+        Mat3::from_cols(x, -y, z),
+        Mat3::from_cols(-x, -y, -z)
+    ]
+}
+
+// Build a program rendering into the faces of a cube map
+pub fn face_program(source: &str) -> Result<Program> {
+    let vert = format!("#version 140\n{}", include_str!("glsl/ibl/face.vert"));
+    let frag = format!("#version 140\n{}\n{}", include_str!("glsl/ibl/common.glsl"), source);
+
+    Program::new(&vert, &frag)
+}
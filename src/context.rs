@@ -1,100 +1,679 @@
-use super::Result;
+use std::f32;
+use std::io;
+use std::cell::{Cell, RefCell};
+
+use gl;
+use gl::types::*;
+
+use super::{Result, Error};
 use shaders::Program;
+use uniforms::Uniform;
 use math::{self, Mat4};
-use lighting::Ambient;
+use camera::Camera;
+use mesh::{self, Attribute, SkinnedVertex, VertexLayout};
+use material::{Material, AlphaMode};
+use environment::Environment;
+use lighting::{self, Ambient, Light, Directional, Point, Shadow};
+use animation;
+use shadows::{ShadowMapArray, CubeShadowMap};
+use target::SavedTarget;
+
+// Texture unit of the shadow maps, which are the layers of one texture
+const SHADOW_MAP_UNIT: u32 = 1;
+
+// Texture unit of the first cube shadow map of point lights
+const POINT_SHADOW_MAP_UNIT: u32 = SHADOW_MAP_UNIT + 1;
+
+// Texture units of the material textures, other than the base color
+// which uses the first unit like the textures of the default shading
+const METALLIC_ROUGHNESS_UNIT: u32 = POINT_SHADOW_MAP_UNIT + lighting::MAX_POINT_SHADOWS as u32;
+const NORMAL_UNIT: u32 = METALLIC_ROUGHNESS_UNIT + 1;
+const OCCLUSION_UNIT: u32 = METALLIC_ROUGHNESS_UNIT + 2;
+const EMISSIVE_UNIT: u32 = METALLIC_ROUGHNESS_UNIT + 3;
+
+// Texture units of the image-based lighting maps
+const IRRADIANCE_UNIT: u32 = EMISSIVE_UNIT + 1;
+const PREFILTERED_UNIT: u32 = EMISSIVE_UNIT + 2;
+const BRDF_LUT_UNIT: u32 = EMISSIVE_UNIT + 3;
 
 /// Contains the main state
 /// of the rendering library
 pub struct Context {
-    shader_program: Program
+    shader_program: Program,
+    pbr_program: Program,
+    depth_program: Program,
+    point_depth_program: Program,
+
+    // Current matrices of the camera, to fit cascaded shadows to its view
+    projection: Cell<Mat4>,
+    view: Cell<Mat4>,
+
+    // Which pass is being rendered
+    pass: Cell<Pass>,
+
+    // Lights casting shadows, and their shadow maps
+    shadow_casters: RefCell<Vec<ShadowCaster>>,
+    shadow_maps: RefCell<Option<ShadowMapArray>>,
+
+    // Point lights casting shadows, and their cube shadow maps
+    point_casters: RefCell<Vec<(Point, Shadow)>>,
+    cube_shadow_maps: RefCell<Vec<CubeShadowMap>>
+}
+
+// Kinds of rendering passes, each using its own program
+#[derive(Clone, Copy, PartialEq)]
+enum Pass {
+    Main,
+    Shadow,
+    PointShadow
+}
+
+// A light casting shadows. Their shadow maps are
+// allocated in order, starting with the first one
+enum ShadowCaster {
+    // Directional lights use one shadow map per cascade,
+    // which depend on the view of the camera
+    Directional(Directional, Shadow),
+
+    // Spot lights use one shadow map, whose matrix is fixed
+    Spot(Mat4, Shadow)
 }
 
 impl Context {
     /// Create a new context, initiate the library
     pub fn new() -> Result<Context> {
-        let vert = r#"
-            #version 140
-
-            uniform mat4 projection;
-            uniform mat4 view;
-            uniform mat4 model;
+        // Create the shader programs, both lit by the same lights. Their
+        // vertices may be skinned and morphed, which extends the default layout
+        let program = try!(Program::with_layout::<BuiltinLayout>(
+            &vertex_shader_source(include_str!("glsl/default.vert")),
+            &lit_shader_source(include_str!("glsl/default.frag"))
+        ));
 
-            in vec3 position;
-            in vec4 color;
-            in vec2 texcoords;
+        let pbr_program = try!(Program::with_layout::<BuiltinLayout>(
+            &vertex_shader_source(include_str!("glsl/default.vert")),
+            &lit_shader_source(include_str!("glsl/pbr.frag"))
+        ));
 
-            out vec4 frag_color;
-            out vec2 frag_texcoords;
+        // Create the programs used to render shadow maps
+        let depth_program = try!(Program::with_layout::<BuiltinLayout>(
+            &vertex_shader_source(include_str!("glsl/depth.vert")),
+            &shader_source(include_str!("glsl/depth.frag"))
+        ));
 
-            void main() {
-                gl_Position = projection * view * model * vec4(position, 1.0);
-
-                frag_color = color;
-                frag_texcoords = texcoords;
-            }
-        "#;
+        let point_depth_program = try!(Program::with_layout::<BuiltinLayout>(
+            &vertex_shader_source(include_str!("glsl/point_depth.vert")),
+            &shader_source(include_str!("glsl/point_depth.frag"))
+        ));
 
-        let frag = r#"
-            #version 140
+        let matrix = math::mat4_identity();
 
-            uniform sampler2D tex;
+        let ctx = Context {
+            shader_program: program,
+            pbr_program: pbr_program,
+            depth_program: depth_program,
+            point_depth_program: point_depth_program,
 
-            uniform vec4 ambient_light_color;
-            uniform float ambient_light_strength;
+            projection: Cell::new(matrix),
+            view: Cell::new(matrix),
 
-            in vec4 frag_color;
-            in vec2 frag_texcoords;
+            pass: Cell::new(Pass::Main),
+            shadow_casters: RefCell::new(Vec::new()),
+            shadow_maps: RefCell::new(None),
+            point_casters: RefCell::new(Vec::new()),
+            cube_shadow_maps: RefCell::new(Vec::new())
+        };
 
-            out vec4 out_color;
+        // Each cube shadow map uses its own texture unit
+        ctx.set("shadow_maps", &(SHADOW_MAP_UNIT as i32));
 
-            void main() {
-                vec4 obj_color = texture2D(tex, frag_texcoords) * frag_color;
+        let units: Vec<i32> = (0..lighting::MAX_POINT_SHADOWS as i32).map(|i| POINT_SHADOW_MAP_UNIT as i32 + i).collect();
+        ctx.set("point_shadow_maps", &units[..]);
 
-                if(ambient_light_strength > 0) {
-                    out_color = ambient_light_strength * ambient_light_color * obj_color;
-                }
-                else {
-                    out_color = obj_color;
-                }
-            }
-        "#;
+        ctx.set("metallic_roughness_texture", &(METALLIC_ROUGHNESS_UNIT as i32));
+        ctx.set("normal_texture", &(NORMAL_UNIT as i32));
+        ctx.set("occlusion_texture", &(OCCLUSION_UNIT as i32));
+        ctx.set("emissive_texture", &(EMISSIVE_UNIT as i32));
 
-        // Create the shader program
-        let program = try!(Program::new(vert, frag));
-        program.bind();
+        ctx.set("irradiance_map", &(IRRADIANCE_UNIT as i32));
+        ctx.set("prefiltered_map", &(PREFILTERED_UNIT as i32));
+        ctx.set("brdf_lut", &(BRDF_LUT_UNIT as i32));
 
         // Set the initial values for the uniform matrices
-        let matrix = math::mat4_identity();
-        program.set_uniform_matrix("projection", matrix.as_ref());
-        program.set_uniform_matrix("view", matrix.as_ref());
-        program.set_uniform_matrix("model", matrix.as_ref());
+        ctx.set("projection", &matrix);
+        ctx.set("view", &matrix);
+        ctx.set("model", &matrix);
+
+        // Default specular highlights
+        ctx.set("specular_strength", &0.5f32);
+        ctx.set("shininess", &32.0f32);
 
         // Bind the program
-        program.bind();
+        ctx.shader_program.bind();
 
-        Ok(Context {
-            shader_program: program
-        })
+        Ok(ctx)
     }
 
     /// Set the projection matrix
     pub fn set_projection(&self, m: Mat4) {
-        self.shader_program.set_uniform_matrix("projection", m.as_ref());
+        if self.pass.get() != Pass::Main {
+            return;
+        }
+
+        self.projection.set(m);
+        self.set("projection", &m);
     }
 
     /// Set the view matrix
     pub fn set_view(&self, m: Mat4) {
-        self.shader_program.set_uniform_matrix("view", m.as_ref());
+        if self.pass.get() != Pass::Main {
+            return;
+        }
+
+        self.view.set(m);
+        self.set("view", &m);
     }
 
     /// Set the model matrix
     pub fn set_model(&self, m: Mat4) {
-        self.shader_program.set_uniform_matrix("model", m.as_ref());
+        match self.pass.get() {
+            Pass::Main => self.set("model", &m),
+            Pass::Shadow => self.depth_program.set_uniform_matrix("model", m.as_ref()),
+            Pass::PointShadow => self.point_depth_program.set_uniform_matrix("model", m.as_ref())
+        }
+    }
+
+    /// Deform the next objects by the matrices of the joints of a skin,
+    /// as computed by `animation::Skin::joint_matrices`, or stop deforming
+    /// them with `None`. Their vertices must have the layout of
+    /// `mesh::SkinnedVertex`. Skins of more than `animation::MAX_JOINTS`
+    /// joints are rejected, leaving the current skin unchanged. Like the
+    /// model matrix, it applies to the current pass only
+    pub fn set_skin(&self, joints: Option<&[Mat4]>) -> Result<()> {
+        if joints.map(|joints| joints.len()).unwrap_or(0) > animation::MAX_JOINTS {
+            let msg = format!("at most {} joints deform a mesh", animation::MAX_JOINTS);
+            return Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput, msg)));
+        }
+
+        let program = match self.pass.get() {
+            Pass::Main => {
+                self.set("skinned", &joints.is_some());
+
+                if let Some(joints) = joints {
+                    self.set("joint_matrices", joints);
+                }
+
+                return Ok(());
+            },
+            Pass::Shadow => &self.depth_program,
+            Pass::PointShadow => &self.point_depth_program
+        };
+
+        let _ = program.set_uniform("skinned", &joints.is_some());
+
+        if let Some(joints) = joints {
+            let _ = program.set_uniform("joint_matrices", joints);
+        }
+
+        Ok(())
+    }
+
+    /// Set the weights of the morph targets of the next objects, one per
+    /// target, or stop morphing them with `None`. This applies to meshes
+    /// blending their targets on the GPU, see `mesh::MorphMode`. Like the
+    /// model matrix, it applies to the current pass only
+    pub fn set_morph_weights(&self, weights: Option<&[f32]>) {
+        let mut padded = [0.0; mesh::MAX_MORPH_TARGETS];
+
+        if let Some(weights) = weights {
+            for (padded, &weight) in padded.iter_mut().zip(weights.iter()) {
+                *padded = weight;
+            }
+        }
+
+        let program = match self.pass.get() {
+            Pass::Main => return self.set("morph_weights", &padded[..]),
+            Pass::Shadow => &self.depth_program,
+            Pass::PointShadow => &self.point_depth_program
+        };
+
+        let _ = program.set_uniform("morph_weights", &padded[..]);
+    }
+
+    /// Use the view of the specified camera. Unlike `set_view`, this
+    /// also tells the position of the viewer for specular lighting
+    pub fn set_camera(&self, camera: &Camera) {
+        self.set_view(camera.view_matrix());
+        self.set("view_position", &camera.position);
     }
 
     /// Use an ambient light
     pub fn set_ambient_light(&self, l: Ambient) {
-        self.shader_program.set_uniform_vector("ambient_light_color", 4, &l.color);
-        self.shader_program.set_uniform_float("ambient_light_strength", l.strength);
+        if self.pass.get() != Pass::Main {
+            return;
+        }
+
+        self.set("ambient_light_color", &l.color);
+        self.set("ambient_light_strength", &l.strength);
+    }
+
+    /// Light physically based materials with an environment, which
+    /// replaces the ambient light for them, or go back to the ambient
+    /// light with `None`. The maps of the environment are bound until
+    /// another environment is set, so it must outlive its use
+    pub fn set_environment(&self, environment: Option<&Environment>) {
+        if self.pass.get() != Pass::Main {
+            return;
+        }
+
+        match environment {
+            Some(environment) => {
+                environment.irradiance().bind_to(IRRADIANCE_UNIT);
+                environment.prefiltered().bind_to(PREFILTERED_UNIT);
+                environment.brdf_lut().bind_to(BRDF_LUT_UNIT);
+
+                let max_lod = (environment.prefiltered().levels() - 1) as f32;
+
+                self.set("has_environment", &true);
+                self.set("environment_intensity", &environment.intensity);
+                self.set("max_reflection_lod", &max_lod);
+            },
+            None => self.set("has_environment", &false)
+        }
+    }
+
+    /// Set the specular highlights of the objects being rendered: their
+    /// strength and their shininess (higher values give smaller highlights)
+    pub fn set_specular(&self, strength: f32, shininess: f32) {
+        self.set("specular_strength", &strength);
+        self.set("shininess", &shininess);
+    }
+
+    /// Render the next objects with a physically based material instead
+    /// of the default shading, until `clear_material` is called. It is
+    /// lit by the same lights, and its textures are bound along with it.
+    /// Its colors are computed in linear space, they should be gamma
+    /// corrected for display, for instance by a `postprocess::PostProcess`
+    pub fn set_material(&self, material: &Material) {
+        if self.pass.get() != Pass::Main {
+            return;
+        }
+
+        let program = &self.pbr_program;
+        program.bind();
+
+        let (alpha_mode, alpha_cutoff) = match material.alpha_mode {
+            AlphaMode::Opaque => (0, 0.0),
+            AlphaMode::Mask(cutoff) => (1, cutoff),
+            AlphaMode::Blend => (2, 0.0)
+        };
+
+        let _ = program.set_uniform("base_color_factor", &material.base_color);
+        let _ = program.set_uniform("metallic_factor", &material.metallic);
+        let _ = program.set_uniform("roughness_factor", &material.roughness);
+        let _ = program.set_uniform("has_normal_texture", &material.normal_texture.is_some());
+        let _ = program.set_uniform("normal_scale", &material.normal_scale);
+        let _ = program.set_uniform("occlusion_strength", &material.occlusion_strength);
+        let _ = program.set_uniform("emissive_factor", &material.emissive);
+        let _ = program.set_uniform("alpha_mode", &alpha_mode);
+        let _ = program.set_uniform("alpha_cutoff", &alpha_cutoff);
+        let _ = program.set_uniform("double_sided", &material.double_sided);
+
+        // Missing textures are replaced by the blank one,
+        // which leaves the factors unchanged
+        let textures = [
+            (&material.base_color_texture, 0),
+            (&material.metallic_roughness_texture, METALLIC_ROUGHNESS_UNIT),
+            (&material.normal_texture, NORMAL_UNIT),
+            (&material.occlusion_texture, OCCLUSION_UNIT),
+            (&material.emissive_texture, EMISSIVE_UNIT)
+        ];
+
+        for &(texture, unit) in textures.iter() {
+            match *texture {
+                Some(ref texture) => texture.bind_to(unit),
+                None => mesh::BLANK_TEXTURE.bind_to(unit)
+            }
+        }
+
+        unsafe {
+            if material.alpha_mode == AlphaMode::Blend {
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            }
+            else {
+                gl::Disable(gl::BLEND);
+            }
+        }
+    }
+
+    /// Go back to the default shading, after `set_material`
+    pub fn clear_material(&self) {
+        if self.pass.get() != Pass::Main {
+            return;
+        }
+
+        unsafe {
+            gl::Disable(gl::BLEND);
+        }
+
+        mesh::BLANK_TEXTURE.bind();
+        self.shader_program.bind();
+    }
+
+    /// Set the lights illuminating the scene, replacing the previous ones.
+    /// Each kind of light is limited to `lighting::MAX_*_LIGHTS`, the
+    /// extra lights are ignored. Without any light, objects are rendered
+    /// using their color and the ambient light only
+    ///
+    /// Directional and spot lights can cast shadows, using up to
+    /// `lighting::MAX_SHADOW_MAPS` shadow maps in total: one per spot
+    /// light, and one per cascade of each directional light. Lights
+    /// whose shadow maps do not fit are not shadowed. Point lights can
+    /// cast shadows too, up to `lighting::MAX_POINT_SHADOWS` of them.
+    /// The shadow maps are updated by `render_shadows`
+    pub fn set_lights(&self, lights: &[Light]) {
+        if self.pass.get() != Pass::Main {
+            return;
+        }
+
+        let (mut directional, mut point, mut spot) = (0, 0, 0);
+        let mut casters = self.shadow_casters.borrow_mut();
+        let mut point_casters = self.point_casters.borrow_mut();
+        let mut slots = 0;
+
+        casters.clear();
+        point_casters.clear();
+
+        for light in lights {
+            match *light {
+                Light::Directional(ref l) if directional < lighting::MAX_DIRECTIONAL_LIGHTS => {
+                    let name = format!("directional_lights[{}]", directional);
+                    let cascades = l.shadow.map_or(1, |shadow| shadow.cascades as usize);
+                    let index = match l.shadow {
+                        Some(shadow) if slots + cascades <= lighting::MAX_SHADOW_MAPS => {
+                            casters.push(ShadowCaster::Directional(*l, shadow));
+                            slots += cascades;
+                            (slots - cascades) as i32
+                        },
+                        _ => -1
+                    };
+                    directional += 1;
+
+                    self.set(&format!("{}.direction", name), &l.direction);
+                    self.set(&format!("{}.color", name), &l.color);
+                    self.set(&format!("{}.strength", name), &l.strength);
+                    self.set(&format!("{}.shadow", name), &index);
+                    self.set(&format!("{}.cascades", name), &(cascades as i32));
+                },
+
+                Light::Point(ref l) if point < lighting::MAX_POINT_LIGHTS => {
+                    let name = format!("point_lights[{}]", point);
+                    let index = match l.shadow {
+                        Some(shadow) if point_casters.len() < lighting::MAX_POINT_SHADOWS => {
+                            let i = point_casters.len();
+                            point_casters.push((*l, shadow));
+
+                            self.set(&format!("point_shadow_ranges[{}]", i), &shadow.distance);
+                            self.set(&format!("point_shadow_biases[{}]", i), &shadow.bias);
+                            i as i32
+                        },
+                        _ => -1
+                    };
+                    point += 1;
+
+                    self.set(&format!("{}.position", name), &l.position);
+                    self.set(&format!("{}.color", name), &l.color);
+                    self.set(&format!("{}.strength", name), &l.strength);
+                    self.set(&format!("{}.attenuation", name), &l.attenuation);
+                    self.set(&format!("{}.shadow", name), &index);
+                },
+
+                Light::Spot(ref l) if spot < lighting::MAX_SPOT_LIGHTS => {
+                    let name = format!("spot_lights[{}]", spot);
+                    let index = match l.shadow {
+                        Some(shadow) if slots < lighting::MAX_SHADOW_MAPS => {
+                            casters.push(ShadowCaster::Spot(l.light_space(&shadow), shadow));
+                            slots += 1;
+                            slots as i32 - 1
+                        },
+                        _ => -1
+                    };
+                    let (inner, outer) = l.cutoffs();
+                    spot += 1;
+
+                    self.set(&format!("{}.position", name), &l.position);
+                    self.set(&format!("{}.direction", name), &l.direction);
+                    self.set(&format!("{}.color", name), &l.color);
+                    self.set(&format!("{}.strength", name), &l.strength);
+                    self.set(&format!("{}.attenuation", name), &l.attenuation);
+                    self.set(&format!("{}.inner_cutoff", name), &inner);
+                    self.set(&format!("{}.outer_cutoff", name), &outer);
+                    self.set(&format!("{}.shadow", name), &index);
+                },
+
+                _ => ()
+            }
+        }
+
+        self.set("num_directional_lights", &(directional as i32));
+        self.set("num_point_lights", &(point as i32));
+        self.set("num_spot_lights", &(spot as i32));
+    }
+
+    /// Update the shadow maps of the lights casting shadows. The
+    /// specified closure is called once per shadow map, and once per
+    /// face of the cube shadow maps of point lights. It must render
+    /// every object casting shadows, just like for the main pass.
+    /// Model matrices set with `set_model` during the closure only
+    /// apply to the shadow pass, and the other settings are ignored
+    ///
+    /// The cascades of directional lights are fitted to the current
+    /// projection and view, which must be set beforehand
+    pub fn render_shadows<F: FnMut(&Context)>(&self, mut render: F) {
+        let casters = self.shadow_casters.borrow();
+        let mut maps = self.shadow_maps.borrow_mut();
+
+        // Matrix, settings and far split distance of each shadow map
+        let mut slots: Vec<(Mat4, Shadow, f32)> = Vec::new();
+
+        for caster in casters.iter() {
+            match *caster {
+                ShadowCaster::Directional(ref light, shadow) if shadow.cascades > 1 => {
+                    let cascades = light.cascade_spaces(&shadow, self.projection.get(), self.view.get());
+                    slots.extend(cascades.into_iter().map(|(matrix, split)| (matrix, shadow, split)));
+                },
+                ShadowCaster::Directional(ref light, shadow) => {
+                    slots.push((light.light_space(&shadow), shadow, f32::MAX));
+                },
+                ShadowCaster::Spot(matrix, shadow) => {
+                    slots.push((matrix, shadow, f32::MAX));
+                }
+            }
+        }
+
+        // The layers of the array are as large as the largest shadow map.
+        // It is created again when it is too small or has another size
+        let resolution = slots.iter().map(|&(_, shadow, _)| shadow.resolution).max().unwrap_or(0);
+
+        let outdated = match *maps {
+            Some(ref maps) => maps.resolution != resolution || (maps.layers as usize) < slots.len(),
+            None => true
+        };
+
+        if outdated && !slots.is_empty() {
+            *maps = Some(ShadowMapArray::new(resolution, slots.len() as u32));
+        }
+
+        let saved = SavedTarget::save();
+        let program = current_program();
+
+        self.depth_program.bind();
+        self.pass.set(Pass::Shadow);
+
+        if let Some(ref maps) = *maps {
+            for (i, &(matrix, shadow, _)) in slots.iter().enumerate() {
+                maps.begin(i as u32, shadow.resolution);
+
+                let _ = self.depth_program.set_uniform("light_space", &matrix);
+                let _ = self.depth_program.set_uniform("model", &math::mat4_identity());
+
+                render(self);
+            }
+        }
+
+        // Render the six faces of the cube maps of point lights
+        let point_casters = self.point_casters.borrow();
+        let mut cube_maps = self.cube_shadow_maps.borrow_mut();
+
+        cube_maps.truncate(point_casters.len());
+
+        for (i, &(_, shadow)) in point_casters.iter().enumerate() {
+            if i == cube_maps.len() {
+                cube_maps.push(CubeShadowMap::new(shadow.resolution));
+            }
+            else if cube_maps[i].resolution != shadow.resolution {
+                cube_maps[i] = CubeShadowMap::new(shadow.resolution);
+            }
+        }
+
+        self.point_depth_program.bind();
+        self.pass.set(Pass::PointShadow);
+
+        for (map, &(ref light, shadow)) in cube_maps.iter().zip(point_casters.iter()) {
+            let _ = self.point_depth_program.set_uniform("light_position", &light.position);
+            let _ = self.point_depth_program.set_uniform("far_plane", &shadow.distance);
+
+            for (face, matrix) in light.face_spaces(&shadow).iter().enumerate() {
+                map.begin(face as u32);
+
+                let _ = self.point_depth_program.set_uniform("light_space", matrix);
+                let _ = self.point_depth_program.set_uniform("model", &math::mat4_identity());
+
+                render(self);
+            }
+        }
+
+        self.pass.set(Pass::Main);
+
+        unsafe {
+            gl::UseProgram(program);
+        }
+
+        saved.restore();
+
+        for (i, map) in cube_maps.iter().enumerate() {
+            map.bind_texture(POINT_SHADOW_MAP_UNIT + i as u32);
+        }
+
+        // Make the shadow maps available to the main pass, with the
+        // part of their layer they cover
+        if let Some(ref maps) = *maps {
+            maps.bind_texture(SHADOW_MAP_UNIT);
+        }
+
+        for (i, &(matrix, shadow, split)) in slots.iter().enumerate() {
+            self.set(&format!("shadow_matrices[{}]", i), &matrix);
+            self.set(&format!("shadow_biases[{}]", i), &shadow.bias);
+            self.set(&format!("shadow_splits[{}]", i), &split);
+            self.set(&format!("shadow_scales[{}]", i), &(shadow.resolution as f32 / resolution as f32));
+        }
     }
+
+    // Set a uniform of the built-in lit shaders, whose types are known
+    // to match, keeping the current program bound. They are not
+    // updated during shadow passes
+    fn set<T: Uniform + ?Sized>(&self, name: &str, value: &T) {
+        if self.pass.get() != Pass::Main {
+            return;
+        }
+
+        let current = current_program();
+
+        for program in [&self.shader_program, &self.pbr_program].iter() {
+            program.bind();
+            let _ = program.set_uniform(name, value);
+        }
+
+        unsafe {
+            gl::UseProgram(current);
+        }
+    }
+}
+
+// Get the program currently in use
+fn current_program() -> GLuint {
+    let mut program: GLint = 0;
+
+    unsafe {
+        gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut program);
+    }
+
+    program as GLuint
+}
+
+// Vertex layout of the built-in programs: skinned vertices,
+// followed by the deltas of the morph targets
+struct BuiltinLayout;
+
+impl VertexLayout for BuiltinLayout {
+    fn attributes() -> Vec<Attribute> {
+        let mut attributes = SkinnedVertex::attributes();
+        attributes.extend(mesh::morph_attributes());
+        attributes
+    }
+}
+
+// Prepend the lights and shadows shared by
+// the lit shaders to the source of one of them
+fn lit_shader_source(body: &str) -> String {
+    shader_source(&format!("{}\n{}\n{}", include_str!("glsl/lights.glsl"), point_shadow_source(), body))
+}
+
+// Generate the lookup into the cube shadow maps, for every one of
+// them. Arrays of samplers can only be indexed by constant
+// expressions in GLSL 1.40
+fn point_shadow_source() -> String {
+    let last = lighting::MAX_POINT_SHADOWS - 1;
+    let mut source = String::from("float point_shadow_depth(int index, vec3 direction) {\n");
+
+    for i in 0..last {
+        source.push_str(&format!("    if(index == {0}) return texture(point_shadow_maps[{0}], direction).r;\n", i));
+    }
+
+    source.push_str(&format!("    return texture(point_shadow_maps[{}], direction).r;\n}}\n", last));
+    source
+}
+
+// Prepend the skinning and morphing functions
+// to the source of a built-in vertex shader
+fn vertex_shader_source(body: &str) -> String {
+    shader_source(&format!(
+        "{}\n{}\n{}",
+        include_str!("glsl/skinning.glsl"),
+        include_str!("glsl/morph.glsl"),
+        body
+    ))
+}
+
+// Prepend the version and the constants shared
+// with the library to the source of a built-in shader
+fn shader_source(body: &str) -> String {
+    format!(
+        "#version 140\n\
+         #define MAX_DIRECTIONAL_LIGHTS {}\n\
+         #define MAX_POINT_LIGHTS {}\n\
+         #define MAX_SPOT_LIGHTS {}\n\
+         #define MAX_SHADOW_MAPS {}\n\
+         #define MAX_POINT_SHADOWS {}\n\
+         #define MAX_JOINTS {}\n\
+         #define MAX_MORPH_TARGETS {}\n{}",
+        lighting::MAX_DIRECTIONAL_LIGHTS,
+        lighting::MAX_POINT_LIGHTS,
+        lighting::MAX_SPOT_LIGHTS,
+        lighting::MAX_SHADOW_MAPS,
+        lighting::MAX_POINT_SHADOWS,
+        animation::MAX_JOINTS,
+        mesh::MAX_MORPH_TARGETS,
+        body
+    )
 }
@@ -0,0 +1,145 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use gl;
+
+use super::Result;
+use mesh::{CubeMap, Texture, TextureFormat};
+use image::HdrImage;
+use shaders::Program;
+use bake::{self, Baker};
+
+/// Default size of the faces of the environment cube map
+pub const DEFAULT_SIZE: u32 = 512;
+
+/// Size of the faces of the irradiance cube map. Irradiance
+/// varies slowly, so it needs very few texels
+pub const IRRADIANCE_SIZE: u32 = 32;
+
+/// Largest size of the faces of the prefiltered cube map
+pub const PREFILTERED_SIZE: u32 = 128;
+
+/// Number of mipmap levels of the prefiltered cube map, from
+/// a smooth (first level) to a fully rough (last level) surface
+pub const PREFILTERED_LEVELS: u32 = 5;
+
+/// Size of the BRDF lookup table
+pub const BRDF_LUT_SIZE: u32 = 256;
+
+/// Represents the light coming from every direction around the scene,
+/// used for image-based lighting of physically based materials. It is
+/// computed once from an equirectangular HDR image into:
+///
+/// - a cube map of the environment itself
+/// - an irradiance cube map, lighting diffuse surfaces
+/// - a cube map prefiltered for increasing roughnesses in its mipmaps,
+///   lighting specular surfaces
+/// - a lookup table of the integrated specular BRDF
+pub struct Environment {
+    /// Scale applied to the light of the environment
+    pub intensity: f32,
+
+    cube_map: Rc<CubeMap>,
+    irradiance: CubeMap,
+    prefiltered: CubeMap,
+    brdf_lut: Texture
+}
+
+impl Environment {
+    /// Load an environment from an equirectangular Radiance (.hdr) image
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Environment> {
+        let image = try!(HdrImage::load(path));
+        Environment::from_image(&image)
+    }
+
+    /// Create an environment from an equirectangular HDR image
+    pub fn from_image(image: &HdrImage) -> Result<Environment> {
+        Environment::with_size(image, DEFAULT_SIZE)
+    }
+
+    /// Create an environment from an equirectangular HDR image, whose
+    /// cube map faces have the specified size. Smaller sizes are faster
+    /// to compute, but blurrier when the environment is seen directly
+    pub fn with_size(image: &HdrImage, size: u32) -> Result<Environment> {
+        // The environment is sampled from its mipmaps when convolving it
+        let cube_map = try!(CubeMap::from_equirect(image, size));
+
+        let baker = Baker::new();
+
+        // Diffuse irradiance. Creating a cube map unbinds the previous
+        // one, so the environment is bound once its targets exist
+        let irradiance = CubeMap::empty(IRRADIANCE_SIZE, TextureFormat::Rgba16F);
+        let prefiltered_size = size.min(PREFILTERED_SIZE);
+        let prefiltered = CubeMap::with_levels(prefiltered_size, TextureFormat::Rgba16F, PREFILTERED_LEVELS);
+
+        cube_map.bind_to(0);
+
+        let program = try!(bake::face_program(include_str!("glsl/ibl/irradiance.frag")));
+        program.bind();
+        let _ = program.set_uniform("environment", &0);
+        let _ = program.set_uniform("resolution", &(size as f32));
+
+        try!(baker.draw_faces(&program, &irradiance, 0));
+
+        // Specular reflections, with one roughness per mipmap level
+        let program = try!(bake::face_program(include_str!("glsl/ibl/prefilter.frag")));
+        program.bind();
+        let _ = program.set_uniform("environment", &0);
+        let _ = program.set_uniform("resolution", &(size as f32));
+
+        for level in 0..prefiltered.levels() {
+            let roughness = level as f32 / (prefiltered.levels() - 1) as f32;
+            let _ = program.set_uniform("roughness", &roughness);
+
+            try!(baker.draw_faces(&program, &prefiltered, level));
+        }
+
+        // Integrated BRDF, which does not depend on the environment
+        let brdf_lut = Texture::empty_with_format(BRDF_LUT_SIZE, BRDF_LUT_SIZE, TextureFormat::Rgba16F);
+
+        let vert = format!("#version 140\n{}", include_str!("glsl/post/fullscreen.vert"));
+        let frag = format!("#version 140\n{}\n{}", include_str!("glsl/ibl/common.glsl"), include_str!("glsl/ibl/brdf.frag"));
+        let program = try!(Program::new(&vert, &frag));
+        program.bind();
+
+        try!(baker.draw(gl::TEXTURE_2D, brdf_lut.id(), 0, BRDF_LUT_SIZE));
+
+        Ok(Environment {
+            intensity: 1.0,
+
+            cube_map: Rc::new(cube_map),
+            irradiance: irradiance,
+            prefiltered: prefiltered,
+            brdf_lut: brdf_lut
+        })
+    }
+
+    /// Set the scale applied to the light of the environment
+    pub fn intensity(mut self, intensity: f32) -> Environment {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Get the cube map of the environment, for instance
+    /// to render it as the background of the scene with a `Skybox`
+    pub fn cube_map(&self) -> &Rc<CubeMap> {
+        &self.cube_map
+    }
+
+    /// Get the irradiance cube map, lighting diffuse surfaces
+    pub fn irradiance(&self) -> &CubeMap {
+        &self.irradiance
+    }
+
+    /// Get the prefiltered cube map, whose mipmap levels
+    /// light increasingly rough specular surfaces
+    pub fn prefiltered(&self) -> &CubeMap {
+        &self.prefiltered
+    }
+
+    /// Get the lookup table of the integrated specular BRDF, with
+    /// the scale in the red channel and the bias in the green one
+    pub fn brdf_lut(&self) -> &Texture {
+        &self.brdf_lut
+    }
+}
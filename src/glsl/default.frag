@@ -0,0 +1,48 @@
+uniform sampler2D tex;
+
+uniform float specular_strength;
+uniform float shininess;
+
+// Accumulated light reaching the fragment
+vec3 diffuse_light = vec3(0.0);
+vec3 specular_light = vec3(0.0);
+
+// Blinn-Phong model
+void add_light(vec3 n, vec3 v, vec3 l, vec3 radiance) {
+    float diffuse = max(dot(n, l), 0.0);
+
+    if(diffuse > 0.0) {
+        vec3 h = normalize(l + v);
+        float specular = pow(max(dot(n, h), 0.0), shininess) * specular_strength;
+
+        diffuse_light += diffuse * radiance;
+        specular_light += specular * radiance;
+    }
+}
+
+void main() {
+    vec4 obj_color = texture2D(tex, frag_texcoords) * frag_color;
+    vec3 ambient = ambient_light_strength * ambient_light_color.rgb;
+
+    // Without any light, keep the unlit behaviour
+    if(num_directional_lights + num_point_lights + num_spot_lights == 0) {
+        if(ambient_light_strength > 0) {
+            out_color = ambient_light_strength * ambient_light_color * obj_color;
+        }
+        else {
+            out_color = obj_color;
+        }
+
+        return;
+    }
+
+    // Vertices without normals only receive ambient light
+    if(length(frag_normal) > 0.0) {
+        vec3 n = normalize(frag_normal);
+        vec3 v = normalize(view_position - frag_position);
+
+        add_lights(n, v);
+    }
+
+    out_color = vec4(obj_color.rgb * (ambient + diffuse_light) + specular_light, obj_color.a);
+}
@@ -0,0 +1,32 @@
+uniform mat4 projection;
+uniform mat4 view;
+uniform mat4 model;
+
+in vec3 position;
+in vec4 color;
+in vec2 texcoords;
+in vec3 normal;
+
+out vec4 frag_color;
+out vec2 frag_texcoords;
+out vec3 frag_position;
+out vec3 frag_normal;
+out float frag_depth;
+
+void main() {
+    mat4 skinned_model = model * skin_matrix();
+
+    // Morph targets are blended in the bind pose, before skinning
+    vec4 world_position = skinned_model * vec4(morph_position(position), 1.0);
+    gl_Position = projection * view * world_position;
+
+    frag_color = color;
+    frag_texcoords = texcoords;
+
+    // Lighting is computed in world space
+    frag_position = world_position.xyz;
+    frag_normal = mat3(transpose(inverse(skinned_model))) * morph_normal(normal);
+
+    // Distance from the camera along its view, to pick shadow cascades
+    frag_depth = -(view * world_position).z;
+}
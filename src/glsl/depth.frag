@@ -0,0 +1,3 @@
+// Only the depth is written
+void main() {
+}
@@ -0,0 +1,8 @@
+uniform mat4 light_space;
+uniform mat4 model;
+
+in vec3 position;
+
+void main() {
+    gl_Position = light_space * model * skin_matrix() * vec4(morph_position(position), 1.0);
+}
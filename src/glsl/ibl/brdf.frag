@@ -0,0 +1,48 @@
+in vec2 frag_texcoords;
+out vec4 out_color;
+
+const uint SAMPLE_COUNT = 1024u;
+
+// Smith geometry term, with the remapping of the
+// Schlick-GGX approximation for image-based lighting
+float geometry(float n_dot_v, float n_dot_l, float roughness) {
+    float k = roughness * roughness / 2.0;
+
+    float view = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    float light = n_dot_l / (n_dot_l * (1.0 - k) + k);
+
+    return view * light;
+}
+
+// Integrate the specular BRDF for a cosine between the normal and the
+// view direction (X) and a roughness (Y). The result is a scale (red)
+// and a bias (green) applied to the reflectance at normal incidence
+void main() {
+    float n_dot_v = max(frag_texcoords.x, 0.0001);
+    float roughness = frag_texcoords.y;
+
+    vec3 v = vec3(sqrt(1.0 - n_dot_v * n_dot_v), 0.0, n_dot_v);
+    vec3 n = vec3(0.0, 0.0, 1.0);
+
+    float scale = 0.0;
+    float bias = 0.0;
+
+    for(uint i = 0u; i < SAMPLE_COUNT; i++) {
+        vec3 h = importance_sample_ggx(hammersley(i, SAMPLE_COUNT), n, roughness);
+        vec3 l = normalize(2.0 * dot(v, h) * h - v);
+
+        float n_dot_l = max(l.z, 0.0);
+        float n_dot_h = max(h.z, 0.0);
+        float v_dot_h = max(dot(v, h), 0.0);
+
+        if(n_dot_l > 0.0) {
+            float visibility = geometry(n_dot_v, n_dot_l, roughness) * v_dot_h / (n_dot_h * n_dot_v);
+            float fresnel = pow(1.0 - v_dot_h, 5.0);
+
+            scale += (1.0 - fresnel) * visibility;
+            bias += fresnel * visibility;
+        }
+    }
+
+    out_color = vec4(scale / float(SAMPLE_COUNT), bias / float(SAMPLE_COUNT), 0.0, 1.0);
+}
@@ -0,0 +1,13 @@
+in vec3 frag_direction;
+out vec4 out_color;
+
+uniform sampler2D equirect;
+
+// Project the direction onto the equirectangular image,
+// whose rows are ordered from the top to the bottom
+void main() {
+    vec3 d = normalize(frag_direction);
+    vec2 uv = vec2(atan(d.z, d.x) / (2.0 * PI) + 0.5, 0.5 - asin(clamp(d.y, -1.0, 1.0)) / PI);
+
+    out_color = vec4(textureLod(equirect, uv, 0.0).rgb, 1.0);
+}
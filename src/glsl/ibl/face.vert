@@ -0,0 +1,14 @@
+// Basis of the cube face being rendered: the X and Y
+// axes of the viewport, and the direction of its center
+uniform mat3 face;
+
+out vec3 frag_direction;
+
+// Single triangle covering the face, the direction
+// is normalized by the fragment shader
+void main() {
+    vec2 p = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2) * 2.0 - 1.0;
+
+    frag_direction = face * vec3(p, 1.0);
+    gl_Position = vec4(p, 0.0, 1.0);
+}
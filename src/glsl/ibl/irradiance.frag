@@ -0,0 +1,42 @@
+in vec3 frag_direction;
+out vec4 out_color;
+
+uniform samplerCube environment;
+
+// Size of the faces of the environment
+uniform float resolution;
+
+const uint SAMPLE_COUNT = 512u;
+
+// Cosine weighted convolution of the environment over the hemisphere
+// around the normal, sampled from a mipmap level matching the solid
+// angle of each sample to avoid aliasing
+void main() {
+    vec3 n = normalize(frag_direction);
+
+    vec3 up = abs(n.y) < 0.999 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, n));
+    vec3 bitangent = cross(n, tangent);
+
+    float texel = 4.0 * PI / (6.0 * resolution * resolution);
+    vec3 irradiance = vec3(0.0);
+
+    for(uint i = 0u; i < SAMPLE_COUNT; i++) {
+        vec2 xi = hammersley(i, SAMPLE_COUNT);
+
+        // Cosine weighted direction, whose pdf is cos(theta) / PI
+        float phi = 2.0 * PI * xi.x;
+        float cos_theta = sqrt(1.0 - xi.y);
+        float sin_theta = sqrt(xi.y);
+
+        vec3 l = tangent * cos(phi) * sin_theta + bitangent * sin(phi) * sin_theta + n * cos_theta;
+
+        float pdf = max(cos_theta / PI, 0.0001);
+        float lod = 0.5 * log2(1.0 / (float(SAMPLE_COUNT) * pdf * texel)) + 1.0;
+
+        irradiance += textureLod(environment, l, max(lod, 0.0)).rgb;
+    }
+
+    // The cosine and PI cancel out with the pdf
+    out_color = vec4(irradiance / float(SAMPLE_COUNT), 1.0);
+}
@@ -0,0 +1,53 @@
+in vec3 frag_direction;
+out vec4 out_color;
+
+uniform samplerCube environment;
+
+// Size of the faces of the environment
+uniform float resolution;
+
+// Roughness matching the mipmap level being rendered
+uniform float roughness;
+
+const uint SAMPLE_COUNT = 512u;
+
+// Trowbridge-Reitz GGX normal distribution
+float distribution(float n_dot_h) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+
+    return a2 / (PI * d * d);
+}
+
+// Convolution of the environment with the GGX lobe, assuming
+// that the view and reflection directions match the normal
+void main() {
+    vec3 n = normalize(frag_direction);
+    vec3 v = n;
+
+    float texel = 4.0 * PI / (6.0 * resolution * resolution);
+
+    vec3 color = vec3(0.0);
+    float weight = 0.0;
+
+    for(uint i = 0u; i < SAMPLE_COUNT; i++) {
+        vec3 h = importance_sample_ggx(hammersley(i, SAMPLE_COUNT), n, roughness);
+        vec3 l = normalize(2.0 * dot(v, h) * h - v);
+
+        float n_dot_l = dot(n, l);
+
+        if(n_dot_l > 0.0) {
+            // Sample a mipmap level matching the solid angle of the
+            // sample, to avoid bright dots with rough surfaces
+            float n_dot_h = max(dot(n, h), 0.0);
+            float pdf = distribution(n_dot_h) / 4.0 + 0.0001;
+            float lod = roughness == 0.0 ? 0.0 : 0.5 * log2(1.0 / (float(SAMPLE_COUNT) * pdf * texel)) + 1.0;
+
+            color += textureLod(environment, l, max(lod, 0.0)).rgb * n_dot_l;
+            weight += n_dot_l;
+        }
+    }
+
+    out_color = vec4(color / max(weight, 0.0001), 1.0);
+}
@@ -0,0 +1,203 @@
+// Lights, shadows and inputs shared by the built-in lit shaders
+
+struct DirectionalLight {
+    vec3 direction;
+    vec3 color;
+    float strength;
+    int shadow;
+    int cascades;
+};
+
+struct PointLight {
+    vec3 position;
+    vec3 color;
+    float strength;
+    vec3 attenuation;
+    int shadow;
+};
+
+struct SpotLight {
+    vec3 position;
+    vec3 direction;
+    vec3 color;
+    float strength;
+    vec3 attenuation;
+    float inner_cutoff;
+    float outer_cutoff;
+    int shadow;
+};
+
+uniform vec4 ambient_light_color;
+uniform float ambient_light_strength;
+
+uniform int num_directional_lights;
+uniform int num_point_lights;
+uniform int num_spot_lights;
+
+uniform DirectionalLight directional_lights[MAX_DIRECTIONAL_LIGHTS];
+uniform PointLight point_lights[MAX_POINT_LIGHTS];
+uniform SpotLight spot_lights[MAX_SPOT_LIGHTS];
+
+uniform sampler2DArrayShadow shadow_maps;
+uniform mat4 shadow_matrices[MAX_SHADOW_MAPS];
+uniform float shadow_biases[MAX_SHADOW_MAPS];
+uniform float shadow_splits[MAX_SHADOW_MAPS];
+uniform float shadow_scales[MAX_SHADOW_MAPS];
+
+uniform samplerCube point_shadow_maps[MAX_POINT_SHADOWS];
+uniform float point_shadow_ranges[MAX_POINT_SHADOWS];
+uniform float point_shadow_biases[MAX_POINT_SHADOWS];
+
+uniform vec3 view_position;
+
+in vec4 frag_color;
+in vec2 frag_texcoords;
+in vec3 frag_position;
+in vec3 frag_normal;
+in float frag_depth;
+
+out vec4 out_color;
+
+// Add the contribution of a light coming from the direction l
+// (pointing towards the light), defined by each shading model
+void add_light(vec3 n, vec3 v, vec3 l, vec3 radiance);
+
+// Fraction of light that reaches the fragment, according to the
+// shadow map of a light, filtered over 3x3 texels (PCF)
+float shadow(int index) {
+    if(index < 0) {
+        return 1.0;
+    }
+
+    // Position of the fragment in the shadow map
+    vec4 p = shadow_matrices[index] * vec4(frag_position, 1.0);
+    vec3 coords = p.xyz / p.w * 0.5 + 0.5;
+
+    // Beyond the far plane of the light
+    if(coords.z > 1.0) {
+        return 1.0;
+    }
+
+    // Each map covers the lower left corner of its layer
+    coords.xy *= shadow_scales[index];
+    coords.z -= shadow_biases[index];
+
+    vec2 texel = 1.0 / vec2(textureSize(shadow_maps, 0).xy);
+    float lit = 0.0;
+
+    for(int x = -1; x <= 1; x++) {
+        for(int y = -1; y <= 1; y++) {
+            lit += texture(shadow_maps, vec4(coords.xy + vec2(x, y) * texel, index, coords.z));
+        }
+    }
+
+    return lit / 9.0;
+}
+
+// Fraction of light that reaches the fragment from a directional
+// light, using the cascade covering the fragment. Fragments close to
+// the end of a cascade blend with the next one to hide the seam
+float directional_shadow(DirectionalLight light) {
+    if(light.shadow < 0 || light.cascades <= 1) {
+        return shadow(light.shadow);
+    }
+
+    for(int i = 0; i < light.cascades; i++) {
+        int index = light.shadow + i;
+        float end = shadow_splits[index];
+
+        if(frag_depth < end) {
+            float lit = shadow(index);
+
+            if(i + 1 < light.cascades) {
+                float start = i > 0 ? shadow_splits[index - 1] : 0.0;
+                float blend = smoothstep(end - (end - start) * 0.1, end, frag_depth);
+
+                lit = mix(lit, shadow(index + 1), blend);
+            }
+
+            return lit;
+        }
+    }
+
+    // Beyond the last cascade
+    return 1.0;
+}
+
+// Distance to the closest occluder stored in a cube shadow map,
+// relative to the range of the shadows. Generated by the library
+// for every cube shadow map
+float point_shadow_depth(int index, vec3 direction);
+
+// Fraction of light that reaches the fragment from a point light,
+// comparing linear distances to the light. The cube map is sampled
+// around the direction of the fragment to soften the edges
+float point_shadow(int index, vec3 light_position) {
+    if(index < 0) {
+        return 1.0;
+    }
+
+    vec3 direction = frag_position - light_position;
+    float range = point_shadow_ranges[index];
+    float depth = length(direction) / range;
+
+    // Beyond the range of the shadows
+    if(depth > 1.0) {
+        return 1.0;
+    }
+
+    depth -= point_shadow_biases[index];
+
+    // Offsets towards the corners of a cube, scaled with the
+    // distance so that they cover the same angle from the light
+    float radius = 0.005 * length(direction);
+    float lit = 0.0;
+
+    for(int x = -1; x <= 1; x += 2) {
+        for(int y = -1; y <= 1; y += 2) {
+            for(int z = -1; z <= 1; z += 2) {
+                vec3 offset = vec3(x, y, z) * radius;
+                lit += depth <= point_shadow_depth(index, direction + offset) ? 1.0 : 0.0;
+            }
+        }
+    }
+
+    return lit / 8.0;
+}
+
+// Intensity of a positional light at the specified distance
+float attenuation(vec3 factors, float d) {
+    return 1.0 / (factors.x + factors.y * d + factors.z * d * d);
+}
+
+// Call add_light for each light reaching the fragment, with its
+// radiance reduced by its attenuation, its cone and its shadows
+void add_lights(vec3 n, vec3 v) {
+    for(int i = 0; i < num_directional_lights; i++) {
+        DirectionalLight light = directional_lights[i];
+        add_light(n, v, -light.direction, light.color * light.strength * directional_shadow(light));
+    }
+
+    for(int i = 0; i < num_point_lights; i++) {
+        PointLight light = point_lights[i];
+
+        vec3 l = light.position - frag_position;
+        float d = length(l);
+
+        add_light(n, v, l / d, light.color * light.strength * attenuation(light.attenuation, d) * point_shadow(light.shadow, light.position));
+    }
+
+    for(int i = 0; i < num_spot_lights; i++) {
+        SpotLight light = spot_lights[i];
+
+        vec3 l = light.position - frag_position;
+        float d = length(l);
+        l /= d;
+
+        // Smooth edge between the inner and the outer cone
+        float theta = dot(l, -light.direction);
+        float cone = clamp((theta - light.outer_cutoff) / max(light.inner_cutoff - light.outer_cutoff, 0.0001), 0.0, 1.0);
+
+        add_light(n, v, l, light.color * light.strength * attenuation(light.attenuation, d) * cone * shadow(light.shadow));
+    }
+}
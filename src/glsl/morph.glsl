@@ -0,0 +1,31 @@
+// Blending of the morph targets of a mesh, whose deltas are zero
+// for the targets it does not have
+uniform float morph_weights[MAX_MORPH_TARGETS];
+
+in vec3 morph_position0;
+in vec3 morph_position1;
+in vec3 morph_position2;
+in vec3 morph_position3;
+
+in vec3 morph_normal0;
+in vec3 morph_normal1;
+in vec3 morph_normal2;
+in vec3 morph_normal3;
+
+// Move a position by the weighted deltas of the targets
+vec3 morph_position(vec3 position) {
+    return position
+         + morph_weights[0] * morph_position0
+         + morph_weights[1] * morph_position1
+         + morph_weights[2] * morph_position2
+         + morph_weights[3] * morph_position3;
+}
+
+// Change a normal by the weighted deltas of the targets
+vec3 morph_normal(vec3 normal) {
+    return normal
+         + morph_weights[0] * morph_normal0
+         + morph_weights[1] * morph_normal1
+         + morph_weights[2] * morph_normal2
+         + morph_weights[3] * morph_normal3;
+}
@@ -0,0 +1,187 @@
+const float PI = 3.14159265359;
+
+// Alpha modes, as in glTF
+const int ALPHA_OPAQUE = 0;
+const int ALPHA_MASK = 1;
+const int ALPHA_BLEND = 2;
+
+uniform sampler2D tex;
+uniform vec4 base_color_factor;
+
+// Metalness in the blue channel, roughness in the green one
+uniform sampler2D metallic_roughness_texture;
+uniform float metallic_factor;
+uniform float roughness_factor;
+
+uniform sampler2D normal_texture;
+uniform bool has_normal_texture;
+uniform float normal_scale;
+
+// Ambient occlusion in the red channel
+uniform sampler2D occlusion_texture;
+uniform float occlusion_strength;
+
+uniform sampler2D emissive_texture;
+uniform vec3 emissive_factor;
+
+// Image-based lighting, replacing the ambient light when enabled
+uniform samplerCube irradiance_map;
+uniform samplerCube prefiltered_map;
+uniform sampler2D brdf_lut;
+uniform bool has_environment;
+uniform float environment_intensity;
+uniform float max_reflection_lod;
+
+uniform int alpha_mode;
+uniform float alpha_cutoff;
+uniform bool double_sided;
+
+// Surface properties of the fragment
+vec3 albedo;
+float metallic;
+float roughness;
+vec3 f0;
+
+// Accumulated light reflected towards the viewer
+vec3 reflected_light = vec3(0.0);
+
+// Textures are stored in sRGB, lighting is computed in linear space
+vec3 srgb_to_linear(vec3 color) {
+    return pow(color, vec3(2.2));
+}
+
+// Trowbridge-Reitz GGX normal distribution
+float distribution(float n_dot_h) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+
+    return a2 / (PI * d * d);
+}
+
+// Smith geometry term with the Schlick-GGX approximation
+float geometry(float n_dot_v, float n_dot_l) {
+    float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+
+    float view = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    float light = n_dot_l / (n_dot_l * (1.0 - k) + k);
+
+    return view * light;
+}
+
+// Schlick approximation of the Fresnel reflectance
+vec3 fresnel(float cos_theta) {
+    return f0 + (1.0 - f0) * pow(1.0 - cos_theta, 5.0);
+}
+
+// Fresnel reflectance averaged over the microfacets, which reflect
+// less at grazing angles when the surface is rough
+vec3 fresnel_roughness(float cos_theta) {
+    return f0 + (max(vec3(1.0 - roughness), f0) - f0) * pow(1.0 - cos_theta, 5.0);
+}
+
+// Light coming from the environment, split into the irradiance of the
+// hemisphere for the diffuse term, and the prefiltered reflection scaled
+// by the integrated BRDF for the specular term
+vec3 environment_light(vec3 n, vec3 v) {
+    float n_dot_v = max(dot(n, v), 0.0);
+    vec3 f = fresnel_roughness(n_dot_v);
+
+    vec3 diffuse = texture(irradiance_map, n).rgb * albedo * (1.0 - f) * (1.0 - metallic);
+
+    vec3 r = reflect(-v, n);
+    vec3 prefiltered = textureLod(prefiltered_map, r, roughness * max_reflection_lod).rgb;
+    vec2 brdf = texture(brdf_lut, vec2(n_dot_v, roughness)).rg;
+    vec3 specular = prefiltered * (f * brdf.x + brdf.y);
+
+    return (diffuse + specular) * environment_intensity;
+}
+
+// Cook-Torrance specular BRDF, with a Lambertian diffuse term
+void add_light(vec3 n, vec3 v, vec3 l, vec3 radiance) {
+    float n_dot_l = max(dot(n, l), 0.0);
+
+    if(n_dot_l > 0.0) {
+        vec3 h = normalize(l + v);
+        float n_dot_v = max(dot(n, v), 0.0001);
+
+        vec3 f = fresnel(max(dot(h, v), 0.0));
+        vec3 specular = distribution(max(dot(n, h), 0.0)) * geometry(n_dot_v, n_dot_l) * f / (4.0 * n_dot_v * n_dot_l);
+
+        // Metals have no diffuse reflection
+        vec3 diffuse = (1.0 - f) * (1.0 - metallic) * albedo / PI;
+
+        reflected_light += (diffuse + specular) * radiance * n_dot_l;
+    }
+}
+
+// Perturb the normal with the normal map, using a tangent frame computed
+// from the screen space derivatives, so that meshes need no tangents
+vec3 perturb_normal(vec3 n) {
+    vec3 dp1 = dFdx(frag_position);
+    vec3 dp2 = dFdy(frag_position);
+    vec2 duv1 = dFdx(frag_texcoords);
+    vec2 duv2 = dFdy(frag_texcoords);
+
+    vec3 dp2perp = cross(dp2, n);
+    vec3 dp1perp = cross(n, dp1);
+    vec3 t = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 b = dp2perp * duv1.y + dp1perp * duv2.y;
+
+    float scale = inversesqrt(max(dot(t, t), dot(b, b)));
+    mat3 tbn = mat3(t * scale, b * scale, n);
+
+    vec3 mapped = texture(normal_texture, frag_texcoords).xyz * 2.0 - 1.0;
+    mapped.xy *= normal_scale;
+
+    return normalize(tbn * mapped);
+}
+
+void main() {
+    vec4 base_color = texture(tex, frag_texcoords);
+    base_color = vec4(srgb_to_linear(base_color.rgb), base_color.a) * base_color_factor * frag_color;
+
+    if(alpha_mode == ALPHA_MASK && base_color.a < alpha_cutoff) {
+        discard;
+    }
+
+    if(alpha_mode == ALPHA_OPAQUE) {
+        base_color.a = 1.0;
+    }
+
+    vec4 metallic_roughness = texture(metallic_roughness_texture, frag_texcoords);
+
+    albedo = base_color.rgb;
+    metallic = clamp(metallic_roughness.b * metallic_factor, 0.0, 1.0);
+    roughness = clamp(metallic_roughness.g * roughness_factor, 0.04, 1.0);
+
+    // Dielectrics reflect about 4% of the light at normal incidence
+    f0 = mix(vec3(0.04), albedo, metallic);
+
+    float occlusion = mix(1.0, texture(occlusion_texture, frag_texcoords).r, occlusion_strength);
+    vec3 emissive = srgb_to_linear(texture(emissive_texture, frag_texcoords).rgb) * emissive_factor;
+    vec3 ambient = ambient_light_strength * ambient_light_color.rgb * albedo * occlusion;
+
+    // Vertices without normals only receive ambient light
+    if(length(frag_normal) > 0.0) {
+        vec3 n = normalize(frag_normal);
+        vec3 v = normalize(view_position - frag_position);
+
+        // The back faces of double sided materials face the other way
+        if(double_sided && !gl_FrontFacing) {
+            n = -n;
+        }
+
+        if(has_normal_texture) {
+            n = perturb_normal(n);
+        }
+
+        if(has_environment) {
+            ambient = environment_light(n, v) * occlusion;
+        }
+
+        add_lights(n, v);
+    }
+
+    out_color = vec4(ambient + reflected_light + emissive, base_color.a);
+}
@@ -0,0 +1,10 @@
+uniform vec3 light_position;
+uniform float far_plane;
+
+in vec3 frag_position;
+
+// Store the linear distance to the light instead of the
+// perspective depth, so that all faces can be compared alike
+void main() {
+    gl_FragDepth = length(frag_position - light_position) / far_plane;
+}
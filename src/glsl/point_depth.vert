@@ -0,0 +1,13 @@
+uniform mat4 light_space;
+uniform mat4 model;
+
+in vec3 position;
+
+out vec3 frag_position;
+
+void main() {
+    vec4 world_position = model * skin_matrix() * vec4(morph_position(position), 1.0);
+    gl_Position = light_space * world_position;
+
+    frag_position = world_position.xyz;
+}
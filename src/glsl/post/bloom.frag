@@ -0,0 +1,7 @@
+uniform sampler2D bloom;
+uniform float strength;
+
+void main() {
+    vec4 color = texture(source, frag_texcoords);
+    out_color = vec4(color.rgb + texture(bloom, frag_texcoords).rgb * strength, color.a);
+}
@@ -0,0 +1,17 @@
+// Offset between two samples, in texels
+uniform vec2 direction;
+
+const float WEIGHTS[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+// One direction of a separable gaussian blur
+void main() {
+    vec2 offset = direction / resolution;
+    vec3 color = texture(source, frag_texcoords).rgb * WEIGHTS[0];
+
+    for(int i = 1; i < 5; i++) {
+        color += texture(source, frag_texcoords + offset * float(i)).rgb * WEIGHTS[i];
+        color += texture(source, frag_texcoords - offset * float(i)).rgb * WEIGHTS[i];
+    }
+
+    out_color = vec4(color, 1.0);
+}
@@ -0,0 +1,11 @@
+uniform float threshold;
+
+// Keep the parts of the image brighter than the threshold,
+// with a soft knee to avoid harsh transitions
+void main() {
+    vec3 color = texture(source, frag_texcoords).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    float contribution = max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+
+    out_color = vec4(color * contribution, 1.0);
+}
@@ -0,0 +1,13 @@
+uniform sampler3D lut;
+uniform float lut_size;
+uniform float intensity;
+
+void main() {
+    vec4 color = texture(source, frag_texcoords);
+
+    // Sample the centers of the texels at the edges of the LUT
+    vec3 coords = clamp(color.rgb, 0.0, 1.0) * ((lut_size - 1.0) / lut_size) + 0.5 / lut_size;
+    vec3 graded = texture(lut, coords).rgb;
+
+    out_color = vec4(mix(color.rgb, graded, intensity), color.a);
+}
@@ -0,0 +1,3 @@
+void main() {
+    out_color = texture(source, frag_texcoords);
+}
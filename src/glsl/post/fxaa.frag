@@ -0,0 +1,52 @@
+const float EDGE_MIN = 1.0 / 128.0;
+const float EDGE_MUL = 1.0 / 8.0;
+const float SPAN_MAX = 8.0;
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+// Fast approximate anti-aliasing: blur along the edges
+// detected from the luminance of the neighbouring pixels
+void main() {
+    vec2 texel = 1.0 / resolution;
+    vec4 center = texture(source, frag_texcoords);
+
+    float nw = luma(texture(source, frag_texcoords + vec2(-1.0, -1.0) * texel).rgb);
+    float ne = luma(texture(source, frag_texcoords + vec2( 1.0, -1.0) * texel).rgb);
+    float sw = luma(texture(source, frag_texcoords + vec2(-1.0,  1.0) * texel).rgb);
+    float se = luma(texture(source, frag_texcoords + vec2( 1.0,  1.0) * texel).rgb);
+    float m = luma(center.rgb);
+
+    float luma_min = min(m, min(min(nw, ne), min(sw, se)));
+    float luma_max = max(m, max(max(nw, ne), max(sw, se)));
+
+    // Direction of the edge
+    vec2 dir = vec2(-((nw + ne) - (sw + se)), (nw + sw) - (ne + se));
+
+    float reduce = max((nw + ne + sw + se) * 0.25 * EDGE_MUL, EDGE_MIN);
+    float scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+
+    dir = clamp(dir * scale, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+    vec3 a = 0.5 * (
+        texture(source, frag_texcoords + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(source, frag_texcoords + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+
+    vec3 b = a * 0.5 + 0.25 * (
+        texture(source, frag_texcoords - dir * 0.5).rgb +
+        texture(source, frag_texcoords + dir * 0.5).rgb
+    );
+
+    // Fall back to the narrower blur when the wider one
+    // samples across another edge
+    float luma_b = luma(b);
+
+    if(luma_b < luma_min || luma_b > luma_max) {
+        out_color = vec4(a, center.a);
+    }
+    else {
+        out_color = vec4(b, center.a);
+    }
+}
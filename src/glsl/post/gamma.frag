@@ -0,0 +1,6 @@
+uniform float gamma;
+
+void main() {
+    vec4 color = texture(source, frag_texcoords);
+    out_color = vec4(pow(max(color.rgb, vec3(0.0)), vec3(1.0 / gamma)), color.a);
+}
@@ -0,0 +1,15 @@
+// Result of the previous effect, or the scene for the first one
+uniform sampler2D source;
+
+// Scene rendered before any effect
+uniform sampler2D scene;
+
+// Size of the output, in pixels
+uniform vec2 resolution;
+
+// Time since the creation of the chain, in seconds
+uniform float time;
+
+in vec2 frag_texcoords;
+out vec4 out_color;
+
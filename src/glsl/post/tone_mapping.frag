@@ -0,0 +1,19 @@
+// 0: Reinhard, 1: ACES filmic
+uniform int curve;
+uniform float exposure;
+
+// Gamma applied to the tone mapped colors
+uniform float gamma;
+
+// Fitted curve of the ACES filmic tone mapping, by Krzysztof Narkowicz
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+void main() {
+    vec4 color = texture(source, frag_texcoords);
+    vec3 hdr = color.rgb * exposure;
+
+    vec3 ldr = curve == 1 ? aces(hdr) : hdr / (hdr + vec3(1.0));
+    out_color = vec4(pow(ldr, vec3(1.0 / gamma)), color.a);
+}
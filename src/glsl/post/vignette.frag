@@ -0,0 +1,12 @@
+uniform float strength;
+uniform float radius;
+
+void main() {
+    vec4 color = texture(source, frag_texcoords);
+
+    // Distance to the center, corrected for the aspect ratio
+    vec2 p = (frag_texcoords - 0.5) * vec2(resolution.x / resolution.y, 1.0);
+    float vignette = smoothstep(radius, radius - 0.5, length(p));
+
+    out_color = vec4(color.rgb * mix(1.0, vignette, strength), color.a);
+}
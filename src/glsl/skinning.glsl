@@ -0,0 +1,18 @@
+// Deformation of the vertices by the joints of a skeleton
+uniform bool skinned;
+uniform mat4 joint_matrices[MAX_JOINTS];
+
+in uvec4 joints;
+in vec4 weights;
+
+// Matrix moving the vertex from the bind pose to the current pose
+mat4 skin_matrix() {
+    if (!skinned) {
+        return mat4(1.0);
+    }
+
+    return weights.x * joint_matrices[joints.x]
+         + weights.y * joint_matrices[joints.y]
+         + weights.z * joint_matrices[joints.z]
+         + weights.w * joint_matrices[joints.w];
+}
@@ -0,0 +1,10 @@
+uniform samplerCube cube_map;
+uniform float intensity;
+
+in vec4 frag_direction;
+out vec4 out_color;
+
+void main() {
+    vec3 direction = frag_direction.xyz / frag_direction.w;
+    out_color = vec4(texture(cube_map, direction).rgb * intensity, 1.0);
+}
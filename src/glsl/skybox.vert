@@ -0,0 +1,13 @@
+// Inverse of the projection and the view without its translation
+uniform mat4 inverse_view_projection;
+
+out vec4 frag_direction;
+
+// Single triangle covering the screen on the far plane. The direction
+// is divided by the fragment shader, after the interpolation
+void main() {
+    vec2 p = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2) * 2.0 - 1.0;
+
+    frag_direction = inverse_view_projection * vec4(p, 1.0, 1.0);
+    gl_Position = vec4(p, 1.0, 1.0);
+}
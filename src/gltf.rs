@@ -0,0 +1,1720 @@
+use std::f32;
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::rc::Rc;
+
+use cgmath::{self, InnerSpace};
+use imagefmt;
+use serde_json::{self, Value};
+
+use super::{Error, Result};
+use math::{self, Vec3, Vec4, Mat4, Quat};
+use mesh::{self, Vertex, SkinnedVertex, Texture, TextureOptions, Wrap, Filter, PrimitiveType, Mesh, MorphTarget, MorphMode};
+use image::Image;
+use material::{Material, AlphaMode};
+use lighting::{Light, Directional, Point, Spot};
+use camera::Camera;
+use context::Context;
+use animation::{Skin, Animation, Channel, Interpolation, Property, MAX_JOINTS};
+
+// Binary glTF container
+const GLB_MAGIC: u32 = 0x46546C67;
+const GLB_JSON_CHUNK: u32 = 0x4E4F534A;
+const GLB_BIN_CHUNK: u32 = 0x004E4942;
+
+// Accessor component types
+const BYTE: u64 = 5120;
+const UNSIGNED_BYTE: u64 = 5121;
+const SHORT: u64 = 5122;
+const UNSIGNED_SHORT: u64 = 5123;
+const UNSIGNED_INT: u64 = 5125;
+const FLOAT: u64 = 5126;
+
+/// Represents a glTF 2.0 asset loaded in main memory: its meshes and
+/// images are decoded, and its other objects are referenced by index,
+/// as in the file. Use a `GltfModel` to render it
+pub struct Gltf {
+    pub meshes: Vec<GltfMesh>,
+    pub images: Vec<Image>,
+    pub textures: Vec<GltfTexture>,
+    pub materials: Vec<GltfMaterial>,
+    pub nodes: Vec<GltfNode>,
+    pub scenes: Vec<GltfScene>,
+
+    /// Scene to display by default, if specified
+    pub scene: Option<usize>,
+
+    pub cameras: Vec<GltfCamera>,
+
+    /// Lights of the KHR_lights_punctual extension
+    pub lights: Vec<GltfLight>,
+
+    pub skins: Vec<Skin>,
+    pub animations: Vec<Animation>
+}
+
+/// A mesh, made of primitives which each use their own material
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+
+    /// Default weights of the morph targets, which every
+    /// primitive has the same number of
+    pub weights: Vec<f32>
+}
+
+/// Geometry to be rendered with a single material
+pub struct GltfPrimitive {
+    pub primitive: PrimitiveType,
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+
+    /// Joints influencing each vertex, and their weights,
+    /// empty unless the primitive is skinned
+    pub joints: Vec<[u16; 4]>,
+    pub weights: Vec<[f32; 4]>,
+
+    /// Morph targets of the primitive, which change the normals
+    /// of all of them or of none of them
+    pub targets: Vec<MorphTarget>,
+
+    /// Material of the primitive, the default one being used without it
+    pub material: Option<usize>
+}
+
+/// A texture: an image and how it is sampled
+pub struct GltfTexture {
+    pub image: usize,
+    pub options: TextureOptions
+}
+
+/// A physically based material, whose textures are referenced by index.
+/// The fields match the ones of `material::Material`
+pub struct GltfMaterial {
+    pub name: Option<String>,
+    pub base_color: [f32; 4],
+    pub base_color_texture: Option<usize>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub metallic_roughness_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<usize>,
+    pub occlusion_strength: f32,
+    pub emissive: [f32; 3],
+    pub emissive_texture: Option<usize>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool
+}
+
+/// Local transform of a node, relative to its parent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transform {
+    /// A matrix, which cannot be animated
+    Matrix(Mat4),
+
+    /// A translation, a rotation and a scale, applied in reverse order
+    Decomposed {
+        translation: Vec3,
+        rotation: Quat,
+        scale: Vec3
+    }
+}
+
+/// A node of the scene hierarchy, which may hold a mesh (deformed
+/// by a skin and its morph targets), a camera or a light
+#[derive(Debug, Clone)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    pub transform: Transform,
+    pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+    pub skin: Option<usize>,
+    pub camera: Option<usize>,
+    pub light: Option<usize>,
+
+    /// Weights of the morph targets of the mesh, the ones
+    /// of the mesh unless the node overrides them
+    pub weights: Vec<f32>
+}
+
+/// A scene: the roots of a node hierarchy
+pub struct GltfScene {
+    pub name: Option<String>,
+    pub nodes: Vec<usize>
+}
+
+/// Projection of a camera. Angles are in radians
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Perspective projection, infinite without a far plane
+    Perspective {
+        yfov: f32,
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        zfar: Option<f32>
+    },
+
+    /// Orthographic projection, with half the width
+    /// and the height of the view
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        znear: f32,
+        zfar: f32
+    }
+}
+
+/// A camera, looking down the negative Z axis of its node
+pub struct GltfCamera {
+    pub name: Option<String>,
+    pub projection: Projection
+}
+
+/// Kinds of punctual lights. Angles are in radians
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Point,
+    Spot {
+        inner_cone: f32,
+        outer_cone: f32
+    }
+}
+
+/// A punctual light, pointing down the negative Z axis of its node
+pub struct GltfLight {
+    pub name: Option<String>,
+    pub kind: LightKind,
+    pub color: [f32; 3],
+    pub intensity: f32,
+
+    /// Distance beyond which the light has no effect
+    pub range: Option<f32>
+}
+
+impl Gltf {
+    /// Load a glTF asset, either JSON (.gltf) or binary (.glb). The
+    /// external buffers and images are searched next to it
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Gltf> {
+        let path = path.as_ref();
+
+        let mut data = Vec::new();
+        let mut file = try!(File::open(path).map_err(Error::Io));
+        try!(file.read_to_end(&mut data).map_err(Error::Io));
+
+        Gltf::from_slice(&data, path.parent())
+    }
+
+    /// Parse a glTF asset, either JSON or binary. External files are
+    /// searched in the specified directory; without one, only the
+    /// embedded buffers and images are available
+    pub fn from_slice(data: &[u8], dir: Option<&Path>) -> Result<Gltf> {
+        let (json, bin) = if data.len() >= 4 && read_u32(data, 0) == GLB_MAGIC {
+            try!(parse_glb(data))
+        }
+        else {
+            (data, None)
+        };
+
+        let json: Value = try!(serde_json::from_slice(json).map_err(Error::Json));
+
+        // Fetch the binary data referenced by the asset
+        let mut buffers = Vec::new();
+
+        for (i, buffer) in try!(array(&json, "buffers")).iter().enumerate() {
+            let context = format!("buffer {}", i);
+            let length = try!(required(&context, "byteLength", try!(get_usize(buffer, "byteLength"))));
+
+            let data = match buffer.get("uri").and_then(|uri| uri.as_str()) {
+                Some(uri) => try!(read_uri(uri, dir, &context)),
+                None => match (i, bin) {
+                    (0, Some(bin)) => bin.to_vec(),
+                    _ => return Err(malformed(&context, "no data"))
+                }
+            };
+
+            if data.len() < length {
+                return Err(malformed(&context, "shorter than its length"));
+            }
+
+            buffers.push(data);
+        }
+
+        let importer = Importer {
+            json: &json,
+            buffers: buffers,
+            dir: dir
+        };
+
+        importer.import()
+    }
+
+    /// Get the scene displayed by default: the specified one, or
+    /// the first one. Without scenes, the roots of the hierarchy
+    /// are returned
+    pub fn default_scene(&self) -> Vec<usize> {
+        if let Some(scene) = self.scene.or(if self.scenes.is_empty() { None } else { Some(0) }) {
+            return self.scenes[scene].nodes.clone();
+        }
+
+        let mut roots = vec![true; self.nodes.len()];
+
+        for node in self.nodes.iter() {
+            for &child in node.children.iter() {
+                roots[child] = false;
+            }
+        }
+
+        (0..self.nodes.len()).filter(|&i| roots[i]).collect()
+    }
+
+    /// Compute the transforms of the nodes relative to the scene,
+    /// for the nodes reachable from the specified roots
+    pub fn world_transforms(&self, roots: &[usize]) -> Vec<Option<Mat4>> {
+        world_transforms(&self.nodes, roots)
+    }
+
+    /// Get the lights of the default scene, placed by their nodes
+    pub fn scene_lights(&self) -> Vec<Light> {
+        let transforms = self.world_transforms(&self.default_scene());
+
+        self.nodes.iter().zip(transforms.iter())
+            .filter_map(|(node, transform)| match (node.light, *transform) {
+                (Some(light), Some(transform)) => Some(self.lights[light].to_light(transform)),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Get the cameras of the default scene, placed by their nodes
+    pub fn scene_cameras(&self) -> Vec<(&GltfCamera, Camera)> {
+        let transforms = self.world_transforms(&self.default_scene());
+
+        self.nodes.iter().zip(transforms.iter())
+            .filter_map(|(node, transform)| match (node.camera, *transform) {
+                (Some(camera), Some(transform)) => Some((&self.cameras[camera], camera_from_transform(transform))),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Upload the textures to the graphics card
+    pub fn load_textures(&self) -> Vec<Rc<Texture>> {
+        self.textures.iter().map(|texture| {
+            let image = &self.images[texture.image];
+            Rc::new(Texture::with_options(image.width, image.height, &image.data, texture.options))
+        }).collect()
+    }
+}
+
+impl GltfPrimitive {
+    /// Upload the primitive to the graphics card. Skinned primitives
+    /// use the layout of `mesh::SkinnedVertex`. Morph targets are blended
+    /// on the GPU, unless there are more than `mesh::MAX_MORPH_TARGETS`
+    pub fn to_mesh(&self) -> Result<Mesh> {
+        if self.joints.is_empty() {
+            return self.upload(&self.vertices);
+        }
+
+        let vertices: Vec<SkinnedVertex> = self.vertices.iter()
+            .zip(self.joints.iter().zip(self.weights.iter()))
+            .map(|(&vertex, (&joints, &weights))| SkinnedVertex::new(vertex, joints, weights))
+            .collect();
+
+        self.upload(&vertices)
+    }
+
+    // Upload the primitive with the specified vertices
+    fn upload<V: mesh::VertexLayout>(&self, vertices: &[V]) -> Result<Mesh> {
+        if self.targets.is_empty() {
+            return Ok(Mesh::new(self.primitive, vertices, Some(&self.indices)));
+        }
+
+        let mode = if self.targets.len() <= mesh::MAX_MORPH_TARGETS { MorphMode::Gpu } else { MorphMode::Cpu };
+
+        // Both vertex types have positions and normals
+        Mesh::with_morph_targets(self.primitive, vertices, Some(&self.indices), &self.targets, mode)
+    }
+}
+
+impl GltfMaterial {
+    /// Convert the material, using the specified textures,
+    /// as returned by `Gltf::load_textures`
+    pub fn to_material(&self, textures: &[Rc<Texture>]) -> Material {
+        let texture = |index: Option<usize>| index.and_then(|i| textures.get(i)).cloned();
+
+        Material {
+            base_color: self.base_color,
+            base_color_texture: texture(self.base_color_texture),
+            metallic: self.metallic,
+            roughness: self.roughness,
+            metallic_roughness_texture: texture(self.metallic_roughness_texture),
+            normal_texture: texture(self.normal_texture),
+            normal_scale: self.normal_scale,
+            occlusion_texture: texture(self.occlusion_texture),
+            occlusion_strength: self.occlusion_strength,
+            emissive: self.emissive,
+            emissive_texture: texture(self.emissive_texture),
+            alpha_mode: self.alpha_mode,
+            double_sided: self.double_sided
+        }
+    }
+}
+
+impl Transform {
+    /// Compute the matrix of the transform
+    pub fn matrix(&self) -> Mat4 {
+        match *self {
+            Transform::Matrix(matrix) => matrix,
+            Transform::Decomposed { translation, rotation, scale } => {
+                Mat4::from_translation(translation) * Mat4::from(rotation) * Mat4::from_nonuniform_scale(scale.x, scale.y, scale.z)
+            }
+        }
+    }
+}
+
+impl GltfCamera {
+    /// Compute the projection matrix of the camera. The aspect
+    /// ratio is used when the camera does not specify one
+    pub fn projection_matrix(&self, aspect_ratio: f32) -> Mat4 {
+        match self.projection {
+            Projection::Perspective { yfov, aspect_ratio: aspect, znear, zfar } => {
+                let aspect = aspect.unwrap_or(aspect_ratio);
+
+                match zfar {
+                    Some(zfar) => cgmath::perspective(cgmath::Rad(yfov), aspect, znear, zfar),
+                    None => {
+                        let f = 1.0 / (yfov / 2.0).tan();
+
+                        Mat4::new(
+                            f / aspect, 0.0, 0.0, 0.0,
+                            0.0, f, 0.0, 0.0,
+                            0.0, 0.0, -1.0, -1.0,
+                            0.0, 0.0, -2.0 * znear, 0.0
+                        )
+                    }
+                }
+            },
+            Projection::Orthographic { xmag, ymag, znear, zfar } => cgmath::ortho(-xmag, xmag, -ymag, ymag, znear, zfar)
+        }
+    }
+}
+
+impl GltfLight {
+    /// Convert the light, placed by the transform of its node. The
+    /// intensity is used as the strength, with an inverse square falloff
+    pub fn to_light(&self, transform: Mat4) -> Light {
+        let position = (transform * Vec4::new(0.0, 0.0, 0.0, 1.0)).truncate();
+        let direction = (transform * Vec4::new(0.0, 0.0, -1.0, 0.0)).truncate().normalize();
+
+        match self.kind {
+            LightKind::Directional => Directional::new(direction, self.color, self.intensity).into(),
+            LightKind::Point => {
+                Point::new(position, self.color, self.intensity)
+                    .attenuation(1.0, 0.0, 1.0)
+                    .into()
+            },
+            LightKind::Spot { inner_cone, outer_cone } => {
+                Spot::new(position, direction, self.color, self.intensity)
+                    .cone(inner_cone.to_degrees(), outer_cone.to_degrees())
+                    .attenuation(1.0, 0.0, 1.0)
+                    .into()
+            }
+        }
+    }
+}
+
+/// A glTF asset uploaded to the graphics card, ready to be rendered.
+/// The transforms of its nodes can be changed between frames, for
+/// instance by `animation::Animation::apply`
+pub struct GltfModel {
+    pub nodes: Vec<GltfNode>,
+
+    /// Nodes of the scene being rendered
+    pub roots: Vec<usize>,
+
+    meshes: Vec<Vec<(Mesh, Option<usize>)>>,
+    materials: Vec<Material>,
+    skins: Vec<Skin>
+}
+
+impl GltfModel {
+    /// Upload the meshes, textures and materials of an asset, to render
+    /// its default scene. Its skins must have at most `animation::MAX_JOINTS`
+    /// joints
+    pub fn new(gltf: &Gltf) -> Result<GltfModel> {
+        for (i, skin) in gltf.skins.iter().enumerate() {
+            if skin.joints.len() > MAX_JOINTS {
+                return Err(malformed(&format!("skin {}", i), &format!("has more than {} joints", MAX_JOINTS)));
+            }
+        }
+
+        let textures = gltf.load_textures();
+
+        let meshes = try!(gltf.meshes.iter().map(|mesh| {
+            mesh.primitives.iter().map(|primitive| Ok((try!(primitive.to_mesh()), primitive.material))).collect()
+        }).collect());
+
+        Ok(GltfModel {
+            nodes: gltf.nodes.clone(),
+            roots: gltf.default_scene(),
+            meshes: meshes,
+            materials: gltf.materials.iter().map(|material| material.to_material(&textures)).collect(),
+            skins: gltf.skins.clone()
+        })
+    }
+
+    /// Get the materials of the model
+    pub fn materials(&self) -> &[Material] {
+        &self.materials
+    }
+
+    /// Compute the transforms of the nodes relative to the
+    /// scene, for the nodes being rendered
+    pub fn world_transforms(&self) -> Vec<Option<Mat4>> {
+        world_transforms(&self.nodes, &self.roots)
+    }
+
+    /// Render the scene, placed by the specified transform. Skinned
+    /// meshes follow the current pose of their skeleton, and morph targets
+    /// are blended with the weights of the nodes. The default shading
+    /// is used again afterwards
+    pub fn render(&self, ctx: &Context, transform: Mat4) {
+        let default_material = Material::new();
+        let transforms = self.world_transforms();
+
+        for (node, world) in self.nodes.iter().zip(transforms.iter()) {
+            let (mesh, world) = match (node.mesh, *world) {
+                (Some(mesh), Some(world)) => (mesh, world),
+                _ => continue
+            };
+
+            ctx.set_model(transform * world);
+
+            // The number of joints was checked by `new`
+            if let Some(skin) = node.skin {
+                let _ = ctx.set_skin(Some(&self.skins[skin].joint_matrices(&transforms, world)));
+            }
+
+            for &(ref primitive, material) in self.meshes[mesh].iter() {
+                ctx.set_material(material.map(|i| &self.materials[i]).unwrap_or(&default_material));
+
+                match primitive.morph_mode() {
+                    Some(MorphMode::Gpu) => ctx.set_morph_weights(Some(&node.weights)),
+                    Some(MorphMode::Cpu) => primitive.set_morph_weights(&node.weights),
+                    None => ()
+                }
+
+                primitive.render();
+
+                if primitive.morph_mode() == Some(MorphMode::Gpu) {
+                    ctx.set_morph_weights(None);
+                }
+            }
+
+            if node.skin.is_some() {
+                let _ = ctx.set_skin(None);
+            }
+        }
+
+        ctx.clear_material();
+    }
+}
+
+/// Compute the transforms of the nodes relative to the scene, for the
+/// nodes reachable from the specified roots. The nodes must form a
+/// hierarchy without cycles, like the ones of a loaded asset
+pub fn world_transforms(nodes: &[GltfNode], roots: &[usize]) -> Vec<Option<Mat4>> {
+    let mut transforms = vec![None; nodes.len()];
+    let mut stack: Vec<(usize, Mat4)> = roots.iter().map(|&root| (root, math::mat4_identity())).collect();
+
+    // The hierarchy was checked to be a forest when loaded
+    while let Some((node, parent)) = stack.pop() {
+        let world = parent * nodes[node].transform.matrix();
+        transforms[node] = Some(world);
+
+        stack.extend(nodes[node].children.iter().map(|&child| (child, world)));
+    }
+
+    transforms
+}
+
+// Place a camera like the node it is attached to
+fn camera_from_transform(transform: Mat4) -> Camera {
+    let mut camera = Camera::new((transform * Vec4::new(0.0, 0.0, 0.0, 1.0)).truncate());
+
+    camera.target = (transform * Vec4::new(0.0, 0.0, -1.0, 0.0)).truncate().normalize();
+    camera.up = (transform * Vec4::new(0.0, 1.0, 0.0, 0.0)).truncate().normalize();
+
+    camera
+}
+
+// Reads the objects of an asset, once its buffers are available
+struct Importer<'a> {
+    json: &'a Value,
+    buffers: Vec<Vec<u8>>,
+    dir: Option<&'a Path>
+}
+
+impl<'a> Importer<'a> {
+    fn import(&self) -> Result<Gltf> {
+        let json = self.json;
+
+        let images = try!(self.images());
+        let textures = try!(self.textures(images.len()));
+        let materials = try!(self.materials(textures.len()));
+        let meshes = try!(self.meshes(materials.len()));
+        let cameras = try!(self.cameras());
+        let lights = try!(self.lights());
+        let skins = try!(self.skins());
+        let animations = try!(self.animations());
+
+        let mut nodes: Vec<GltfNode> = try!(
+            array(json, "nodes").and_then(|nodes| nodes.iter().enumerate().map(|(i, node)| self.node(i, node)).collect())
+        );
+
+        // Check the references of the nodes, and that they form a forest
+        let mut parents = vec![None; nodes.len()];
+
+        for (i, node) in nodes.iter_mut().enumerate() {
+            let context = format!("node {}", i);
+
+            try!(check_index(&context, "mesh", node.mesh, meshes.len()));
+
+            // Nodes weight the morph targets of their mesh
+            let targets = node.mesh.map(|mesh| &meshes[mesh].weights[..]).unwrap_or(&[]);
+
+            if node.weights.is_empty() {
+                node.weights = targets.to_vec();
+            }
+            else if node.weights.len() != targets.len() {
+                return Err(malformed(&context, "has weights not matching the morph targets of its mesh"));
+            }
+        }
+
+        for (i, node) in nodes.iter().enumerate() {
+            let context = format!("node {}", i);
+
+            try!(check_index(&context, "skin", node.skin, skins.len()));
+
+            // Skinned primitives use the joints of the skin of their node
+            if let (Some(mesh), Some(skin)) = (node.mesh, node.skin) {
+                let joints = skins[skin].joints.len();
+
+                for primitive in meshes[mesh].primitives.iter() {
+                    if primitive.joints.iter().any(|j| j.iter().any(|&joint| joint as usize >= joints)) {
+                        return Err(malformed(&context, "has a mesh using joints missing from its skin"));
+                    }
+                }
+            }
+            try!(check_index(&context, "camera", node.camera, cameras.len()));
+            try!(check_index(&context, "light", node.light, lights.len()));
+
+            for &child in node.children.iter() {
+                try!(check_index(&context, "child", Some(child), nodes.len()));
+
+                if parents[child].is_some() {
+                    return Err(malformed(&format!("node {}", child), "has several parents"));
+                }
+
+                parents[child] = Some(i);
+            }
+        }
+
+        for i in 0..nodes.len() {
+            let mut node = i;
+
+            for _ in 0..nodes.len() {
+                node = match parents[node] {
+                    Some(parent) => parent,
+                    None => break
+                };
+
+                if node == i {
+                    return Err(malformed(&format!("node {}", i), "is its own ancestor"));
+                }
+            }
+        }
+
+        let mut scenes = Vec::new();
+
+        for (i, scene) in try!(array(json, "scenes")).iter().enumerate() {
+            let context = format!("scene {}", i);
+            let roots = try!(get_indices(scene, "nodes", &context));
+
+            for &root in roots.iter() {
+                try!(check_index(&context, "node", Some(root), nodes.len()));
+            }
+
+            scenes.push(GltfScene {
+                name: get_name(scene),
+                nodes: roots
+            });
+        }
+
+        let scene = try!(get_usize(json, "scene"));
+        try!(check_index("asset", "scene", scene, scenes.len()));
+
+        // Joints and animated properties belong to nodes
+        for (i, skin) in skins.iter().enumerate() {
+            let context = format!("skin {}", i);
+
+            for &joint in skin.joints.iter() {
+                try!(check_index(&context, "joint", Some(joint), nodes.len()));
+            }
+
+            try!(check_index(&context, "skeleton", skin.skeleton, nodes.len()));
+        }
+
+        for (i, animation) in animations.iter().enumerate() {
+            let context = format!("animation {}", i);
+
+            for channel in animation.channels.iter() {
+                try!(check_index(&context, "node", Some(channel.node), nodes.len()));
+
+                // Weights are animated for each morph target
+                if channel.property == Property::Weights {
+                    let per_keyframe = if channel.interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+
+                    if nodes[channel.node].weights.is_empty()
+                        || channel.values.len() != channel.times.len() * per_keyframe * nodes[channel.node].weights.len() {
+                        return Err(malformed(&context, "has weights not matching the morph targets of their node"));
+                    }
+                }
+            }
+        }
+
+        Ok(Gltf {
+            meshes: meshes,
+            images: images,
+            textures: textures,
+            materials: materials,
+            nodes: nodes,
+            scenes: scenes,
+            scene: scene,
+            cameras: cameras,
+            lights: lights,
+            skins: skins,
+            animations: animations
+        })
+    }
+
+    // Decode the images, stored in files, data URIs or buffer views
+    fn images(&self) -> Result<Vec<Image>> {
+        let mut images = Vec::new();
+
+        for (i, image) in try!(array(self.json, "images")).iter().enumerate() {
+            let context = format!("image {}", i);
+
+            let data = match (image.get("uri").and_then(|uri| uri.as_str()), try!(get_usize(image, "bufferView"))) {
+                (Some(uri), _) => try!(read_uri(uri, self.dir, &context)),
+                (None, Some(view)) => try!(self.buffer_view(view)).to_vec(),
+                (None, None) => return Err(malformed(&context, "no data"))
+            };
+
+            let decoded = try!(imagefmt::read_from(&mut Cursor::new(data), imagefmt::ColFmt::RGBA).map_err(|err| {
+                malformed(&context, &format!("cannot be decoded ({:?})", err))
+            }));
+
+            images.push(Image::new(decoded.w as u32, decoded.h as u32, decoded.buf));
+        }
+
+        Ok(images)
+    }
+
+    fn textures(&self, images: usize) -> Result<Vec<GltfTexture>> {
+        let samplers = try!(array(self.json, "samplers"));
+        let mut textures = Vec::new();
+
+        for (i, texture) in try!(array(self.json, "textures")).iter().enumerate() {
+            let context = format!("texture {}", i);
+
+            let image = try!(required(&context, "source", try!(get_usize(texture, "source"))));
+            try!(check_index(&context, "source", Some(image), images));
+
+            let options = match try!(get_usize(texture, "sampler")) {
+                Some(sampler) => {
+                    try!(check_index(&context, "sampler", Some(sampler), samplers.len()));
+                    try!(sampler_options(&samplers[sampler], &format!("sampler {}", sampler)))
+                },
+                None => TextureOptions::new().filter(Filter::Trilinear)
+            };
+
+            textures.push(GltfTexture {
+                image: image,
+                options: options
+            });
+        }
+
+        Ok(textures)
+    }
+
+    fn materials(&self, textures: usize) -> Result<Vec<GltfMaterial>> {
+        let mut materials = Vec::new();
+
+        for (i, value) in try!(array(self.json, "materials")).iter().enumerate() {
+            let context = format!("material {}", i);
+            let pbr = value.get("pbrMetallicRoughness").unwrap_or(&Value::Null);
+
+            // Texture references, and the factor stored along with some of them
+            let texture = |value: &Value, key: &str, factor: &str, default: f32| -> Result<(Option<usize>, f32)> {
+                let info = match value.get(key) {
+                    Some(info) => info,
+                    None => return Ok((None, default))
+                };
+
+                let index = try!(required(&context, key, try!(get_usize(info, "index"))));
+                try!(check_index(&context, key, Some(index), textures));
+
+                Ok((Some(index), try!(get_f32(info, factor, default, &context))))
+            };
+
+            let base_color = try!(get_floats(pbr, "baseColorFactor", &[1.0, 1.0, 1.0, 1.0], &context));
+            let emissive = try!(get_floats(value, "emissiveFactor", &[0.0, 0.0, 0.0], &context));
+
+            let (base_color_texture, _) = try!(texture(pbr, "baseColorTexture", "", 1.0));
+            let (metallic_roughness_texture, _) = try!(texture(pbr, "metallicRoughnessTexture", "", 1.0));
+            let (normal_texture, normal_scale) = try!(texture(value, "normalTexture", "scale", 1.0));
+            let (occlusion_texture, occlusion_strength) = try!(texture(value, "occlusionTexture", "strength", 1.0));
+            let (emissive_texture, _) = try!(texture(value, "emissiveTexture", "", 1.0));
+
+            let alpha_mode = match value.get("alphaMode").and_then(|mode| mode.as_str()).unwrap_or("OPAQUE") {
+                "OPAQUE" => AlphaMode::Opaque,
+                "MASK" => AlphaMode::Mask(try!(get_f32(value, "alphaCutoff", 0.5, &context))),
+                "BLEND" => AlphaMode::Blend,
+                _ => return Err(malformed(&context, "unknown alpha mode"))
+            };
+
+            materials.push(GltfMaterial {
+                name: get_name(value),
+                base_color: [base_color[0], base_color[1], base_color[2], base_color[3]],
+                base_color_texture: base_color_texture,
+                metallic: try!(get_f32(pbr, "metallicFactor", 1.0, &context)),
+                roughness: try!(get_f32(pbr, "roughnessFactor", 1.0, &context)),
+                metallic_roughness_texture: metallic_roughness_texture,
+                normal_texture: normal_texture,
+                normal_scale: normal_scale,
+                occlusion_texture: occlusion_texture,
+                occlusion_strength: occlusion_strength,
+                emissive: [emissive[0], emissive[1], emissive[2]],
+                emissive_texture: emissive_texture,
+                alpha_mode: alpha_mode,
+                double_sided: value.get("doubleSided").and_then(|v| v.as_bool()).unwrap_or(false)
+            });
+        }
+
+        Ok(materials)
+    }
+
+    fn meshes(&self, materials: usize) -> Result<Vec<GltfMesh>> {
+        let mut meshes = Vec::new();
+
+        for (i, mesh) in try!(array(self.json, "meshes")).iter().enumerate() {
+            let context = format!("mesh {}", i);
+            let mut primitives: Vec<GltfPrimitive> = Vec::new();
+
+            for (j, primitive) in try!(array(mesh, "primitives")).iter().enumerate() {
+                let context = format!("mesh {} primitive {}", i, j);
+                primitives.push(try!(self.primitive(primitive, materials, &context)));
+            }
+
+            // The weights of the mesh apply to the targets of every primitive
+            let targets = primitives.first().map(|primitive| primitive.targets.len()).unwrap_or(0);
+
+            if primitives.iter().any(|primitive| primitive.targets.len() != targets) {
+                return Err(malformed(&context, "has primitives with different numbers of morph targets"));
+            }
+
+            meshes.push(GltfMesh {
+                name: get_name(mesh),
+                primitives: primitives,
+                weights: try!(get_floats(mesh, "weights", &vec![0.0; targets], &context))
+            });
+        }
+
+        Ok(meshes)
+    }
+
+    fn primitive(&self, value: &Value, materials: usize, context: &str) -> Result<GltfPrimitive> {
+        let attributes = value.get("attributes").unwrap_or(&Value::Null);
+        let attribute = |name: &str| get_usize(attributes, name);
+
+        let position = try!(required(context, "POSITION", try!(attribute("POSITION"))));
+        let positions = try!(self.accessor_floats(position, 3));
+        let count = positions.len() / 3;
+
+        let mut vertices: Vec<Vertex> = positions.chunks(3).map(|p| Vertex::new(p[0], p[1], p[2])).collect();
+
+        // Other attributes must have one value per vertex
+        let read = |name: &str, components: &[usize]| -> Result<Option<(usize, Vec<f32>)>> {
+            let accessor = match try!(attribute(name)) {
+                Some(accessor) => accessor,
+                None => return Ok(None)
+            };
+
+            let (size, values) = try!(self.accessor(accessor));
+
+            if !components.contains(&size) || values.len() != count * size {
+                return Err(malformed(context, &format!("invalid {} attribute", name)));
+            }
+
+            Ok(Some((size, values.iter().map(|&(value, _)| value).collect())))
+        };
+
+        let normals = try!(read("NORMAL", &[3]));
+
+        if let Some((_, ref normals)) = normals {
+            for (vertex, n) in vertices.iter_mut().zip(normals.chunks(3)) {
+                vertex.normal = [n[0], n[1], n[2]];
+            }
+        }
+
+        if let Some((_, texcoords)) = try!(read("TEXCOORD_0", &[2])) {
+            for (vertex, t) in vertices.iter_mut().zip(texcoords.chunks(2)) {
+                vertex.texcoords = [t[0], t[1]];
+            }
+        }
+
+        if let Some((size, colors)) = try!(read("COLOR_0", &[3, 4])) {
+            for (vertex, c) in vertices.iter_mut().zip(colors.chunks(size)) {
+                vertex.color = [c[0], c[1], c[2], if size == 4 { c[3] } else { 1.0 }];
+            }
+        }
+
+        // Skinned vertices are influenced by up to four joints
+        let (mut joints, mut weights) = (Vec::new(), Vec::new());
+
+        if let Some(accessor) = try!(attribute("JOINTS_0")) {
+            let values = try!(self.accessor_integers(accessor));
+
+            if values.len() != count * 4 || values.iter().any(|&joint| joint > u16::MAX as u32) {
+                return Err(malformed(context, "invalid JOINTS_0 attribute"));
+            }
+
+            joints = values.chunks(4).map(|j| [j[0] as u16, j[1] as u16, j[2] as u16, j[3] as u16]).collect();
+
+            weights = match try!(read("WEIGHTS_0", &[4])) {
+                Some((_, values)) => values.chunks(4).map(|w| [w[0], w[1], w[2], w[3]]).collect(),
+                None => return Err(malformed(context, "missing WEIGHTS_0 attribute"))
+            };
+        }
+
+        // Morph targets, whose normals are zero where they are missing
+        let mut targets = Vec::new();
+
+        for (t, target) in try!(array(value, "targets")).iter().enumerate() {
+            let read_delta = |name: &str| -> Result<Vec<[f32; 3]>> {
+                let accessor = match try!(get_usize(target, name)) {
+                    Some(accessor) => accessor,
+                    None => return Ok(Vec::new())
+                };
+
+                let deltas = try!(self.accessor_floats(accessor, 3));
+
+                if deltas.len() != count * 3 {
+                    return Err(malformed(context, &format!("invalid {} of morph target {}", name, t)));
+                }
+
+                Ok(deltas.chunks(3).map(|d| [d[0], d[1], d[2]]).collect())
+            };
+
+            let positions = try!(read_delta("POSITION"));
+            let positions = if positions.is_empty() { vec![[0.0; 3]; count] } else { positions };
+
+            targets.push(MorphTarget::new(positions).normals(try!(read_delta("NORMAL"))));
+        }
+
+        if targets.iter().any(|target: &MorphTarget| !target.normals.is_empty()) {
+            for target in targets.iter_mut().filter(|target| target.normals.is_empty()) {
+                target.normals = vec![[0.0; 3]; count];
+            }
+        }
+
+        // Indices, the vertices being used in order without them
+        let indices = match try!(get_usize(value, "indices")) {
+            Some(accessor) => try!(self.accessor_integers(accessor)),
+            None => (0..count as u32).collect()
+        };
+
+        if indices.iter().any(|&index| index as usize >= count) {
+            return Err(malformed(context, "index out of range"));
+        }
+
+        let (primitive, mut indices) = match value.get("mode").and_then(|mode| mode.as_u64()).unwrap_or(4) {
+            0 => (PrimitiveType::Points, indices),
+            1 => (PrimitiveType::Lines, indices),
+            2 => (PrimitiveType::Lines, line_strip(&indices, true)),
+            3 => (PrimitiveType::Lines, line_strip(&indices, false)),
+            4 => (PrimitiveType::Triangles, indices),
+            5 => (PrimitiveType::Triangles, triangle_strip(&indices)),
+            6 => (PrimitiveType::Triangles, triangle_fan(&indices)),
+            _ => return Err(malformed(context, "unknown primitive mode"))
+        };
+
+        // Triangles without normals are flat shaded, so each
+        // triangle gets its own vertices
+        if primitive == PrimitiveType::Triangles && normals.is_none() {
+            let mut flat = Vec::with_capacity(indices.len());
+            let corners: Vec<usize> = indices.chunks(3)
+                .filter(|triangle| triangle.len() == 3)
+                .flat_map(|triangle| triangle.iter().map(|&i| i as usize))
+                .collect();
+
+            for triangle in indices.chunks(3).filter(|triangle| triangle.len() == 3) {
+                let p: Vec<Vec3> = triangle.iter().map(|&i| Vec3::from(vertices[i as usize].position)).collect();
+                let n = (p[1] - p[0]).cross(p[2] - p[0]);
+                let n = if n.magnitude2() > 0.0 { n.normalize() } else { n };
+
+                for &i in triangle.iter() {
+                    flat.push(vertices[i as usize].normal(n.x, n.y, n.z));
+                }
+            }
+
+            if !joints.is_empty() {
+                joints = corners.iter().map(|&i| joints[i]).collect();
+                weights = corners.iter().map(|&i| weights[i]).collect();
+            }
+
+            // Morphed normals are left flat too
+            for target in targets.iter_mut() {
+                target.positions = corners.iter().map(|&i| target.positions[i]).collect();
+                target.normals = Vec::new();
+            }
+
+            vertices = flat;
+            indices = (0..vertices.len() as u32).collect();
+        }
+
+        let material = try!(get_usize(value, "material"));
+        try!(check_index(context, "material", material, materials));
+
+        Ok(GltfPrimitive {
+            primitive: primitive,
+            vertices: vertices,
+            indices: indices,
+            joints: joints,
+            weights: weights,
+            targets: targets,
+            material: material
+        })
+    }
+
+    fn node(&self, index: usize, value: &Value) -> Result<GltfNode> {
+        let context = format!("node {}", index);
+
+        let transform = match value.get("matrix") {
+            Some(_) => {
+                let m = try!(get_floats(value, "matrix", &[0.0; 16], &context));
+
+                Transform::Matrix(Mat4::new(
+                    m[0], m[1], m[2], m[3],
+                    m[4], m[5], m[6], m[7],
+                    m[8], m[9], m[10], m[11],
+                    m[12], m[13], m[14], m[15]
+                ))
+            },
+            None => {
+                let t = try!(get_floats(value, "translation", &[0.0, 0.0, 0.0], &context));
+                let r = try!(get_floats(value, "rotation", &[0.0, 0.0, 0.0, 1.0], &context));
+                let s = try!(get_floats(value, "scale", &[1.0, 1.0, 1.0], &context));
+
+                // Quaternions are stored as (x, y, z, w)
+                Transform::Decomposed {
+                    translation: Vec3::new(t[0], t[1], t[2]),
+                    rotation: Quat::new(r[3], r[0], r[1], r[2]),
+                    scale: Vec3::new(s[0], s[1], s[2])
+                }
+            }
+        };
+
+        let light = value.get("extensions")
+            .and_then(|extensions| extensions.get("KHR_lights_punctual"))
+            .unwrap_or(&Value::Null);
+
+        Ok(GltfNode {
+            name: get_name(value),
+            transform: transform,
+            children: try!(get_indices(value, "children", &context)),
+            mesh: try!(get_usize(value, "mesh")),
+            skin: try!(get_usize(value, "skin")),
+            camera: try!(get_usize(value, "camera")),
+            light: try!(get_usize(light, "light")),
+
+            // Checked against the mesh, whose weights are used without them
+            weights: match value.get("weights") {
+                None => Vec::new(),
+                Some(&Value::Array(ref weights)) => weights.iter().filter_map(|w| w.as_f64()).map(|w| w as f32).collect(),
+                Some(_) => return Err(malformed(&context, "invalid weights"))
+            }
+        })
+    }
+
+    fn cameras(&self) -> Result<Vec<GltfCamera>> {
+        let mut cameras = Vec::new();
+
+        for (i, camera) in try!(array(self.json, "cameras")).iter().enumerate() {
+            let context = format!("camera {}", i);
+
+            let projection = match camera.get("type").and_then(|kind| kind.as_str()) {
+                Some("perspective") => {
+                    let value = camera.get("perspective").unwrap_or(&Value::Null);
+
+                    Projection::Perspective {
+                        yfov: try!(get_f32(value, "yfov", f32::NAN, &context)),
+                        aspect_ratio: value.get("aspectRatio").and_then(|v| v.as_f64()).map(|v| v as f32),
+                        znear: try!(get_f32(value, "znear", f32::NAN, &context)),
+                        zfar: value.get("zfar").and_then(|v| v.as_f64()).map(|v| v as f32)
+                    }
+                },
+                Some("orthographic") => {
+                    let value = camera.get("orthographic").unwrap_or(&Value::Null);
+
+                    Projection::Orthographic {
+                        xmag: try!(get_f32(value, "xmag", f32::NAN, &context)),
+                        ymag: try!(get_f32(value, "ymag", f32::NAN, &context)),
+                        znear: try!(get_f32(value, "znear", f32::NAN, &context)),
+                        zfar: try!(get_f32(value, "zfar", f32::NAN, &context))
+                    }
+                },
+                _ => return Err(malformed(&context, "unknown projection"))
+            };
+
+            cameras.push(GltfCamera {
+                name: get_name(camera),
+                projection: projection
+            });
+        }
+
+        Ok(cameras)
+    }
+
+    fn lights(&self) -> Result<Vec<GltfLight>> {
+        let extension = self.json.get("extensions")
+            .and_then(|extensions| extensions.get("KHR_lights_punctual"))
+            .unwrap_or(&Value::Null);
+
+        let mut lights = Vec::new();
+
+        for (i, light) in try!(array(extension, "lights")).iter().enumerate() {
+            let context = format!("light {}", i);
+
+            let kind = match light.get("type").and_then(|kind| kind.as_str()) {
+                Some("directional") => LightKind::Directional,
+                Some("point") => LightKind::Point,
+                Some("spot") => {
+                    let spot = light.get("spot").unwrap_or(&Value::Null);
+
+                    LightKind::Spot {
+                        inner_cone: try!(get_f32(spot, "innerConeAngle", 0.0, &context)),
+                        outer_cone: try!(get_f32(spot, "outerConeAngle", f32::consts::FRAC_PI_4, &context))
+                    }
+                },
+                _ => return Err(malformed(&context, "unknown light type"))
+            };
+
+            let color = try!(get_floats(light, "color", &[1.0, 1.0, 1.0], &context));
+
+            lights.push(GltfLight {
+                name: get_name(light),
+                kind: kind,
+                color: [color[0], color[1], color[2]],
+                intensity: try!(get_f32(light, "intensity", 1.0, &context)),
+                range: light.get("range").and_then(|v| v.as_f64()).map(|v| v as f32)
+            });
+        }
+
+        Ok(lights)
+    }
+
+    fn skins(&self) -> Result<Vec<Skin>> {
+        let mut skins = Vec::new();
+
+        for (i, skin) in try!(array(self.json, "skins")).iter().enumerate() {
+            let context = format!("skin {}", i);
+            let joints = try!(get_indices(skin, "joints", &context));
+
+            if joints.is_empty() {
+                return Err(malformed(&context, "has no joints"));
+            }
+
+            if joints.len() > MAX_JOINTS {
+                return Err(malformed(&context, &format!("has more than {} joints", MAX_JOINTS)));
+            }
+
+            // The bind pose defaults to the identity
+            let inverse_bind_matrices = match try!(get_usize(skin, "inverseBindMatrices")) {
+                Some(accessor) => {
+                    let values = try!(self.accessor_floats(accessor, 16));
+
+                    if values.len() < joints.len() * 16 {
+                        return Err(malformed(&context, "misses inverse bind matrices"));
+                    }
+
+                    values.chunks(16).map(|m| Mat4::new(
+                        m[0], m[1], m[2], m[3],
+                        m[4], m[5], m[6], m[7],
+                        m[8], m[9], m[10], m[11],
+                        m[12], m[13], m[14], m[15]
+                    )).collect()
+                },
+                None => vec![math::mat4_identity(); joints.len()]
+            };
+
+            skins.push(Skin {
+                name: get_name(skin),
+                joints: joints,
+                inverse_bind_matrices: inverse_bind_matrices,
+                skeleton: try!(get_usize(skin, "skeleton"))
+            });
+        }
+
+        Ok(skins)
+    }
+
+    fn animations(&self) -> Result<Vec<Animation>> {
+        let mut animations = Vec::new();
+
+        for (i, animation) in try!(array(self.json, "animations")).iter().enumerate() {
+            let context = format!("animation {}", i);
+            let samplers = try!(array(animation, "samplers"));
+            let mut channels = Vec::new();
+
+            for channel in try!(array(animation, "channels")).iter() {
+                let target = channel.get("target").unwrap_or(&Value::Null);
+
+                // Channels without a node are meant for extensions
+                let node = match try!(get_usize(target, "node")) {
+                    Some(node) => node,
+                    None => continue
+                };
+
+                let (property, components) = match target.get("path").and_then(|path| path.as_str()) {
+                    Some("translation") => (Property::Translation, 3),
+                    Some("rotation") => (Property::Rotation, 4),
+                    Some("scale") => (Property::Scale, 3),
+                    Some("weights") => (Property::Weights, 1),
+                    Some(_) => continue,
+                    None => return Err(malformed(&context, "channel without a path"))
+                };
+
+                let sampler = try!(required(&context, "sampler", try!(get_usize(channel, "sampler"))));
+                try!(check_index(&context, "sampler", Some(sampler), samplers.len()));
+
+                let sampler = &samplers[sampler];
+
+                let interpolation = match sampler.get("interpolation").and_then(|v| v.as_str()).unwrap_or("LINEAR") {
+                    "STEP" => Interpolation::Step,
+                    "LINEAR" => Interpolation::Linear,
+                    "CUBICSPLINE" => Interpolation::CubicSpline,
+                    _ => return Err(malformed(&context, "unknown interpolation"))
+                };
+
+                let input = try!(required(&context, "sampler input", try!(get_usize(sampler, "input"))));
+                let output = try!(required(&context, "sampler output", try!(get_usize(sampler, "output"))));
+
+                let times = try!(self.accessor_floats(input, 1));
+                let values = try!(self.accessor_floats(output, components));
+                let per_keyframe = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+
+                // Weights have one value per morph target of their node, which
+                // is checked along with the nodes
+                let elements = times.len() * per_keyframe * components;
+                let matching = match property {
+                    Property::Weights => values.len() % elements.max(1) == 0,
+                    _ => values.len() == elements
+                };
+
+                if !matching {
+                    return Err(malformed(&context, "has a sampler whose output does not match its input"));
+                }
+
+                if times.windows(2).any(|pair| pair[1] < pair[0]) {
+                    return Err(malformed(&context, "has keyframes out of order"));
+                }
+
+                channels.push(Channel {
+                    node: node,
+                    property: property,
+                    interpolation: interpolation,
+                    times: times,
+                    values: values
+                });
+            }
+
+            animations.push(Animation {
+                name: get_name(animation),
+                channels: channels
+            });
+        }
+
+        Ok(animations)
+    }
+
+    // Get the bytes of a buffer view
+    fn buffer_view(&self, index: usize) -> Result<&[u8]> {
+        let context = format!("buffer view {}", index);
+        let view = try!(self.view(index));
+
+        let buffer = try!(required(&context, "buffer", try!(get_usize(view, "buffer"))));
+        try!(check_index(&context, "buffer", Some(buffer), self.buffers.len()));
+
+        let offset = try!(get_usize(view, "byteOffset")).unwrap_or(0);
+        let length = try!(required(&context, "byteLength", try!(get_usize(view, "byteLength"))));
+
+        match self.buffers[buffer].get(offset..offset + length) {
+            Some(data) => Ok(data),
+            None => Err(malformed(&context, "exceeds its buffer"))
+        }
+    }
+
+    fn view(&self, index: usize) -> Result<&Value> {
+        let views = try!(array(self.json, "bufferViews"));
+        try!(check_index("asset", "buffer view", Some(index), views.len()));
+
+        Ok(&views[index])
+    }
+
+    // Read the values of an accessor as floats, with
+    // the specified number of components
+    fn accessor_floats(&self, index: usize, components: usize) -> Result<Vec<f32>> {
+        let (size, values) = try!(self.accessor(index));
+
+        if size != components {
+            return Err(malformed(&format!("accessor {}", index), "has an unexpected type"));
+        }
+
+        Ok(values.iter().map(|&(value, _)| value).collect())
+    }
+
+    // Read the values of an integer accessor
+    fn accessor_integers(&self, index: usize) -> Result<Vec<u32>> {
+        let (_, values) = try!(self.accessor(index));
+
+        values.iter().map(|&(_, integer)| match integer {
+            Some(integer) => Ok(integer),
+            None => Err(malformed(&format!("accessor {}", index), "is not made of integers"))
+        }).collect()
+    }
+
+    // Read the values of an accessor, returning their number of
+    // components, and each component both as a float (normalized if
+    // needed) and as an integer if it is not normalized
+    fn accessor(&self, index: usize) -> Result<(usize, Vec<(f32, Option<u32>)>)> {
+        let context = format!("accessor {}", index);
+        let accessors = try!(array(self.json, "accessors"));
+        try!(check_index("asset", "accessor", Some(index), accessors.len()));
+
+        let accessor = &accessors[index];
+
+        let count = try!(required(&context, "count", try!(get_usize(accessor, "count"))));
+        let kind = try!(required(&context, "componentType", accessor.get("componentType").and_then(|v| v.as_u64())));
+        let normalized = accessor.get("normalized").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let components = match accessor.get("type").and_then(|v| v.as_str()) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") | Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(malformed(&context, "unknown type"))
+        };
+
+        let component_size = match kind {
+            BYTE | UNSIGNED_BYTE => 1,
+            SHORT | UNSIGNED_SHORT => 2,
+            UNSIGNED_INT | FLOAT => 4,
+            _ => return Err(malformed(&context, "unknown component type"))
+        };
+
+        // Accessors without a buffer view are filled with zeros
+        let mut values = match try!(get_usize(accessor, "bufferView")) {
+            Some(view) => {
+                let data = try!(self.buffer_view(view));
+                let offset = try!(get_usize(accessor, "byteOffset")).unwrap_or(0);
+                let stride = try!(get_usize(try!(self.view(view)), "byteStride")).unwrap_or(components * component_size);
+
+                try!(read_elements(data, offset, stride, kind, components, count).map_err(|msg| malformed(&context, msg)))
+            },
+            None => match count.checked_mul(components) {
+                Some(len) => vec![0.0f64; len],
+                None => return Err(malformed(&context, "has too many elements"))
+            }
+        };
+
+        // Sparse accessors replace some of the values
+        if let Some(sparse) = accessor.get("sparse") {
+            let sparse_count = try!(required(&context, "sparse count", try!(get_usize(sparse, "count"))));
+            let indices = sparse.get("indices").unwrap_or(&Value::Null);
+            let replacements = sparse.get("values").unwrap_or(&Value::Null);
+
+            let index_kind = try!(required(&context, "sparse indices", indices.get("componentType").and_then(|v| v.as_u64())));
+            let index_view = try!(required(&context, "sparse indices", try!(get_usize(indices, "bufferView"))));
+            let value_view = try!(required(&context, "sparse values", try!(get_usize(replacements, "bufferView"))));
+
+            if sparse_count > count {
+                return Err(malformed(&context, "has more sparse values than elements"));
+            }
+
+            let index_size = match index_kind {
+                UNSIGNED_BYTE => 1,
+                UNSIGNED_SHORT => 2,
+                UNSIGNED_INT => 4,
+                _ => return Err(malformed(&context, "invalid sparse index type"))
+            };
+
+            let positions = try!(read_elements(
+                try!(self.buffer_view(index_view)),
+                try!(get_usize(indices, "byteOffset")).unwrap_or(0),
+                index_size,
+                index_kind,
+                1,
+                sparse_count
+            ).map_err(|msg| malformed(&context, msg)));
+
+            let sparse_values = try!(read_elements(
+                try!(self.buffer_view(value_view)),
+                try!(get_usize(replacements, "byteOffset")).unwrap_or(0),
+                components * component_size,
+                kind,
+                components,
+                sparse_count
+            ).map_err(|msg| malformed(&context, msg)));
+
+            for (i, &position) in positions.iter().enumerate() {
+                let position = position as usize;
+
+                if position >= count {
+                    return Err(malformed(&context, "sparse index out of range"));
+                }
+
+                values[position * components..(position + 1) * components]
+                    .copy_from_slice(&sparse_values[i * components..(i + 1) * components]);
+            }
+        }
+
+        // Normalized integers are mapped to [0, 1] or [-1, 1]
+        let values = values.into_iter().map(|value| {
+            if kind == FLOAT {
+                return (value as f32, None);
+            }
+
+            if !normalized {
+                return (value as f32, if value >= 0.0 { Some(value as u32) } else { None });
+            }
+
+            let normalized = match kind {
+                BYTE => (value / 127.0).max(-1.0),
+                UNSIGNED_BYTE => value / 255.0,
+                SHORT => (value / 32767.0).max(-1.0),
+                UNSIGNED_SHORT => value / 65535.0,
+                _ => value / 4294967295.0
+            };
+
+            (normalized as f32, None)
+        }).collect();
+
+        Ok((components, values))
+    }
+}
+
+// Read count elements of the specified component type. The elements
+// are checked to fit in the data before anything is allocated for them
+fn read_elements(data: &[u8], offset: usize, stride: usize, kind: u64, components: usize, count: usize) -> ::std::result::Result<Vec<f64>, &'static str> {
+    let component_size = match kind {
+        BYTE | UNSIGNED_BYTE => 1,
+        SHORT | UNSIGNED_SHORT => 2,
+        _ => 4
+    };
+
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Elements may be interleaved, but must not overlap
+    if stride < components * component_size {
+        return Err("has an invalid byte stride");
+    }
+
+    let end = stride.checked_mul(count - 1)
+        .and_then(|n| n.checked_add(offset))
+        .and_then(|n| n.checked_add(components * component_size));
+
+    match end {
+        Some(end) if end <= data.len() => (),
+        _ => return Err("exceeds its buffer view")
+    }
+
+    let mut values = vec![0.0f64; count * components];
+
+    for i in 0..count {
+        for c in 0..components {
+            let at = offset + stride * i + c * component_size;
+
+            values[i * components + c] = match kind {
+                BYTE => data[at] as i8 as f64,
+                UNSIGNED_BYTE => data[at] as f64,
+                SHORT => (data[at] as u16 | (data[at + 1] as u16) << 8) as i16 as f64,
+                UNSIGNED_SHORT => (data[at] as u16 | (data[at + 1] as u16) << 8) as f64,
+                UNSIGNED_INT => read_u32(data, at) as f64,
+                _ => f32::from_bits(read_u32(data, at)) as f64
+            };
+        }
+    }
+
+    Ok(values)
+}
+
+// Convert a sampler into texture options
+fn sampler_options(sampler: &Value, context: &str) -> Result<TextureOptions> {
+    let wrap = |key: &str| match sampler.get(key).and_then(|v| v.as_u64()).unwrap_or(10497) {
+        33071 => Ok(Wrap::ClampToEdge),
+        33648 => Ok(Wrap::MirroredRepeat),
+        10497 => Ok(Wrap::Repeat),
+        _ => Err(malformed(context, "unknown wrap mode"))
+    };
+
+    let min_filter = sampler.get("minFilter").and_then(|v| v.as_u64());
+    let mag_filter = sampler.get("magFilter").and_then(|v| v.as_u64());
+
+    // The minification filter tells whether mipmaps are used
+    let (filter, mipmaps) = match (min_filter, mag_filter) {
+        (Some(9728), _) => (Filter::Nearest, false),
+        (Some(9729), _) => (Filter::Linear, false),
+        (Some(9984), _) => (Filter::Nearest, true),
+        (Some(9985), _) => (Filter::Linear, true),
+        (Some(9986), _) | (Some(9987), _) => (Filter::Trilinear, true),
+        (None, Some(9728)) => (Filter::Nearest, true),
+        (None, _) => (Filter::Trilinear, true),
+        _ => return Err(malformed(context, "unknown filter"))
+    };
+
+    Ok(TextureOptions::new()
+        .wrap_s(try!(wrap("wrapS")))
+        .wrap_t(try!(wrap("wrapT")))
+        .filter(filter)
+        .mipmaps(mipmaps))
+}
+
+// Convert a line loop or a line strip into a list of lines
+fn line_strip(indices: &[u32], closed: bool) -> Vec<u32> {
+    let mut lines: Vec<u32> = indices.windows(2).flat_map(|pair| pair.to_vec()).collect();
+
+    if closed && indices.len() > 2 {
+        lines.extend_from_slice(&[indices[indices.len() - 1], indices[0]]);
+    }
+
+    lines
+}
+
+// Convert a triangle strip into a list of triangles,
+// keeping the winding order of every other triangle
+fn triangle_strip(indices: &[u32]) -> Vec<u32> {
+    indices.windows(3).enumerate().flat_map(|(i, t)| {
+        if i % 2 == 0 { vec![t[0], t[1], t[2]] } else { vec![t[1], t[0], t[2]] }
+    }).collect()
+}
+
+// Convert a triangle fan into a list of triangles
+fn triangle_fan(indices: &[u32]) -> Vec<u32> {
+    if indices.len() < 3 {
+        return Vec::new();
+    }
+
+    indices[1..].windows(2).flat_map(|pair| vec![indices[0], pair[0], pair[1]]).collect()
+}
+
+// Split a binary glTF container into its JSON and binary chunks
+fn parse_glb(data: &[u8]) -> Result<(&[u8], Option<&[u8]>)> {
+    if data.len() < 20 || read_u32(data, 4) != 2 {
+        return Err(malformed("binary container", "unsupported version"));
+    }
+
+    let length = (read_u32(data, 8) as usize).min(data.len());
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+
+    while offset + 8 <= length {
+        let chunk_length = read_u32(data, offset) as usize;
+        let kind = read_u32(data, offset + 4);
+
+        let chunk = match data.get(offset + 8..offset + 8 + chunk_length) {
+            Some(chunk) => chunk,
+            None => return Err(malformed("binary container", "truncated chunk"))
+        };
+
+        chunks.push((kind, chunk));
+        offset += 8 + chunk_length;
+    }
+
+    match chunks.first() {
+        Some(&(GLB_JSON_CHUNK, json)) => {
+            let bin = chunks.get(1).and_then(|&(kind, chunk)| if kind == GLB_BIN_CHUNK { Some(chunk) } else { None });
+            Ok((json, bin))
+        },
+        _ => Err(malformed("binary container", "missing JSON chunk"))
+    }
+}
+
+// Read the data of a URI: either embedded (base64 data URI),
+// or a file relative to the asset
+fn read_uri(uri: &str, dir: Option<&Path>, context: &str) -> Result<Vec<u8>> {
+    if uri.starts_with("data:") {
+        return match uri.find(";base64,") {
+            Some(start) => base64_decode(&uri[start + 8..]).ok_or_else(|| malformed(context, "invalid base64 data")),
+            None => Err(malformed(context, "unsupported data URI"))
+        };
+    }
+
+    let dir = match dir {
+        Some(dir) => dir,
+        None => return Err(malformed(context, "external file without a directory"))
+    };
+
+    let mut data = Vec::new();
+    let mut file = try!(File::open(dir.join(percent_decode(uri))).map_err(Error::Io));
+    try!(file.read_to_end(&mut data).map_err(Error::Io));
+
+    Ok(data)
+}
+
+// Decode standard base64, with optional padding
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+
+    for c in text.bytes().filter(|&c| c != b'=' && !(c as char).is_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None
+        };
+
+        bits = bits << 6 | value as u32;
+        count += 6;
+
+        if count >= 8 {
+            count -= 8;
+            data.push((bits >> count) as u8);
+        }
+    }
+
+    Some(data)
+}
+
+// Decode the escaped characters of a relative URI
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = if bytes[i] == b'%' && i + 2 < bytes.len() {
+            u8::from_str_radix(&uri[i + 1..i + 3], 16).ok()
+        }
+        else {
+            None
+        };
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            },
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Little endian 32 bit integer
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    data[at] as u32 | (data[at + 1] as u32) << 8 | (data[at + 2] as u32) << 16 | (data[at + 3] as u32) << 24
+}
+
+// Get an array of objects, which may be missing
+fn array<'a>(value: &'a Value, key: &str) -> Result<&'a [Value]> {
+    match value.get(key) {
+        None => Ok(&[]),
+        Some(&Value::Array(ref values)) => Ok(values),
+        Some(_) => Err(malformed(key, "is not an array"))
+    }
+}
+
+// Get an index or a size, which may be missing
+fn get_usize(value: &Value, key: &str) -> Result<Option<usize>> {
+    match value.get(key) {
+        None => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(v) => Ok(Some(v as usize)),
+            None => Err(malformed(key, "is not a positive integer"))
+        }
+    }
+}
+
+// Get a list of indices, which may be missing
+fn get_indices(value: &Value, key: &str, context: &str) -> Result<Vec<usize>> {
+    try!(array(value, key)).iter().map(|v| match v.as_u64() {
+        Some(v) => Ok(v as usize),
+        None => Err(malformed(context, &format!("invalid {}", key)))
+    }).collect()
+}
+
+// Get a number, or its default value. A NaN default makes it required
+fn get_f32(value: &Value, key: &str, default: f32, context: &str) -> Result<f32> {
+    match value.get(key) {
+        None if !default.is_nan() => Ok(default),
+        None => Err(malformed(context, &format!("missing {}", key))),
+        Some(v) => match v.as_f64() {
+            Some(v) => Ok(v as f32),
+            None => Err(malformed(context, &format!("invalid {}", key)))
+        }
+    }
+}
+
+// Get a list of numbers with the length of the default value
+fn get_floats(value: &Value, key: &str, default: &[f32], context: &str) -> Result<Vec<f32>> {
+    let values = match value.get(key) {
+        None => return Ok(default.to_vec()),
+        Some(&Value::Array(ref values)) => values,
+        Some(_) => return Err(malformed(context, &format!("invalid {}", key)))
+    };
+
+    let floats: Vec<f32> = values.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect();
+
+    if floats.len() != default.len() {
+        return Err(malformed(context, &format!("{} needs {} numbers", key, default.len())));
+    }
+
+    Ok(floats)
+}
+
+fn get_name(value: &Value) -> Option<String> {
+    value.get("name").and_then(|name| name.as_str()).map(|name| name.to_owned())
+}
+
+// Unwrap a property that must be present
+fn required<T>(context: &str, key: &str, value: Option<T>) -> Result<T> {
+    value.ok_or_else(|| malformed(context, &format!("missing {}", key)))
+}
+
+// Check that a reference to another object is valid
+fn check_index(context: &str, kind: &str, index: Option<usize>, count: usize) -> Result<()> {
+    match index {
+        Some(index) if index >= count => Err(malformed(context, &format!("references missing {} {}", kind, index))),
+        _ => Ok(())
+    }
+}
+
+fn malformed(context: &str, msg: &str) -> Error {
+    Error::Gltf(format!("{} {}", context, msg))
+}
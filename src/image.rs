@@ -0,0 +1,408 @@
+use std::cmp;
+use std::fs::File;
+use std::io::{self, Read, BufRead, Write, BufReader, BufWriter};
+use std::path::Path;
+
+use super::{Result, Error};
+
+/// Represents an 8 bit RGBA image stored in main memory.
+/// Rows are ordered from the top of the image to the bottom
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>
+}
+
+impl Image {
+    /// Create a new image from 8 bit RGBA data
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Image {
+        assert_eq!(data.len(), (width * height * 4) as usize, "image data does not match its dimensions");
+
+        Image {
+            width: width,
+            height: height,
+            data: data
+        }
+    }
+
+    /// Read back a rectangle of the currently bound framebuffer
+    pub fn from_framebuffer(x: u32, y: u32, width: u32, height: u32) -> Image {
+        let mut image = Image::new(width, height, super::read_pixels(x, y, width, height));
+
+        // OpenGL returns the bottom row first
+        image.flip_vertical();
+        image
+    }
+
+    /// Get the RGBA value of the pixel at the specified coordinates
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+    }
+
+    /// Flip the image upside down
+    pub fn flip_vertical(&mut self) {
+        let stride = (self.width * 4) as usize;
+        let height = self.height as usize;
+
+        for y in 0..height / 2 {
+            let (top, bottom) = self.data.split_at_mut((height - y - 1) * stride);
+            top[y * stride..(y + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+        }
+    }
+
+    /// Copy a rectangle of the image into a new one
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Image {
+        assert!(x + width <= self.width && y + height <= self.height, "rectangle exceeds the image");
+
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+
+        for row in y..y + height {
+            let start = ((row * self.width + x) * 4) as usize;
+            data.extend_from_slice(&self.data[start..start + (width * 4) as usize]);
+        }
+
+        Image::new(width, height, data)
+    }
+
+    /// Rotate the image by 180 degrees
+    pub fn rotate_half_turn(&mut self) {
+        let pixels = self.data.len() / 4;
+
+        for i in 0..pixels / 2 {
+            let j = pixels - i - 1;
+
+            for c in 0..4 {
+                self.data.swap(i * 4 + c, j * 4 + c);
+            }
+        }
+    }
+
+    /// Save the image to the specified file. The format is
+    /// selected from the extension of the path: PNG (.png) or PPM (.ppm)
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let ext = path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase());
+
+        // Check the format before creating the file
+        let write: fn(&Image, &mut BufWriter<File>) -> io::Result<()> = match ext.as_ref().map(|ext| ext.as_str()) {
+            Some("png") => Image::write_png,
+            Some("ppm") => Image::write_ppm,
+            _ => return Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput, "unsupported image format")))
+        };
+
+        let file = try!(File::create(path).map_err(Error::Io));
+        let mut writer = BufWriter::new(file);
+
+        write(self, &mut writer).and_then(|_| writer.flush()).map_err(Error::Io)
+    }
+
+    /// Encode the image as a PNG file. The pixel data is stored
+    /// without compression
+    pub fn write_png<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        // File signature
+        try!(w.write_all(&[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]));
+
+        // Image header: dimensions, 8 bit depth, RGBA color type,
+        // default compression, filtering and no interlacing
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&be_u32(self.width));
+        header.extend_from_slice(&be_u32(self.height));
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        try!(write_png_chunk(w, b"IHDR", &header));
+
+        // Every row is prefixed by its filter type (none)
+        let stride = (self.width * 4) as usize;
+        let mut raw = Vec::with_capacity((stride + 1) * self.height as usize);
+
+        for row in self.data.chunks(stride) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        try!(write_png_chunk(w, b"IDAT", &zlib_store(&raw)));
+        write_png_chunk(w, b"IEND", &[])
+    }
+
+    /// Encode the image as a binary PPM file. The alpha
+    /// channel is discarded
+    pub fn write_ppm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        try!(write!(w, "P6\n{} {}\n255\n", self.width, self.height));
+
+        let rgb: Vec<u8> = self.data.chunks(4)
+            .flat_map(|px| px[..3].iter().cloned())
+            .collect();
+
+        w.write_all(&rgb)
+    }
+}
+
+// Largest width or height accepted when decoding Radiance images,
+// which is also the limit of run length encoded scanlines
+const MAX_HDR_SIZE: u32 = 0x7fff;
+
+/// Represents a high dynamic range RGB image stored in main memory,
+/// with one linear 32 bit float per channel. Rows are ordered from
+/// the top of the image to the bottom
+pub struct HdrImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<f32>
+}
+
+impl HdrImage {
+    /// Create a new image from floating point RGB data
+    pub fn new(width: u32, height: u32, data: Vec<f32>) -> HdrImage {
+        assert_eq!(data.len(), (width * height * 3) as usize, "image data does not match its dimensions");
+
+        HdrImage {
+            width: width,
+            height: height,
+            data: data
+        }
+    }
+
+    /// Load a Radiance RGBE (.hdr) image from the specified file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<HdrImage> {
+        let file = try!(File::open(path).map_err(Error::Io));
+        HdrImage::decode(BufReader::new(file)).map_err(Error::Io)
+    }
+
+    /// Decode a Radiance RGBE image. Both flat and run length
+    /// encoded scanlines are supported, in the standard
+    /// orientation (-Y height +X width)
+    pub fn decode<R: BufRead>(mut r: R) -> io::Result<HdrImage> {
+        // The header is a list of lines, ended by an empty one
+        let mut line = String::new();
+        try!(r.read_line(&mut line));
+
+        if !line.starts_with("#?") {
+            return Err(invalid_data("not a Radiance image"));
+        }
+
+        loop {
+            line.clear();
+
+            if try!(r.read_line(&mut line)) == 0 {
+                return Err(invalid_data("unexpected end of header"));
+            }
+
+            let line = line.trim();
+
+            if line.is_empty() {
+                break;
+            }
+
+            if line.starts_with("FORMAT=") && line != "FORMAT=32-bit_rle_rgbe" {
+                return Err(invalid_data("unsupported pixel format"));
+            }
+        }
+
+        // Resolution line
+        line.clear();
+        try!(r.read_line(&mut line));
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        let (height, width) = match fields.as_slice() {
+            &["-Y", h, "+X", w] => match (h.parse::<u32>(), w.parse::<u32>()) {
+                (Ok(h), Ok(w)) => (h, w),
+                _ => return Err(invalid_data("invalid image dimensions"))
+            },
+            _ => return Err(invalid_data("unsupported image orientation"))
+        };
+
+        // The dimensions are checked before anything is allocated
+        // for them, and the data only grows as scanlines are read
+        if width == 0 || height == 0 || width > MAX_HDR_SIZE || height > MAX_HDR_SIZE {
+            return Err(invalid_data("invalid image dimensions"));
+        }
+
+        let len = try!((width as usize).checked_mul(height as usize)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| invalid_data("invalid image dimensions")));
+
+        let mut data = Vec::with_capacity(cmp::min(len, width as usize * 3 * 64));
+        let mut scanline = vec![[0u8; 4]; width as usize];
+
+        for _ in 0..height {
+            try!(read_scanline(&mut r, &mut scanline));
+
+            for rgbe in &scanline {
+                data.extend_from_slice(&rgbe_to_rgb(*rgbe));
+            }
+        }
+
+        Ok(HdrImage::new(width, height, data))
+    }
+
+    /// Get the RGB value of the pixel at the specified coordinates
+    pub fn pixel(&self, x: u32, y: u32) -> [f32; 3] {
+        let i = ((y * self.width + x) * 3) as usize;
+        [self.data[i], self.data[i + 1], self.data[i + 2]]
+    }
+}
+
+// Read one RGBE scanline, either run length encoded or flat
+fn read_scanline<R: Read>(r: &mut R, scanline: &mut [[u8; 4]]) -> io::Result<()> {
+    let width = scanline.len();
+
+    let mut first = [0u8; 4];
+    try!(r.read_exact(&mut first));
+
+    // New style RLE scanlines start with 2, 2 and their width
+    let rle = width >= 8 && width < 0x8000 && first[0] == 2 && first[1] == 2 && first[2] & 0x80 == 0;
+
+    if !rle {
+        if scanline.is_empty() {
+            return Err(invalid_data("empty scanline"));
+        }
+
+        scanline[0] = first;
+
+        for pixel in scanline[1..].iter_mut() {
+            try!(r.read_exact(pixel));
+        }
+
+        return Ok(());
+    }
+
+    if ((first[2] as usize) << 8 | first[3] as usize) != width {
+        return Err(invalid_data("scanline width does not match the image"));
+    }
+
+    // Each channel is encoded separately, as runs and literals
+    for channel in 0..4 {
+        let mut x = 0;
+
+        while x < width {
+            let mut count = [0u8; 1];
+            try!(r.read_exact(&mut count));
+
+            if count[0] > 128 {
+                let len = (count[0] - 128) as usize;
+
+                if len > width - x {
+                    return Err(invalid_data("run exceeds the scanline"));
+                }
+
+                let mut value = [0u8; 1];
+                try!(r.read_exact(&mut value));
+
+                for pixel in scanline[x..x + len].iter_mut() {
+                    pixel[channel] = value[0];
+                }
+
+                x += len;
+            }
+            else {
+                let len = count[0] as usize;
+
+                if len == 0 || len > width - x {
+                    return Err(invalid_data("invalid literal in scanline"));
+                }
+
+                for pixel in scanline[x..x + len].iter_mut() {
+                    let mut value = [0u8; 1];
+                    try!(r.read_exact(&mut value));
+                    pixel[channel] = value[0];
+                }
+
+                x += len;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Convert a shared exponent RGBE pixel to linear RGB
+fn rgbe_to_rgb(rgbe: [u8; 4]) -> [f32; 3] {
+    if rgbe[3] == 0 {
+        return [0.0; 3];
+    }
+
+    let scale = 2f32.powi(rgbe[3] as i32 - 136);
+    [rgbe[0] as f32 * scale, rgbe[1] as f32 * scale, rgbe[2] as f32 * scale]
+}
+
+// Error for malformed files
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Write a PNG chunk: length, type, data and CRC
+fn write_png_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    try!(w.write_all(&be_u32(data.len() as u32)));
+    try!(w.write_all(kind));
+    try!(w.write_all(data));
+
+    let crc = crc32(crc32(0, kind), data);
+    w.write_all(&be_u32(crc))
+}
+
+// Wrap data in a zlib stream made of uncompressed deflate blocks
+fn zlib_store(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 65535 * 5 + 16);
+
+    // Deflate, 32K window, no preset dictionary
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(65535).peekable();
+
+    if blocks.peek().is_none() {
+        // An empty stream still needs a final block
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none() as u8;
+        let len = block.len() as u16;
+
+        out.push(last);
+        out.extend_from_slice(&[len as u8, (len >> 8) as u8]);
+        out.extend_from_slice(&[!len as u8, (!len >> 8) as u8]);
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&be_u32(adler32(data)));
+    out
+}
+
+// Big endian representation of a 32 bit integer
+fn be_u32(v: u32) -> [u8; 4] {
+    [(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]
+}
+
+// Update a CRC-32 checksum (as used by PNG) with more data
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xedb88320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+// Adler-32 checksum, as used by zlib
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+
+        a %= 65521;
+        b %= 65521;
+    }
+
+    (b << 16) | a
+}
@@ -6,8 +6,12 @@ extern crate gl;
 extern crate libc;
 extern crate glutin;
 extern crate cgmath;
+extern crate serde_json;
+extern crate imagefmt;
 
 use std::io;
+use std::fmt;
+use std::error;
 
 /// Error type used in this library
 #[derive(Debug)]
@@ -15,7 +19,46 @@ pub enum Error {
     Io(io::Error),
     WindowCreation(glutin::CreationError),
     GlContext(glutin::ContextError),
-    GlShader(String)
+    GlShader(shaders::ShaderError),
+    UniformType(String, gl::types::GLenum),
+    VertexLayout(String),
+    Framebuffer(gl::types::GLenum),
+    Json(serde_json::Error),
+    Gltf(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::WindowCreation(ref err) => write!(f, "Failed to create window: {}", err),
+            Error::GlContext(ref err) => write!(f, "OpenGL context error: {}", err),
+            Error::GlShader(ref err) => write!(f, "Shader error: {}", err),
+            Error::UniformType(ref name, kind) => {
+                write!(f, "Value does not match the type of uniform {} ({})", name, uniforms::type_name(kind))
+            },
+            Error::VertexLayout(ref msg) => write!(f, "Vertex layout does not match the shader: {}", msg),
+            Error::Framebuffer(status) => write!(f, "Incomplete framebuffer (status 0x{:04X})", status),
+            Error::Json(ref err) => write!(f, "JSON error: {}", err),
+            Error::Gltf(ref msg) => write!(f, "Invalid glTF asset: {}", msg)
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            Error::WindowCreation(ref err) => Some(err),
+            Error::GlContext(ref err) => Some(err),
+            Error::GlShader(ref err) => Some(err),
+            Error::UniformType(..) => None,
+            Error::VertexLayout(_) => None,
+            Error::Framebuffer(_) => None,
+            Error::Json(ref err) => Some(err),
+            Error::Gltf(_) => None
+        }
+    }
 }
 
 /// Result type used in this library
@@ -33,6 +76,9 @@ pub fn set_clear_color(r: f32, g: f32, b: f32, a: f32) {
 /// Clear the screen, both the color buffer
 /// and the depth buffer
 pub fn clear() {
+    // Delete the objects released since the last frame
+    resources::collect();
+
     unsafe {
         gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
     }
@@ -42,12 +88,43 @@ pub fn clear() {
     mesh::BLANK_TEXTURE.bind();
 }
 
+/// Read back a rectangle of the currently bound framebuffer as
+/// 8 bit RGBA pixels. Just like OpenGL, rows are ordered from
+/// the bottom of the rectangle to the top
+pub fn read_pixels(x: u32, y: u32, width: u32, height: u32) -> Vec<u8> {
+    let mut data = vec![0u8; (width * height * 4) as usize];
+
+    unsafe {
+        // Rows are tightly packed
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+
+        gl::ReadPixels(
+            x as i32,
+            y as i32,
+            width as i32,
+            height as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            data.as_mut_ptr() as *mut std::os::raw::c_void
+        );
+    }
+
+    data
+}
+
+// Macros
+#[macro_use]
+mod macros;
+
 // Windowing subsystem
 pub mod window;
 
 // Shader program management
 pub mod shaders;
 
+// Shader uniform values
+pub mod uniforms;
+
 // Mesh rendering
 pub mod mesh;
 
@@ -60,6 +137,42 @@ pub mod camera;
 // Lighting
 pub mod lighting;
 
+// Physically based materials
+pub mod material;
+
+// Image-based lighting
+pub mod environment;
+
+// Wavefront OBJ models
+pub mod obj;
+
+// glTF 2.0 assets
+pub mod gltf;
+
+// Skeletal animation
+pub mod animation;
+
+// Skybox rendering
+pub mod skybox;
+
+// Shadow maps
+mod shadows;
+
+// Offscreen rendering
+pub mod target;
+
+// Offscreen passes into cube maps
+mod bake;
+
+// Post-processing effects
+pub mod postprocess;
+
+// OpenGL objects lifetime
+pub mod resources;
+
+// Image manipulation & encoding
+pub mod image;
+
 // Context
 pub mod context;
 pub use context::Context;
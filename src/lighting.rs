@@ -1,3 +1,28 @@
+use cgmath::{self, InnerSpace, EuclideanSpace, SquareMatrix, Angle};
+
+use math::{P3, Vec3, Vec4, Mat4, Deg};
+
+/// Maximum number of directional lights used at the same time
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+
+/// Maximum number of point lights used at the same time
+pub const MAX_POINT_LIGHTS: usize = 8;
+
+/// Maximum number of spot lights used at the same time
+pub const MAX_SPOT_LIGHTS: usize = 4;
+
+/// Maximum number of shadow maps used at the same time. Each spot
+/// light casting shadows uses one, and each directional light uses
+/// one per cascade. They are the layers of a single texture
+pub const MAX_SHADOW_MAPS: usize = 8;
+
+/// Maximum number of cascades of a directional light
+pub const MAX_CASCADES: usize = 4;
+
+/// Maximum number of point lights casting shadows at the same time.
+/// Each of them renders its shadows into the six faces of a cube map
+pub const MAX_POINT_SHADOWS: usize = 2;
+
 /// Represents an ambient light source
 pub struct Ambient {
     pub color: [f32; 4],
@@ -13,3 +38,362 @@ impl Ambient {
         }
     }
 }
+
+/// Describes how a light casts shadows
+#[derive(Debug, Clone, Copy)]
+pub struct Shadow {
+    /// Width and height of the shadow map, in texels
+    pub resolution: u32,
+
+    /// Depth offset applied when comparing against the shadow
+    /// map, to avoid shadow acne. Higher values detach shadows
+    /// from their casters. For point lights, it is relative
+    /// to the range of the shadows
+    pub bias: f32,
+
+    /// Range of the shadows. For a spot or a point light, this is the
+    /// distance after which objects do not cast shadows anymore. For a
+    /// directional light, this is the radius of the area around
+    /// the center that receives shadows
+    pub distance: f32,
+
+    /// Center of the area receiving shadows from a directional
+    /// light without cascades. Unused by spot lights
+    pub center: Vec3,
+
+    /// Number of cascades of a directional light. With more than one
+    /// cascade, the view of the camera is split along its depth up to
+    /// `distance`, and each part gets its own shadow map fitted around
+    /// it. Unused by spot lights
+    pub cascades: u32,
+
+    /// How cascades are split, from 0 (uniform splits) to 1
+    /// (logarithmic splits, giving more resolution close to the camera)
+    pub split_lambda: f32
+}
+
+impl Shadow {
+    /// Create new shadow settings
+    /// Default resolution: 1024x1024
+    /// Default bias: 0.005
+    /// Default distance: 50 units around the origin
+    pub fn new() -> Shadow {
+        Shadow {
+            resolution: 1024,
+            bias: 0.005,
+            distance: 50.0,
+            center: Vec3::new(0.0, 0.0, 0.0),
+            cascades: 1,
+            split_lambda: 0.75
+        }
+    }
+
+    /// Set the resolution of the shadow map
+    pub fn resolution(mut self, resolution: u32) -> Shadow {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Set the depth bias
+    pub fn bias(mut self, bias: f32) -> Shadow {
+        self.bias = bias;
+        self
+    }
+
+    /// Set the range of the shadows
+    pub fn distance(mut self, distance: f32) -> Shadow {
+        self.distance = distance;
+        self
+    }
+
+    /// Set the center of the area receiving shadows
+    pub fn center(mut self, center: Vec3) -> Shadow {
+        self.center = center;
+        self
+    }
+
+    /// Set the number of cascades, up to `MAX_CASCADES`
+    pub fn cascades(mut self, cascades: u32) -> Shadow {
+        self.cascades = cascades.max(1).min(MAX_CASCADES as u32);
+        self
+    }
+
+    /// Set how the cascades are split
+    pub fn split_lambda(mut self, lambda: f32) -> Shadow {
+        self.split_lambda = lambda;
+        self
+    }
+}
+
+/// Compute the distances from the camera at which the cascades of a
+/// shadow end, blending uniform and logarithmic splits of `[near, far]`
+/// by `lambda`. The last split is always `far`
+pub fn cascade_splits(near: f32, far: f32, count: u32, lambda: f32) -> Vec<f32> {
+    (1..count + 1).map(|i| {
+        let f = i as f32 / count as f32;
+
+        let log = near * (far / near).powf(f);
+        let uniform = near + (far - near) * f;
+
+        lambda * log + (1.0 - lambda) * uniform
+    }).collect()
+}
+
+/// Represents a light infinitely far away, whose rays
+/// all go in the same direction, such as the sun
+#[derive(Debug, Clone, Copy)]
+pub struct Directional {
+    pub direction: Vec3,
+    pub color: [f32; 3],
+    pub strength: f32,
+    pub shadow: Option<Shadow>
+}
+
+impl Directional {
+    /// Create a new directional light, shining
+    /// in the specified direction
+    pub fn new(direction: Vec3, color: [f32; 3], strength: f32) -> Directional {
+        Directional {
+            direction: direction.normalize(),
+            color: color,
+            strength: strength,
+            shadow: None
+        }
+    }
+
+    /// Make the light cast shadows
+    pub fn shadow(mut self, shadow: Shadow) -> Directional {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    // Matrix transforming world coordinates to the
+    // clip space of the shadow map
+    pub(crate) fn light_space(&self, shadow: &Shadow) -> Mat4 {
+        let d = shadow.distance;
+        let eye = shadow.center - self.direction * d * 2.0;
+
+        let view = Mat4::look_at(P3::from_vec(eye), P3::from_vec(shadow.center), up_vector(self.direction));
+        let projection = cgmath::ortho(-d, d, -d, d, 0.0, d * 4.0);
+
+        projection * view
+    }
+
+    // Matrices of each cascade of the light, fitted to the view of a
+    // camera, along with the distance from the camera where they end
+    pub(crate) fn cascade_spaces(&self, shadow: &Shadow, projection: Mat4, view: Mat4) -> Vec<(Mat4, f32)> {
+        // Near and far planes of the camera, from its perspective projection
+        let near = projection.w.z / (projection.z.z - 1.0);
+        let far = (projection.w.z / (projection.z.z + 1.0)).min(shadow.distance);
+
+        // Corners of the frustum of the camera in world space
+        let inverse = (projection * view).invert().unwrap_or(Mat4::identity());
+
+        let corners: Vec<(Vec3, Vec3)> = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].iter().map(|&(x, y)| {
+            let n = inverse * Vec4::new(x, y, -1.0, 1.0);
+            let f = inverse * Vec4::new(x, y, 1.0, 1.0);
+
+            (n.truncate() / n.w, f.truncate() / f.w)
+        }).collect();
+
+        let full_far = projection.w.z / (projection.z.z + 1.0);
+        let mut start = near;
+
+        cascade_splits(near, far, shadow.cascades, shadow.split_lambda).into_iter().map(|end| {
+            // Corners of the part of the frustum covered by the cascade
+            let mut points = Vec::with_capacity(8);
+
+            for &(n, f) in &corners {
+                points.push(n + (f - n) * ((start - near) / (full_far - near)));
+                points.push(n + (f - n) * ((end - near) / (full_far - near)));
+            }
+
+            start = end;
+
+            // Bounding sphere of the cascade, its size does not
+            // change when the camera rotates
+            let center = points.iter().fold(Vec3::new(0.0, 0.0, 0.0), |acc, p| acc + *p) / 8.0;
+            let radius = points.iter().map(|p| (*p - center).magnitude()).fold(0.0, f32::max);
+
+            // Include the objects between the light and the cascade
+            let eye = center - self.direction * (radius + shadow.distance);
+            let view = Mat4::look_at(P3::from_vec(eye), P3::from_vec(center), up_vector(self.direction));
+            let mut projection = cgmath::ortho(-radius, radius, -radius, radius, 0.0, radius * 2.0 + shadow.distance);
+
+            // Snap the projection to texels, so that shadow
+            // edges do not shimmer when the camera moves
+            let origin = (projection * view) * Vec4::new(0.0, 0.0, 0.0, 1.0);
+            let texel = 2.0 / shadow.resolution as f32;
+
+            projection.w.x += (origin.x / texel).round() * texel - origin.x;
+            projection.w.y += (origin.y / texel).round() * texel - origin.y;
+
+            (projection * view, end)
+        }).collect()
+    }
+}
+
+/// Represents a light emitting in every direction from a
+/// position, such as a light bulb. Its intensity decreases
+/// with the distance d, divided by `constant + linear * d + quadratic * d²`
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    pub position: Vec3,
+    pub color: [f32; 3],
+    pub strength: f32,
+    pub attenuation: [f32; 3],
+    pub shadow: Option<Shadow>
+}
+
+impl Point {
+    /// Create a new point light
+    /// Default attenuation: range of about 50 units
+    pub fn new(position: Vec3, color: [f32; 3], strength: f32) -> Point {
+        Point {
+            position: position,
+            color: color,
+            strength: strength,
+            attenuation: [1.0, 0.09, 0.032],
+            shadow: None
+        }
+    }
+
+    /// Set the attenuation factors of the light
+    pub fn attenuation(mut self, constant: f32, linear: f32, quadratic: f32) -> Point {
+        self.attenuation = [constant, linear, quadratic];
+        self
+    }
+
+    /// Make the light cast shadows in every direction
+    pub fn shadow(mut self, shadow: Shadow) -> Point {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    // Matrices transforming world coordinates to the clip space
+    // of each face of the cube shadow map, in the order of the
+    // GL cube map faces: +X, -X, +Y, -Y, +Z, -Z
+    pub(crate) fn face_spaces(&self, shadow: &Shadow) -> Vec<Mat4> {
+        let faces = [
+            (Vec3::new( 1.0,  0.0,  0.0), Vec3::new(0.0, -1.0,  0.0)),
+            (Vec3::new(-1.0,  0.0,  0.0), Vec3::new(0.0, -1.0,  0.0)),
+            (Vec3::new( 0.0,  1.0,  0.0), Vec3::new(0.0,  0.0,  1.0)),
+            (Vec3::new( 0.0, -1.0,  0.0), Vec3::new(0.0,  0.0, -1.0)),
+            (Vec3::new( 0.0,  0.0,  1.0), Vec3::new(0.0, -1.0,  0.0)),
+            (Vec3::new( 0.0,  0.0, -1.0), Vec3::new(0.0, -1.0,  0.0)),
+        ];
+
+        let eye = P3::from_vec(self.position);
+        let projection = cgmath::perspective(Deg(90.0), 1.0, 0.1, shadow.distance);
+
+        faces.iter().map(|&(direction, up)| {
+            projection * Mat4::look_at(eye, eye + direction, up)
+        }).collect()
+    }
+}
+
+/// Represents a light emitting from a position in a cone,
+/// such as a flashlight. The intensity fades out between the
+/// inner and the outer angle of the cone, and decreases
+/// with the distance like a point light
+#[derive(Debug, Clone, Copy)]
+pub struct Spot {
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub color: [f32; 3],
+    pub strength: f32,
+    pub attenuation: [f32; 3],
+    pub inner_angle: Deg<f32>,
+    pub outer_angle: Deg<f32>,
+    pub shadow: Option<Shadow>
+}
+
+impl Spot {
+    /// Create a new spot light, pointing in the specified direction
+    /// Default cone: 12.5° to 17.5°
+    /// Default attenuation: range of about 50 units
+    pub fn new(position: Vec3, direction: Vec3, color: [f32; 3], strength: f32) -> Spot {
+        Spot {
+            position: position,
+            direction: direction.normalize(),
+            color: color,
+            strength: strength,
+            attenuation: [1.0, 0.09, 0.032],
+            inner_angle: Deg(12.5),
+            outer_angle: Deg(17.5),
+            shadow: None
+        }
+    }
+
+    /// Set the angles of the cone, in degrees, from
+    /// the direction of the light to its edges
+    pub fn cone(mut self, inner: f32, outer: f32) -> Spot {
+        self.inner_angle = Deg(inner);
+        self.outer_angle = Deg(outer.max(inner));
+        self
+    }
+
+    /// Set the attenuation factors of the light
+    pub fn attenuation(mut self, constant: f32, linear: f32, quadratic: f32) -> Spot {
+        self.attenuation = [constant, linear, quadratic];
+        self
+    }
+
+    /// Make the light cast shadows
+    pub fn shadow(mut self, shadow: Shadow) -> Spot {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    // Cosines of the cone angles, as compared in the shader
+    pub(crate) fn cutoffs(&self) -> (f32, f32) {
+        (self.inner_angle.cos(), self.outer_angle.cos())
+    }
+
+    // Matrix transforming world coordinates to the
+    // clip space of the shadow map
+    pub(crate) fn light_space(&self, shadow: &Shadow) -> Mat4 {
+        let eye = P3::from_vec(self.position);
+        let view = Mat4::look_at(eye, eye + self.direction, up_vector(self.direction));
+        let projection = cgmath::perspective(self.outer_angle * 2.0, 1.0, 0.1, shadow.distance);
+
+        projection * view
+    }
+}
+
+/// Represents any kind of light that can
+/// illuminate a scene
+#[derive(Debug, Clone, Copy)]
+pub enum Light {
+    Directional(Directional),
+    Point(Point),
+    Spot(Spot)
+}
+
+impl From<Directional> for Light {
+    fn from(l: Directional) -> Light {
+        Light::Directional(l)
+    }
+}
+
+impl From<Point> for Light {
+    fn from(l: Point) -> Light {
+        Light::Point(l)
+    }
+}
+
+impl From<Spot> for Light {
+    fn from(l: Spot) -> Light {
+        Light::Spot(l)
+    }
+}
+
+// Find an up vector for a view looking in the specified direction
+fn up_vector(direction: Vec3) -> Vec3 {
+    if direction.y.abs() > 0.99 {
+        Vec3::new(0.0, 0.0, 1.0)
+    }
+    else {
+        Vec3::new(0.0, 1.0, 0.0)
+    }
+}
@@ -0,0 +1,37 @@
+/// Implement `mesh::VertexLayout` for a `#[repr(C)]` vertex structure,
+/// by associating some of its fields to shader attribute locations.
+/// The type and number of components of each attribute are deduced
+/// from the type of the field, and its name is used as the name of the
+/// shader input.
+///
+/// ```ignore
+/// #[repr(C)]
+/// struct MyVertex {
+///     position: [f32; 3],
+///     uv: [f32; 2],
+///     joints: [u16; 4]
+/// }
+///
+/// vertex_layout!(MyVertex {
+///     position: 0,
+///     uv: 4,
+///     joints: 5
+/// });
+/// ```
+#[macro_export]
+macro_rules! vertex_layout {
+    ($ty:ty { $($field:ident: $location:expr),* $(,)* }) => {
+        impl $crate::mesh::VertexLayout for $ty {
+            fn attributes() -> Vec<$crate::mesh::Attribute> {
+                vec![$(
+                    $crate::mesh::Attribute::of_field(
+                        stringify!($field),
+                        $location,
+                        ::std::mem::offset_of!($ty, $field),
+                        |v: &$ty| &v.$field
+                    )
+                ),*]
+            }
+        }
+    };
+}
@@ -0,0 +1,141 @@
+use std::rc::Rc;
+
+use mesh::Texture;
+
+/// How the alpha channel of the base color is interpreted,
+/// following the glTF alpha modes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    /// The alpha channel is ignored
+    Opaque,
+
+    /// Fragments whose alpha is below the cutoff are discarded
+    Mask(f32),
+
+    /// Fragments are blended with what is behind them
+    Blend
+}
+
+/// Represents a physically based material, following the metallic
+/// roughness model of glTF. Each factor is multiplied by the matching
+/// texture, if any. The base color and emissive textures are in sRGB,
+/// the other ones are linear
+#[derive(Clone)]
+pub struct Material {
+    pub base_color: [f32; 4],
+    pub base_color_texture: Option<Rc<Texture>>,
+
+    /// How metallic the surface is, from 0 (dielectric) to 1 (metal)
+    pub metallic: f32,
+
+    /// How rough the surface is, from 0 (mirror) to 1 (fully diffuse)
+    pub roughness: f32,
+
+    /// Metalness in the blue channel, roughness in the green one
+    pub metallic_roughness_texture: Option<Rc<Texture>>,
+
+    /// Tangent space normal map, and the scale applied
+    /// to its X and Y components
+    pub normal_texture: Option<Rc<Texture>>,
+    pub normal_scale: f32,
+
+    /// Ambient occlusion in the red channel, and how much of it applies
+    pub occlusion_texture: Option<Rc<Texture>>,
+    pub occlusion_strength: f32,
+
+    /// Light emitted by the surface, unaffected by the lights
+    pub emissive: [f32; 3],
+    pub emissive_texture: Option<Rc<Texture>>,
+
+    pub alpha_mode: AlphaMode,
+
+    /// Whether back faces are lit as if they faced the viewer.
+    /// Back face culling is left to the application
+    pub double_sided: bool
+}
+
+impl Material {
+    /// Create a new material, with the default values of glTF
+    /// Default base color: opaque white
+    /// Default metallic and roughness: 1
+    pub fn new() -> Material {
+        Material {
+            base_color: [1.0, 1.0, 1.0, 1.0],
+            base_color_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: [0.0, 0.0, 0.0],
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false
+        }
+    }
+
+    /// Set the base color factor
+    pub fn base_color(mut self, r: f32, g: f32, b: f32, a: f32) -> Material {
+        self.base_color = [r, g, b, a];
+        self
+    }
+
+    /// Set the base color texture
+    pub fn base_color_texture(mut self, texture: Rc<Texture>) -> Material {
+        self.base_color_texture = Some(texture);
+        self
+    }
+
+    /// Set the metallic and roughness factors, clamped to [0, 1]
+    pub fn metallic_roughness(mut self, metallic: f32, roughness: f32) -> Material {
+        self.metallic = metallic.max(0.0).min(1.0);
+        self.roughness = roughness.max(0.0).min(1.0);
+        self
+    }
+
+    /// Set the metallic-roughness texture
+    pub fn metallic_roughness_texture(mut self, texture: Rc<Texture>) -> Material {
+        self.metallic_roughness_texture = Some(texture);
+        self
+    }
+
+    /// Set the normal map and its scale
+    pub fn normal_texture(mut self, texture: Rc<Texture>, scale: f32) -> Material {
+        self.normal_texture = Some(texture);
+        self.normal_scale = scale;
+        self
+    }
+
+    /// Set the ambient occlusion texture and its strength
+    pub fn occlusion_texture(mut self, texture: Rc<Texture>, strength: f32) -> Material {
+        self.occlusion_texture = Some(texture);
+        self.occlusion_strength = strength;
+        self
+    }
+
+    /// Set the emissive factor
+    pub fn emissive(mut self, r: f32, g: f32, b: f32) -> Material {
+        self.emissive = [r, g, b];
+        self
+    }
+
+    /// Set the emissive texture
+    pub fn emissive_texture(mut self, texture: Rc<Texture>) -> Material {
+        self.emissive_texture = Some(texture);
+        self
+    }
+
+    /// Set the alpha mode
+    pub fn alpha_mode(mut self, mode: AlphaMode) -> Material {
+        self.alpha_mode = mode;
+        self
+    }
+
+    /// Set whether the material is double sided
+    pub fn double_sided(mut self, double_sided: bool) -> Material {
+        self.double_sided = double_sided;
+        self
+    }
+}
@@ -12,9 +12,21 @@ pub type Vec2 = cgmath::Vector2<f32>;
 /// 3D vector
 pub type Vec3 = cgmath::Vector3<f32>;
 
+/// 4D vector
+pub type Vec4 = cgmath::Vector4<f32>;
+
+/// 2x2 square matrix
+pub type Mat2 = cgmath::Matrix2<f32>;
+
+/// 3x3 square matrix
+pub type Mat3 = cgmath::Matrix3<f32>;
+
 /// 4x4 square matrix
 pub type Mat4 = cgmath::Matrix4<f32>;
 
+/// Quaternion, representing a rotation
+pub type Quat = cgmath::Quaternion<f32>;
+
 /// Degrees & Radians angles
 pub use cgmath::Deg;
 pub use cgmath::Rad;
@@ -1,9 +1,19 @@
 use std;
+use std::io;
+use std::cell::Cell;
 use std::os::raw::c_void;
 
 use gl;
 use gl::types::*;
 
+use super::{Error, Result};
+use image::{Image, HdrImage};
+use bake::{self, Baker};
+use resources::{self, ContextId, Resource};
+
+// Procedural geometry
+pub mod shapes;
+
 // Define a globally available default blank texture
 lazy_static! {
     pub static ref BLANK_TEXTURE: Texture = Texture::blank();
@@ -11,6 +21,7 @@ lazy_static! {
 
 /// Represents a 3D vertex
 #[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vertex {
     pub position: [GLfloat; 3],
     pub color: [GLfloat; 4],
@@ -51,19 +62,427 @@ impl Vertex {
     }
 }
 
+// Memory layout of the default vertex type
+vertex_layout!(Vertex {
+    position: 0,
+    color: 1,
+    texcoords: 2,
+    normal: 3
+});
+
+/// A vertex deformed by the joints of a skeleton, which extends the
+/// default vertex type. It can be rendered with the built-in shaders,
+/// after setting the joint matrices with `Context::set_skin`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkinnedVertex {
+    pub position: [GLfloat; 3],
+    pub color: [GLfloat; 4],
+    pub texcoords: [GLfloat; 2],
+    pub normal: [GLfloat; 3],
+
+    /// Indices of the joints influencing the vertex
+    pub joints: [u16; 4],
+
+    /// Influence of each joint, which should add up to 1
+    pub weights: [GLfloat; 4]
+}
+
+impl SkinnedVertex {
+    /// Create a new vertex influenced by the specified joints
+    pub fn new(vertex: Vertex, joints: [u16; 4], weights: [f32; 4]) -> SkinnedVertex {
+        SkinnedVertex {
+            position: vertex.position,
+            color: vertex.color,
+            texcoords: vertex.texcoords,
+            normal: vertex.normal,
+            joints: joints,
+            weights: weights
+        }
+    }
+}
+
+// Memory layout of skinned vertices, sharing
+// the locations of the default vertex type
+vertex_layout!(SkinnedVertex {
+    position: 0,
+    color: 1,
+    texcoords: 2,
+    normal: 3,
+    joints: 4,
+    weights: 5
+});
+
+/// Represents the type of the components of a vertex attribute
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttributeType {
+    Float,
+    Byte,
+    UnsignedByte,
+    Short,
+    UnsignedShort,
+    Int,
+    UnsignedInt
+}
+
+impl AttributeType {
+    // Get the corresponding GL constant
+    fn to_gl_const(&self) -> GLenum {
+        match *self {
+            AttributeType::Float => gl::FLOAT,
+            AttributeType::Byte => gl::BYTE,
+            AttributeType::UnsignedByte => gl::UNSIGNED_BYTE,
+            AttributeType::Short => gl::SHORT,
+            AttributeType::UnsignedShort => gl::UNSIGNED_SHORT,
+            AttributeType::Int => gl::INT,
+            AttributeType::UnsignedInt => gl::UNSIGNED_INT
+        }
+    }
+}
+
+/// Describes an attribute of a vertex structure: where it is
+/// stored in the vertex, and which shader input it feeds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attribute {
+    pub name: &'static str,
+    pub location: GLuint,
+    pub kind: AttributeType,
+    pub components: GLint,
+    pub offset: usize,
+
+    /// Integer attributes are converted to floats in the range
+    /// [0, 1] (or [-1, 1]) instead of being exposed as integers
+    pub normalized: bool
+}
+
+impl Attribute {
+    /// Create a new attribute description
+    pub fn new(name: &'static str, location: GLuint, kind: AttributeType, components: GLint, offset: usize) -> Attribute {
+        Attribute {
+            name: name,
+            location: location,
+            kind: kind,
+            components: components,
+            offset: offset,
+            normalized: false
+        }
+    }
+
+    /// Describe a field of a vertex structure, the type and number of
+    /// components are deduced from the type of the field. This is
+    /// used by the `vertex_layout!` macro
+    pub fn of_field<V, F: AttributeFormat>(name: &'static str, location: GLuint, offset: usize, _field: fn(&V) -> &F) -> Attribute {
+        Attribute::new(name, location, F::kind(), F::components(), offset)
+    }
+
+    /// Normalize the attribute values when they are read by the shader
+    pub fn normalized(mut self) -> Attribute {
+        self.normalized = true;
+        self
+    }
+}
+
+/// Implemented by the types that can be used as vertex attributes
+pub trait AttributeFormat {
+    /// Type of each component
+    fn kind() -> AttributeType;
+
+    /// Number of components
+    fn components() -> GLint;
+}
+
+// Implement AttributeFormat for scalars and arrays of scalars
+macro_rules! attribute_format {
+    ($ty:ty, $kind:ident, $components:expr) => {
+        impl AttributeFormat for $ty {
+            fn kind() -> AttributeType {
+                AttributeType::$kind
+            }
+
+            fn components() -> GLint {
+                $components
+            }
+        }
+    };
+
+    ($scalar:ty, $kind:ident) => {
+        attribute_format!($scalar, $kind, 1);
+        attribute_format!([$scalar; 1], $kind, 1);
+        attribute_format!([$scalar; 2], $kind, 2);
+        attribute_format!([$scalar; 3], $kind, 3);
+        attribute_format!([$scalar; 4], $kind, 4);
+    };
+}
+
+attribute_format!(f32, Float);
+attribute_format!(i8, Byte);
+attribute_format!(u8, UnsignedByte);
+attribute_format!(i16, Short);
+attribute_format!(u16, UnsignedShort);
+attribute_format!(i32, Int);
+attribute_format!(u32, UnsignedInt);
+
+/// Describes the memory layout of a vertex type, so that it can be
+/// used to build a `Mesh`. The type should be `#[repr(C)]`, and the
+/// `vertex_layout!` macro can be used to implement this trait
+pub trait VertexLayout {
+    /// List the attributes of the vertex type
+    fn attributes() -> Vec<Attribute>;
+}
+
+// Anisotropic filtering, from the widely supported
+// EXT_texture_filter_anisotropic extension
+const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
+
+lazy_static! {
+    // Highest anisotropy supported, 1 without the extension
+    static ref MAX_ANISOTROPY: f32 = max_anisotropy();
+}
+
+/// How texture coordinates outside of [0, 1] are handled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wrap {
+    /// The texture is tiled
+    Repeat,
+
+    /// The texture is tiled, every other tile being mirrored
+    MirroredRepeat,
+
+    /// The texels on the edges are stretched
+    ClampToEdge,
+
+    /// The border color is used outside of the texture
+    ClampToBorder
+}
+
+impl Wrap {
+    // Get the corresponding GL constant
+    fn to_gl_const(&self) -> GLenum {
+        match *self {
+            Wrap::Repeat => gl::REPEAT,
+            Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+            Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+            Wrap::ClampToBorder => gl::CLAMP_TO_BORDER
+        }
+    }
+}
+
+/// How textures are sampled between their texels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// The nearest texel is used, keeping hard edges for pixel art.
+    /// With mipmaps, the nearest level is used as well
+    Nearest,
+
+    /// The nearest texels are blended. With mipmaps,
+    /// the nearest level is used
+    Linear,
+
+    /// The nearest texels of the two nearest mipmap levels are
+    /// blended. Mipmaps are always generated with this filter
+    Trilinear
+}
+
+/// Describes how a texture is sampled: its wrapping, filtering and
+/// mipmaps. By default, the texture is repeated and linearly filtered
+/// without mipmaps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureOptions {
+    /// Wrapping along the horizontal axis
+    pub wrap_s: Wrap,
+
+    /// Wrapping along the vertical axis
+    pub wrap_t: Wrap,
+
+    /// Wrapping along the depth axis, for cube maps
+    pub wrap_r: Wrap,
+
+    pub filter: Filter,
+
+    /// Whether mipmaps are generated, for textures seen from afar
+    pub mipmaps: bool,
+
+    /// Maximum anisotropy, improving textures seen at grazing angles.
+    /// It is clamped to what the hardware supports, 1 disables it
+    pub anisotropy: f32,
+
+    /// Color used outside of the texture with `Wrap::ClampToBorder`
+    pub border_color: [f32; 4]
+}
+
+impl TextureOptions {
+    /// Create the default options: repeated, linearly
+    /// filtered and without mipmaps
+    pub fn new() -> TextureOptions {
+        TextureOptions {
+            wrap_s: Wrap::Repeat,
+            wrap_t: Wrap::Repeat,
+            wrap_r: Wrap::Repeat,
+            filter: Filter::Linear,
+            mipmaps: false,
+            anisotropy: 1.0,
+            border_color: [0.0, 0.0, 0.0, 0.0]
+        }
+    }
+
+    /// Set the wrapping along every axis
+    pub fn wrap(mut self, wrap: Wrap) -> TextureOptions {
+        self.wrap_s = wrap;
+        self.wrap_t = wrap;
+        self.wrap_r = wrap;
+        self
+    }
+
+    /// Set the wrapping along the horizontal axis
+    pub fn wrap_s(mut self, wrap: Wrap) -> TextureOptions {
+        self.wrap_s = wrap;
+        self
+    }
+
+    /// Set the wrapping along the vertical axis
+    pub fn wrap_t(mut self, wrap: Wrap) -> TextureOptions {
+        self.wrap_t = wrap;
+        self
+    }
+
+    /// Set the wrapping along the depth axis
+    pub fn wrap_r(mut self, wrap: Wrap) -> TextureOptions {
+        self.wrap_r = wrap;
+        self
+    }
+
+    /// Set the filtering
+    pub fn filter(mut self, filter: Filter) -> TextureOptions {
+        self.filter = filter;
+        self
+    }
+
+    /// Set whether mipmaps are generated
+    pub fn mipmaps(mut self, mipmaps: bool) -> TextureOptions {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// Set the maximum anisotropy
+    pub fn anisotropy(mut self, anisotropy: f32) -> TextureOptions {
+        self.anisotropy = anisotropy;
+        self
+    }
+
+    /// Set the border color
+    pub fn border_color(mut self, r: f32, g: f32, b: f32, a: f32) -> TextureOptions {
+        self.border_color = [r, g, b, a];
+        self
+    }
+
+    /// Check whether the texture needs mipmaps, either because
+    /// they were requested or because of trilinear filtering
+    pub fn has_mipmaps(&self) -> bool {
+        self.mipmaps || self.filter == Filter::Trilinear
+    }
+
+    // Set the sampling parameters of the texture bound to the target
+    unsafe fn apply(&self, target: GLenum) {
+        let (min_filter, mag_filter) = match (self.filter, self.has_mipmaps()) {
+            (Filter::Nearest, false) => (gl::NEAREST, gl::NEAREST),
+            (Filter::Nearest, true) => (gl::NEAREST_MIPMAP_NEAREST, gl::NEAREST),
+            (Filter::Linear, false) => (gl::LINEAR, gl::LINEAR),
+            (Filter::Linear, true) => (gl::LINEAR_MIPMAP_NEAREST, gl::LINEAR),
+            (Filter::Trilinear, _) => (gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR)
+        };
+
+        gl::TexParameteri(target, gl::TEXTURE_WRAP_S, self.wrap_s.to_gl_const() as i32);
+        gl::TexParameteri(target, gl::TEXTURE_WRAP_T, self.wrap_t.to_gl_const() as i32);
+        gl::TexParameteri(target, gl::TEXTURE_WRAP_R, self.wrap_r.to_gl_const() as i32);
+        gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+        gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, mag_filter as i32);
+        gl::TexParameterfv(target, gl::TEXTURE_BORDER_COLOR, self.border_color.as_ptr());
+
+        if *MAX_ANISOTROPY > 1.0 {
+            gl::TexParameterf(target, TEXTURE_MAX_ANISOTROPY, self.anisotropy.max(1.0).min(*MAX_ANISOTROPY));
+        }
+    }
+}
+
+// Query the highest anisotropy supported by the current context
+fn max_anisotropy() -> f32 {
+    unsafe {
+        let mut count: GLint = 0;
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+
+        let supported = (0..count as GLuint).any(|i| {
+            let name = gl::GetStringi(gl::EXTENSIONS, i);
+
+            if name.is_null() {
+                return false;
+            }
+
+            let name = std::ffi::CStr::from_ptr(name as *const std::os::raw::c_char).to_bytes();
+            name == b"GL_EXT_texture_filter_anisotropic" || name == b"GL_ARB_texture_filter_anisotropic"
+        });
+
+        let mut max = 1.0;
+
+        if supported {
+            gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
+        }
+
+        max
+    }
+}
+
+/// Formats in which the texels of a texture are stored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureFormat {
+    /// 8 bit unsigned normalized RGBA, clamped to [0, 1]
+    Rgba8,
+
+    /// 16 bit floating point RGBA, for high dynamic range colors
+    Rgba16F,
+
+    /// 32 bit floating point RGBA, for precise high dynamic range data
+    Rgba32F
+}
+
+impl TextureFormat {
+    // Get the corresponding GL internal format
+    fn to_gl_const(&self) -> GLenum {
+        match *self {
+            TextureFormat::Rgba8 => gl::RGBA8,
+            TextureFormat::Rgba16F => gl::RGBA16F,
+            TextureFormat::Rgba32F => gl::RGBA32F
+        }
+    }
+
+    /// Check whether the format stores floating point values
+    pub fn is_float(&self) -> bool {
+        *self != TextureFormat::Rgba8
+    }
+}
+
 /// Represents a texture
 /// A 2D image loaded onto the graphics card's memory
 pub struct Texture {
     pub width: u32,
     pub height: u32,
+    pub format: TextureFormat,
 
-    id: GLuint
+    id: GLuint,
+    owner: ContextId
 }
 
 impl Texture {
     /// Create a new texture with the specified with and height.
-    /// The image data must be 8 bit RGBA
+    /// The image data must be 8 bit RGBA. It is repeated and
+    /// linearly filtered, without mipmaps
     pub fn new(width: u32, height: u32, data: &[u8]) -> Texture {
+        Texture::with_options(width, height, data, TextureOptions::new())
+    }
+
+    /// Create a new texture from 8 bit RGBA data,
+    /// sampled with the specified options
+    pub fn with_options(width: u32, height: u32, data: &[u8], options: TextureOptions) -> Texture {
         unsafe {
             // Create and bind the texture
             let mut id: GLuint = 0;
@@ -71,12 +490,6 @@ impl Texture {
             gl::GenTextures(1, (&mut id) as *mut GLuint);
             gl::BindTexture(gl::TEXTURE_2D, id);
 
-            // Usual texture parameters
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-
             // Upload the texture data to the GPU
             gl::TexImage2D(
                 gl::TEXTURE_2D,
@@ -90,8 +503,12 @@ impl Texture {
                 data.as_ptr() as *const c_void
             );
 
-            // Generate mipmap
-            gl::GenerateMipmap(gl::TEXTURE_2D);
+            // Sampling parameters, and mipmaps if needed
+            options.apply(gl::TEXTURE_2D);
+
+            if options.has_mipmaps() {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
 
             // Unbind the texture
             gl::BindTexture(gl::TEXTURE_2D, 0);
@@ -99,7 +516,90 @@ impl Texture {
             Texture {
                 width: width,
                 height: height,
-                id: id
+                format: TextureFormat::Rgba8,
+                id: id,
+                owner: resources::current_context()
+            }
+        }
+    }
+
+    /// Create a new floating point texture with the specified width and
+    /// height, from 32 bit RGBA values, which are not clamped. The
+    /// format must be one of the floating point ones, and the data
+    /// must hold exactly four values per texel
+    pub fn from_floats(width: u32, height: u32, format: TextureFormat, data: &[f32]) -> Texture {
+        assert!(format.is_float(), "floating point data would be clamped by {:?}", format);
+        assert_eq!(data.len(), (width * height * 4) as usize, "texture data does not match its dimensions");
+
+        let texture = Texture::empty_with_format(width, height, format);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, texture.id);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                0,
+                0,
+                width as GLsizei,
+                height as GLsizei,
+                gl::RGBA,
+                gl::FLOAT,
+                data.as_ptr() as *const c_void
+            );
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        texture
+    }
+
+    /// Create a texture with the specified width and height, without
+    /// any data, to be rendered to. It is not repeated nor mipmapped
+    pub fn empty(width: u32, height: u32) -> Texture {
+        Texture::empty_with_format(width, height, TextureFormat::Rgba8)
+    }
+
+    /// Create an empty texture storing its texels in the specified
+    /// format, such as a floating point one to render high
+    /// dynamic range colors
+    pub fn empty_with_format(width: u32, height: u32, format: TextureFormat) -> Texture {
+        let kind = if format.is_float() { gl::FLOAT } else { gl::UNSIGNED_BYTE };
+
+        unsafe {
+            let mut id: GLuint = 0;
+
+            gl::GenTextures(1, (&mut id) as *mut GLuint);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                format.to_gl_const() as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                0,
+                gl::RGBA,
+                kind,
+                0 as *const c_void
+            );
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            Texture {
+                width: width,
+                height: height,
+                format: format,
+                id: id,
+                owner: resources::current_context()
             }
         }
     }
@@ -110,6 +610,22 @@ impl Texture {
         Texture::new(1, 1, &[255, 255, 255, 255])
     }
 
+    /// Change how the texture is sampled, for instance to filter
+    /// the color attachment of a render target without blending.
+    /// Mipmaps are generated from the current content if needed
+    pub fn set_options(&self, options: TextureOptions) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            options.apply(gl::TEXTURE_2D);
+
+            if options.has_mipmaps() {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
     /// Bind the texture for use in
     /// rendering
     pub fn bind(&self) {
@@ -123,10 +639,277 @@ impl Texture {
     pub fn unbind(&self) {
         BLANK_TEXTURE.bind();
     }
+
+    /// Bind the texture to the specified texture unit, for
+    /// shaders sampling several textures. The active unit
+    /// is set back to the first one afterwards
+    pub fn bind_to(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+
+    // Name of the OpenGL texture object
+    pub(crate) fn id(&self) -> GLuint {
+        self.id
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        resources::release(self.owner, Resource::Texture(self.id));
+    }
+}
+
+/// Represents a cube map texture, made of six square faces
+/// and sampled with a direction rather than coordinates
+pub struct CubeMap {
+    pub size: u32,
+    pub format: TextureFormat,
+
+    // Number of mipmap levels of each face
+    levels: Cell<u32>,
+
+    id: GLuint,
+    owner: ContextId
+}
+
+impl CubeMap {
+    /// Create a cube map whose faces have the specified size,
+    /// without any data, to be rendered to
+    pub fn empty(size: u32, format: TextureFormat) -> CubeMap {
+        CubeMap::with_levels(size, format, 1)
+    }
+
+    /// Create an empty cube map with the specified number of mipmap
+    /// levels, each one half the size of the previous one. They are
+    /// sampled with trilinear filtering
+    pub fn with_levels(size: u32, format: TextureFormat, levels: u32) -> CubeMap {
+        let kind = if format.is_float() { gl::FLOAT } else { gl::UNSIGNED_BYTE };
+        let levels = levels.max(1);
+
+        unsafe {
+            let mut id: GLuint = 0;
+
+            gl::GenTextures(1, (&mut id) as *mut GLuint);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+
+            for level in 0..levels {
+                let level_size = (size >> level).max(1);
+
+                for face in 0..6 {
+                    gl::TexImage2D(
+                        gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                        level as GLint,
+                        format.to_gl_const() as GLint,
+                        level_size as GLsizei,
+                        level_size as GLsizei,
+                        0,
+                        gl::RGBA,
+                        kind,
+                        0 as *const c_void
+                    );
+                }
+            }
+
+            let min_filter = if levels > 1 { gl::LINEAR_MIPMAP_LINEAR } else { gl::LINEAR };
+
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAX_LEVEL, levels as i32 - 1);
+
+            // Filter across the edges of the faces
+            gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+
+            CubeMap {
+                size: size,
+                format: format,
+                levels: Cell::new(levels),
+                id: id,
+                owner: resources::current_context()
+            }
+        }
+    }
+
+    /// Create a cube map from six 8 bit RGBA images, in the order
+    /// +X, -X, +Y, -Y, +Z, -Z. The faces must be square and of the same
+    /// size, with their rows ordered from the top to the bottom as seen
+    /// from the inside of the cube
+    pub fn from_images(faces: &[Image]) -> Result<CubeMap> {
+        let size = faces.first().map(|face| face.width).unwrap_or(0);
+
+        if faces.len() != 6 || size == 0 || faces.iter().any(|face| face.width != size || face.height != size) {
+            return Err(invalid_input("cube map faces must be six square images of the same size"));
+        }
+
+        let cube_map = CubeMap::empty(size, TextureFormat::Rgba8);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, cube_map.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+
+            for (i, face) in faces.iter().enumerate() {
+                gl::TexSubImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as GLenum,
+                    0,
+                    0,
+                    0,
+                    size as GLsizei,
+                    size as GLsizei,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    face.data.as_ptr() as *const c_void
+                );
+            }
+
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+
+        Ok(cube_map)
+    }
+
+    /// Create a cube map from an image laid out as a cross, either
+    /// horizontal (4 faces wide, 3 faces high) or vertical (3 faces
+    /// wide, 4 faces high). The +Y and -Y faces are above and below +Z,
+    /// with -X, +Z, +X and -Z from left to right. In a vertical cross,
+    /// -Z is below -Y and upside down
+    pub fn from_cross(image: &Image) -> Result<CubeMap> {
+        let horizontal = image.width * 3 == image.height * 4;
+        let vertical = image.width * 4 == image.height * 3;
+
+        if !horizontal && !vertical {
+            return Err(invalid_input(format!("invalid cube map cross of {}x{} pixels", image.width, image.height)));
+        }
+
+        let size = if horizontal { image.width / 4 } else { image.width / 3 };
+        let face = |col: u32, row: u32| image.crop(col * size, row * size, size, size);
+
+        let back = if horizontal {
+            face(3, 1)
+        }
+        else {
+            let mut back = face(1, 3);
+            back.rotate_half_turn();
+            back
+        };
+
+        CubeMap::from_images(&[face(2, 1), face(0, 1), face(1, 0), face(1, 2), face(1, 1), back])
+    }
+
+    /// Create a cube map whose faces have the specified size by projecting
+    /// an equirectangular HDR image onto them. The cube map stores floating
+    /// point colors, and all its mipmap levels are computed
+    pub fn from_equirect(image: &HdrImage, size: u32) -> Result<CubeMap> {
+        let rgba: Vec<f32> = image.data.chunks(3)
+            .flat_map(|rgb| vec![rgb[0], rgb[1], rgb[2], 1.0])
+            .collect();
+
+        // The image wraps around horizontally, but not vertically
+        let equirect = Texture::from_floats(image.width, image.height, TextureFormat::Rgba32F, &rgba);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, equirect.id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        let levels = 32 - size.max(1).leading_zeros();
+        let cube_map = CubeMap::with_levels(size, TextureFormat::Rgba16F, levels);
+
+        let program = try!(bake::face_program(include_str!("glsl/ibl/equirect.frag")));
+
+        {
+            let baker = Baker::new();
+
+            program.bind();
+            let _ = program.set_uniform("equirect", &0);
+
+            equirect.bind_to(0);
+            try!(baker.draw_faces(&program, &cube_map, 0));
+        }
+
+        cube_map.generate_mipmaps();
+        Ok(cube_map)
+    }
+
+    /// Get the number of mipmap levels of each face
+    pub fn levels(&self) -> u32 {
+        self.levels.get()
+    }
+
+    /// Change how the cube map is sampled. When mipmaps are needed
+    /// and the cube map has none, they are generated from the faces.
+    /// Existing mipmap levels are kept, as they may have been rendered
+    pub fn set_options(&self, options: TextureOptions) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            options.apply(gl::TEXTURE_CUBE_MAP);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+
+        if options.has_mipmaps() && self.levels() == 1 {
+            self.generate_mipmaps();
+        }
+    }
+
+    /// Compute the mipmap levels of the faces from the first one.
+    /// A cube map created without mipmaps gets a complete chain
+    pub fn generate_mipmaps(&self) {
+        if self.levels() == 1 {
+            let levels = 32 - self.size.max(1).leading_zeros();
+
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+                gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAX_LEVEL, levels as i32 - 1);
+            }
+
+            self.levels.set(levels);
+        }
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+    }
+
+    /// Bind the cube map to the specified texture unit. The
+    /// active unit is set back to the first one afterwards
+    pub fn bind_to(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+
+    // Name of the OpenGL texture object
+    pub(crate) fn id(&self) -> GLuint {
+        self.id
+    }
+}
+
+impl Drop for CubeMap {
+    fn drop(&mut self) {
+        resources::release(self.owner, Resource::Texture(self.id));
+    }
+}
+
+// Error for images that cannot be used as textures
+fn invalid_input<S: Into<String>>(msg: S) -> Error {
+    Error::Io(io::Error::new(io::ErrorKind::InvalidInput, msg.into()))
 }
 
 /// Represents all the drawable primitives
 /// that can be rendered to the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PrimitiveType {
     Points,
     Lines,
@@ -146,18 +929,230 @@ impl PrimitiveType {
     }
 }
 
+/// Largest number of morph targets blended by the vertex shader of the
+/// built-in programs. Their deltas use two vertex attributes each, along
+/// with the attributes of skinned vertices
+pub const MAX_MORPH_TARGETS: usize = 4;
+
+/// A morph target (or blend shape): offsets moving the vertices of a
+/// mesh, blended with the other targets according to their weights
+#[derive(Debug, Clone, PartialEq)]
+pub struct MorphTarget {
+    /// Offset of the position of each vertex
+    pub positions: Vec<[f32; 3]>,
+
+    /// Offset of the normal of each vertex, empty if they do not change
+    pub normals: Vec<[f32; 3]>
+}
+
+impl MorphTarget {
+    /// Create a morph target moving the positions of the vertices
+    pub fn new(positions: Vec<[f32; 3]>) -> MorphTarget {
+        MorphTarget {
+            positions: positions,
+            normals: Vec::new()
+        }
+    }
+
+    /// Also change the normals of the vertices
+    pub fn normals(mut self, normals: Vec<[f32; 3]>) -> MorphTarget {
+        self.normals = normals;
+        self
+    }
+}
+
+/// Where the morph targets of a mesh are blended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MorphMode {
+    /// In the vertex shader of the built-in programs, for up to
+    /// `MAX_MORPH_TARGETS` targets. The weights are set for each
+    /// instance with `Context::set_morph_weights`
+    Gpu,
+
+    /// On the CPU into a dynamic vertex buffer, for any number of
+    /// targets. The weights are set with `Mesh::set_morph_weights`,
+    /// and apply to every instance of the mesh drawn afterwards
+    Cpu
+}
+
+// Deltas of the morph targets blended on the GPU, for one vertex
+#[repr(C)]
+struct MorphDeltas {
+    morph_position0: [GLfloat; 3],
+    morph_position1: [GLfloat; 3],
+    morph_position2: [GLfloat; 3],
+    morph_position3: [GLfloat; 3],
+    morph_normal0: [GLfloat; 3],
+    morph_normal1: [GLfloat; 3],
+    morph_normal2: [GLfloat; 3],
+    morph_normal3: [GLfloat; 3]
+}
+
+// The deltas follow the attributes of skinned vertices
+vertex_layout!(MorphDeltas {
+    morph_position0: 6,
+    morph_position1: 7,
+    morph_position2: 8,
+    morph_position3: 9,
+    morph_normal0: 10,
+    morph_normal1: 11,
+    morph_normal2: 12,
+    morph_normal3: 13
+});
+
+// Attributes of the deltas of morph targets, bound by the built-in programs
+pub(crate) fn morph_attributes() -> Vec<Attribute> {
+    MorphDeltas::attributes()
+}
+
+// Morph targets of a mesh, and what is needed to blend them
+enum Morph {
+    // Deltas stored in their own buffer
+    Gpu {
+        targets: usize,
+        vbo: GLuint
+    },
+
+    // Vertex data blended into the vertex buffer, whose positions
+    // and normals are at the specified offsets of each vertex
+    Cpu {
+        targets: Vec<MorphTarget>,
+        vertices: Vec<u8>,
+        stride: usize,
+        position: usize,
+        normal: Option<usize>
+    }
+}
+
 /// Represents a renderable 3D object
 pub struct Mesh {
     primitive: PrimitiveType,
     count: i32,
+    vao: GLuint,
     vbo: GLuint,
-    ibo: Option<GLuint>
+    ibo: Option<GLuint>,
+    morph: Option<Morph>,
+    owner: ContextId
 }
 
 impl Mesh {
-    /// Create a new mesh
-    pub fn new(p: PrimitiveType, vertices: &[Vertex], indicies: Option<&[u32]>) -> Mesh {
+    /// Create a new mesh. Any vertex type describing its layout
+    /// can be used
+    pub fn new<V: VertexLayout>(p: PrimitiveType, vertices: &[V], indicies: Option<&[u32]>) -> Mesh {
+        Mesh::with_usage(p, vertices, indicies, gl::STATIC_DRAW)
+    }
+
+    /// Create a new mesh with morph targets, which each move every
+    /// vertex. Blending them on the CPU requires the vertex type to have
+    /// a `position` attribute (and a `normal` one if the targets change
+    /// the normals) made of three floats
+    pub fn with_morph_targets<V: VertexLayout>(p: PrimitiveType, vertices: &[V], indicies: Option<&[u32]>,
+                                              targets: &[MorphTarget], mode: MorphMode) -> Result<Mesh> {
+        let has_normals = targets.iter().any(|target| !target.normals.is_empty());
+
+        for target in targets {
+            if target.positions.len() != vertices.len() || (has_normals && target.normals.len() != vertices.len()) {
+                return Err(invalid_input("morph targets must have one offset per vertex"));
+            }
+        }
+
+        match mode {
+            MorphMode::Gpu => {
+                if targets.len() > MAX_MORPH_TARGETS {
+                    return Err(invalid_input(format!("at most {} morph targets are blended on the GPU", MAX_MORPH_TARGETS)));
+                }
+
+                let mut mesh = Mesh::new(p, vertices, indicies);
+
+                // Deltas of the missing targets are left to zero
+                let mut deltas = vec![[0.0f32; 3]; vertices.len() * MAX_MORPH_TARGETS * 2];
+
+                for (t, target) in targets.iter().enumerate() {
+                    for i in 0..vertices.len() {
+                        deltas[i * MAX_MORPH_TARGETS * 2 + t] = target.positions[i];
+
+                        if has_normals {
+                            deltas[i * MAX_MORPH_TARGETS * 2 + MAX_MORPH_TARGETS + t] = target.normals[i];
+                        }
+                    }
+                }
+
+                unsafe {
+                    gl::BindVertexArray(mesh.vao);
+
+                    let mut vbo: GLuint = 0;
+
+                    gl::GenBuffers(1, (&mut vbo) as *mut GLuint);
+                    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+                    gl::BufferData(
+                        gl::ARRAY_BUFFER,
+                        (std::mem::size_of::<[f32; 3]>() * deltas.len()) as GLsizeiptr,
+                        deltas.as_ptr() as *const c_void,
+                        gl::STATIC_DRAW
+                    );
+
+                    enable_attributes(&MorphDeltas::attributes(), std::mem::size_of::<MorphDeltas>());
+
+                    gl::BindVertexArray(0);
+                    gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+
+                    mesh.morph = Some(Morph::Gpu {
+                        targets: targets.len(),
+                        vbo: vbo
+                    });
+                }
+
+                Ok(mesh)
+            },
+            MorphMode::Cpu => {
+                let attributes = V::attributes();
+
+                // Offset of an attribute made of three floats
+                let offset = |name: &str| attributes.iter()
+                    .find(|attr| attr.name == name && attr.kind == AttributeType::Float && attr.components == 3)
+                    .map(|attr| attr.offset);
+
+                let position = match offset("position") {
+                    Some(position) => position,
+                    None => return Err(Error::VertexLayout("morphing on the CPU needs a position attribute".to_string()))
+                };
+
+                let normal = match (has_normals, offset("normal")) {
+                    (false, _) => None,
+                    (true, Some(normal)) => Some(normal),
+                    (true, None) => return Err(Error::VertexLayout("morphing normals needs a normal attribute".to_string()))
+                };
+
+                let stride = std::mem::size_of::<V>();
+                let data = unsafe {
+                    std::slice::from_raw_parts(vertices.as_ptr() as *const u8, stride * vertices.len()).to_vec()
+                };
+
+                let mut mesh = Mesh::with_usage(p, vertices, indicies, gl::DYNAMIC_DRAW);
+
+                mesh.morph = Some(Morph::Cpu {
+                    targets: targets.to_vec(),
+                    vertices: data,
+                    stride: stride,
+                    position: position,
+                    normal: normal
+                });
+
+                Ok(mesh)
+            }
+        }
+    }
+
+    // Create a new mesh, whose vertex buffer has the specified usage
+    fn with_usage<V: VertexLayout>(p: PrimitiveType, vertices: &[V], indicies: Option<&[u32]>, usage: GLenum) -> Mesh {
         unsafe {
+            // Create a VAO to record the vertex layout
+            let mut vao: GLuint = 0;
+
+            gl::GenVertexArrays(1, (&mut vao) as *mut GLuint);
+            gl::BindVertexArray(vao);
+
             // Create a VBO to store vertex data
             let mut vbo: GLuint = 0;
 
@@ -167,13 +1162,14 @@ impl Mesh {
             // Upload the vertex data to the craphics card
             gl::BufferData(
                 gl::ARRAY_BUFFER,
-                (std::mem::size_of::<Vertex>() * vertices.len()) as GLsizeiptr,
+                (std::mem::size_of::<V>() * vertices.len()) as GLsizeiptr,
                 vertices.as_ptr() as *const c_void,
-                gl::STATIC_DRAW
+                usage
             );
 
-            // Unbind VBO
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            // Specify where each attribute of each vertex
+            // is in GPU memory
+            enable_attributes(&V::attributes(), std::mem::size_of::<V>());
 
             // Optional IBO
             let mut ibo: Option<GLuint> = None;
@@ -181,7 +1177,7 @@ impl Mesh {
 
             // If the caller specified indicies
             if let Some(indicies) = indicies {
-                // Create a new IBO
+                // Create a new IBO, its binding is recorded by the VAO
                 let mut ibo_id: GLuint = 0;
 
                 gl::GenBuffers(1, (&mut ibo_id) as *mut GLuint);
@@ -195,89 +1191,155 @@ impl Mesh {
                     gl::STATIC_DRAW
                 );
 
-                // Unbind the IBO
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-
                 // Specify the IBO and use the correct element count
                 ibo = Some(ibo_id);
                 count = indicies.len() as i32;
             }
 
+            // Unbind the VAO first, so that it keeps the IBO binding
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
+
             Mesh{
                 primitive: p,
                 count: count,
+                vao: vao,
                 vbo: vbo,
-                ibo: ibo
+                ibo: ibo,
+                morph: None,
+                owner: resources::current_context()
             }
         }
     }
 
-    /// Render the mesh to the screen
-    pub fn render(&self) {
-        unsafe {
-            gl::Enable(gl::DEPTH_TEST);
-            // Bind the VBO
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+    /// Get the number of morph targets of the mesh
+    pub fn morph_targets(&self) -> usize {
+        match self.morph {
+            Some(Morph::Gpu { targets, .. }) => targets,
+            Some(Morph::Cpu { ref targets, .. }) => targets.len(),
+            None => 0
+        }
+    }
 
-            // Enable the atttributes
-            gl::EnableVertexAttribArray(0);
-            gl::EnableVertexAttribArray(1);
-            gl::EnableVertexAttribArray(2);
+    /// Get where the morph targets of the mesh are blended, if it has any
+    pub fn morph_mode(&self) -> Option<MorphMode> {
+        match self.morph {
+            Some(Morph::Gpu { .. }) => Some(MorphMode::Gpu),
+            Some(Morph::Cpu { .. }) => Some(MorphMode::Cpu),
+            None => None
+        }
+    }
 
-            // Specify where each different attirbute of each vertex
-            // are in GPU memory
+    /// Blend the morph targets blended on the CPU with the specified
+    /// weights, one per target, the missing ones being zero. Nothing
+    /// is done for the other meshes
+    pub fn set_morph_weights(&self, weights: &[f32]) {
+        let (targets, vertices, stride, position, normal) = match self.morph {
+            Some(Morph::Cpu { ref targets, ref vertices, stride, position, normal }) => (targets, vertices, stride, position, normal),
+            _ => return
+        };
 
-            // Position attribute
-            // Offsert in vertex memory structure: 0
-            gl::VertexAttribPointer(
-                0 as GLuint,
-                3 as GLint,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<Vertex>() as GLsizei,
-                0 as *const c_void
-            );
+        let mut data = vertices.clone();
 
-            // Color attribute
-            // Offsert in vertex memory structure: 3 floats, 12 bytes
-            gl::VertexAttribPointer(
-                1 as GLuint,
-                4 as GLint,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<Vertex>() as GLsizei,
-                (std::mem::size_of::<f32>() * 3) as *const c_void
-            );
+        // Add the weighted offsets to three floats of each vertex
+        let mut add = |offset: usize, deltas: &[[f32; 3]], weight: f32| {
+            for (i, delta) in deltas.iter().enumerate() {
+                for (c, &component) in delta.iter().enumerate() {
+                    let at = i * stride + offset + c * 4;
+                    let mut bytes = [0u8; 4];
 
-            // Texture coordinates attribute
-            // Offsert in vertex memory structure: 3 floats + 4 floats, 28 bytes
-            gl::VertexAttribPointer(
-                2 as GLuint,
-                2 as GLint,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<Vertex>() as GLsizei,
-                (std::mem::size_of::<f32>() * (3 + 4)) as *const c_void
-            );
+                    bytes.copy_from_slice(&data[at..at + 4]);
+                    let value = f32::from_ne_bytes(bytes) + weight * component;
+                    data[at..at + 4].copy_from_slice(&value.to_ne_bytes());
+                }
+            }
+        };
+
+        for (target, &weight) in targets.iter().zip(weights.iter()).filter(|&(_, &weight)| weight != 0.0) {
+            add(position, &target.positions, weight);
+
+            if let Some(normal) = normal {
+                add(normal, &target.normals, weight);
+            }
+        }
+
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferSubData(gl::ARRAY_BUFFER, 0, data.len() as GLsizeiptr, data.as_ptr() as *const c_void);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+
+    /// Render the mesh to the screen
+    pub fn render(&self) {
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+
+            // Bind the VAO, which holds the whole vertex layout
+            gl::BindVertexArray(self.vao);
 
             // If indicies are being used
-            if let Some(ibo) = self.ibo {
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ibo);
+            if self.ibo.is_some() {
                 gl::DrawElements(self.primitive.to_gl_const(), self.count, gl::UNSIGNED_INT, 0 as *const c_void);
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
             }
             // Otherwise just render the vertices
             else {
                 gl::DrawArrays(self.primitive.to_gl_const(), 0, self.count);
             }
 
-            // Disable the attributes
-            gl::DisableVertexAttribArray(0);
-            gl::DisableVertexAttribArray(1);
-            gl::DisableVertexAttribArray(2);
+            // Unbind the VAO
+            gl::BindVertexArray(0);
+        }
+    }
+}
 
-            // Unbind VBO
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+impl Drop for Mesh {
+    fn drop(&mut self) {
+        resources::release(self.owner, Resource::VertexArray(self.vao));
+        resources::release(self.owner, Resource::Buffer(self.vbo));
+
+        if let Some(ibo) = self.ibo {
+            resources::release(self.owner, Resource::Buffer(ibo));
+        }
+
+        if let Some(Morph::Gpu { vbo, .. }) = self.morph {
+            resources::release(self.owner, Resource::Buffer(vbo));
+        }
+    }
+}
+
+// Specify where each attribute of the bound vertex buffer is, for the
+// bound VAO. The stride is the size of each vertex
+unsafe fn enable_attributes(attributes: &[Attribute], stride: usize) {
+    for attr in attributes {
+        gl::EnableVertexAttribArray(attr.location);
+
+        match attr.kind {
+            // Integer attributes are exposed as integers to the shader,
+            // unless they need to be normalized
+            AttributeType::Float => (),
+            _ if attr.normalized => (),
+            _ => {
+                gl::VertexAttribIPointer(
+                    attr.location,
+                    attr.components,
+                    attr.kind.to_gl_const(),
+                    stride as GLsizei,
+                    attr.offset as *const c_void
+                );
+
+                continue;
+            }
         }
+
+        gl::VertexAttribPointer(
+            attr.location,
+            attr.components,
+            attr.kind.to_gl_const(),
+            attr.normalized as GLboolean,
+            stride as GLsizei,
+            attr.offset as *const c_void
+        );
     }
 }
@@ -0,0 +1,372 @@
+use std::f32;
+use std::collections::HashMap;
+
+use cgmath::{InnerSpace, ElementWise};
+
+use math::Vec3;
+use super::{Vertex, PrimitiveType, Mesh};
+
+/// Geometry of a procedural shape: indexed triangles, wound counter
+/// clockwise when seen from the side their normals point to.
+/// Texture coordinates start at the top left of the texture
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>
+}
+
+impl Shape {
+    // Create an empty shape
+    fn new() -> Shape {
+        Shape {
+            vertices: Vec::new(),
+            indices: Vec::new()
+        }
+    }
+
+    /// Set the color of every vertex
+    pub fn color(mut self, r: f32, g: f32, b: f32, a: f32) -> Shape {
+        for vertex in self.vertices.iter_mut() {
+            vertex.color = [r, g, b, a];
+        }
+
+        self
+    }
+
+    /// Upload the shape to the graphics card
+    pub fn to_mesh(&self) -> Mesh {
+        Mesh::new(PrimitiveType::Triangles, &self.vertices, Some(&self.indices))
+    }
+
+    // Add a surface given by a vertex for each (u, v) in [0, 1], sampled
+    // on a grid of cells. The surface faces the side of the cross product
+    // of its derivatives along v then u, and triangles collapsed by the
+    // parametrization (like at the poles of a sphere) are left out
+    fn surface<F: Fn(f32, f32) -> Vertex>(&mut self, columns: u32, rows: u32, vertex: F) {
+        let first = self.vertices.len() as u32;
+
+        for row in 0..rows + 1 {
+            for column in 0..columns + 1 {
+                self.vertices.push(vertex(column as f32 / columns as f32, row as f32 / rows as f32));
+            }
+        }
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let a = first + row * (columns + 1) + column;
+                let (b, c) = (a + 1, a + columns + 1);
+
+                self.triangle(a, c, b);
+                self.triangle(b, c, c + 1);
+            }
+        }
+    }
+
+    // Add a surface of revolution around the Y axis. The profile gives
+    // the radius and height of each v in [0, 1], along with the radial
+    // and vertical components of the normal, and it faces outwards
+    // when going down the outside. The seam of the texture is at the back
+    fn revolve<F: Fn(f32) -> (f32, f32, f32, f32)>(&mut self, segments: u32, rows: u32, v: (f32, f32), profile: F) {
+        self.surface(segments, rows, |s, t| {
+            let (radius, y, normal_radius, normal_y) = profile(t);
+            let (sin, cos) = turn(s - 0.5);
+
+            Vertex::new(radius * sin, y, radius * cos)
+                .normal(normal_radius * sin, normal_y, normal_radius * cos)
+                .texcoords(s, v.0 + (v.1 - v.0) * t)
+        });
+    }
+
+    // Add a disc facing up or down, at the specified height
+    fn disc(&mut self, radius: f32, y: f32, segments: u32, up: bool) {
+        let center = self.vertices.len() as u32;
+        let (normal, flip) = if up { (1.0, 1.0) } else { (-1.0, -1.0) };
+
+        // Textures are projected from the side the disc faces
+        let vertex = |x: f32, z: f32| {
+            Vertex::new(x, y, z)
+                .normal(0.0, normal, 0.0)
+                .texcoords(0.5 + x / (2.0 * radius), 0.5 + flip * z / (2.0 * radius))
+        };
+
+        self.vertices.push(vertex(0.0, 0.0));
+
+        for segment in 0..segments {
+            let (sin, cos) = turn(segment as f32 / segments as f32);
+            self.vertices.push(vertex(radius * sin, radius * cos));
+        }
+
+        for segment in 0..segments {
+            let a = center + 1 + segment;
+            let b = center + 1 + (segment + 1) % segments;
+
+            if up {
+                self.triangle(center, a, b);
+            }
+            else {
+                self.triangle(center, b, a);
+            }
+        }
+    }
+
+    // Add a triangle, unless two of its corners are at the same place
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        let position = |i: u32| self.vertices[i as usize].position;
+
+        if position(a) == position(b) || position(b) == position(c) || position(c) == position(a) {
+            return;
+        }
+
+        self.indices.extend_from_slice(&[a, b, c]);
+    }
+}
+
+/// Create a flat plane facing up, centered on the origin and
+/// divided into `segments_x` by `segments_z` cells
+pub fn plane(width: f32, depth: f32, segments_x: u32, segments_z: u32) -> Shape {
+    grid(width, depth, segments_x, segments_z, |_, _| 0.0)
+}
+
+/// Create a grid of `segments_x` by `segments_z` cells centered on the
+/// origin, whose vertices are raised by the specified function of their
+/// horizontal position, like a terrain. The normals follow its slopes
+pub fn grid<F: Fn(f32, f32) -> f32>(width: f32, depth: f32, segments_x: u32, segments_z: u32, height: F) -> Shape {
+    let (segments_x, segments_z) = (segments_x.max(1), segments_z.max(1));
+
+    // Slopes are measured across half a cell
+    let (dx, dz) = (width / segments_x as f32 / 2.0, depth / segments_z as f32 / 2.0);
+
+    let mut shape = Shape::new();
+
+    shape.surface(segments_x, segments_z, |u, v| {
+        let (x, z) = ((u - 0.5) * width, (v - 0.5) * depth);
+
+        let slope_x = (height(x + dx, z) - height(x - dx, z)) / (2.0 * dx);
+        let slope_z = (height(x, z + dz) - height(x, z - dz)) / (2.0 * dz);
+        let normal = Vec3::new(-slope_x, 1.0, -slope_z).normalize();
+
+        Vertex::new(x, height(x, z), z)
+            .normal(normal.x, normal.y, normal.z)
+            .texcoords(u, v)
+    });
+
+    shape
+}
+
+/// Create a box centered on the origin, whose faces are divided into
+/// `segments` by `segments` cells. Each face is textured by the whole
+/// texture, upright on the sides
+pub fn cuboid(width: f32, height: f32, depth: f32, segments: u32) -> Shape {
+    let segments = segments.max(1);
+    let size = Vec3::new(width, height, depth);
+
+    // Normal, then the directions of u and v on each face
+    let faces = [
+        (Vec3::unit_z(), Vec3::unit_x(), -Vec3::unit_y()),
+        (-Vec3::unit_z(), -Vec3::unit_x(), -Vec3::unit_y()),
+        (Vec3::unit_x(), -Vec3::unit_z(), -Vec3::unit_y()),
+        (-Vec3::unit_x(), Vec3::unit_z(), -Vec3::unit_y()),
+        (Vec3::unit_y(), Vec3::unit_x(), Vec3::unit_z()),
+        (-Vec3::unit_y(), Vec3::unit_x(), -Vec3::unit_z())
+    ];
+
+    let mut shape = Shape::new();
+
+    for &(normal, u_axis, v_axis) in faces.iter() {
+        shape.surface(segments, segments, |u, v| {
+            let p = (normal * 0.5 + u_axis * (u - 0.5) + v_axis * (v - 0.5)).mul_element_wise(size);
+
+            Vertex::new(p.x, p.y, p.z)
+                .normal(normal.x, normal.y, normal.z)
+                .texcoords(u, v)
+        });
+    }
+
+    shape
+}
+
+/// Create a sphere centered on the origin, made of `segments` around
+/// its axis by `rings` from pole to pole. Textures are wrapped around
+/// it, the top of the texture being at the north pole
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Shape {
+    let mut shape = Shape::new();
+
+    shape.revolve(segments.max(3), rings.max(2), (0.0, 1.0), |v| {
+        let (sin, cos) = half_turn(v);
+        (radius * sin, radius * cos, sin, cos)
+    });
+
+    shape
+}
+
+/// Create a sphere centered on the origin by subdividing the faces of an
+/// icosahedron, so that its triangles are evenly spread. Each subdivision
+/// splits every triangle into four. Textures are wrapped around it like
+/// on a UV sphere, the texture coordinates going slightly above 1 where
+/// triangles cross the seam
+pub fn icosphere(radius: f32, subdivisions: u32) -> Shape {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+
+    let mut points: Vec<Vec3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0)
+    ].iter().map(|&(x, y, z)| Vec3::new(x, y, z).normalize()).collect();
+
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1]
+    ];
+
+    for _ in 0..subdivisions {
+        // Points in the middle of the edges, shared by their two triangles
+        let mut middles: HashMap<(u32, u32), u32> = HashMap::new();
+
+        let mut middle = |a: u32, b: u32, points: &mut Vec<Vec3>| {
+            *middles.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                points.push(((points[a as usize] + points[b as usize]) / 2.0).normalize());
+                points.len() as u32 - 1
+            })
+        };
+
+        triangles = triangles.iter().flat_map(|&[a, b, c]| {
+            let (ab, bc, ca) = (middle(a, b, &mut points), middle(b, c, &mut points), middle(c, a, &mut points));
+            vec![[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+        }).collect();
+    }
+
+    let mut shape = Shape::new();
+
+    shape.vertices = points.iter().map(|p| {
+        Vertex::new(p.x * radius, p.y * radius, p.z * radius)
+            .normal(p.x, p.y, p.z)
+            .texcoords(0.5 + p.x.atan2(p.z) / (2.0 * f32::consts::PI), p.y.clamp(-1.0, 1.0).acos() / f32::consts::PI)
+    }).collect();
+
+    // Triangles crossing the seam at the back use copies of their
+    // vertices on the left half of the texture, moved past its right edge
+    let mut copies: HashMap<u32, u32> = HashMap::new();
+
+    for triangle in triangles.iter_mut() {
+        let u: Vec<f32> = triangle.iter().map(|&i| shape.vertices[i as usize].texcoords[0]).collect();
+
+        if u.iter().cloned().fold(0.0, f32::max) - u.iter().cloned().fold(1.0, f32::min) <= 0.5 {
+            continue;
+        }
+
+        for index in triangle.iter_mut() {
+            let vertices = &mut shape.vertices;
+
+            if vertices[*index as usize].texcoords[0] >= 0.5 {
+                continue;
+            }
+
+            *index = *copies.entry(*index).or_insert_with(|| {
+                let mut copy = vertices[*index as usize];
+                copy.texcoords[0] += 1.0;
+
+                vertices.push(copy);
+                vertices.len() as u32 - 1
+            });
+        }
+    }
+
+    shape.indices = triangles.iter().flat_map(|triangle| triangle.iter().cloned()).collect();
+    shape
+}
+
+/// Create a closed cylinder centered on the origin, along the Y axis,
+/// made of `segments` around its axis by `stacks` along it
+pub fn cylinder(radius: f32, height: f32, segments: u32, stacks: u32) -> Shape {
+    let segments = segments.max(3);
+    let mut shape = Shape::new();
+
+    shape.revolve(segments, stacks.max(1), (0.0, 1.0), |v| (radius, height * (0.5 - v), 1.0, 0.0));
+    shape.disc(radius, height / 2.0, segments, true);
+    shape.disc(radius, -height / 2.0, segments, false);
+
+    shape
+}
+
+/// Create a closed cone centered on the origin, pointing up along
+/// the Y axis, made of `segments` around its axis by `stacks` along it
+pub fn cone(radius: f32, height: f32, segments: u32, stacks: u32) -> Shape {
+    let segments = segments.max(3);
+    let slant = Vec3::new(height, radius, 0.0).normalize();
+
+    let mut shape = Shape::new();
+
+    shape.revolve(segments, stacks.max(1), (0.0, 1.0), |v| (radius * v, height * (0.5 - v), slant.x, slant.y));
+    shape.disc(radius, -height / 2.0, segments, false);
+
+    shape
+}
+
+/// Create a capsule centered on the origin, along the Y axis: a cylinder
+/// of the specified height capped by two hemispheres, made of `segments`
+/// around its axis by `rings` on each hemisphere. Textures are wrapped
+/// around it, stretched along its whole length
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Shape {
+    let (segments, rings) = (segments.max(3), rings.max(1));
+
+    // Texture coordinates follow the length of the profile
+    let length = f32::consts::PI * radius + height;
+    let (top, bottom) = (f32::consts::PI * radius / 2.0 / length, 1.0 - f32::consts::PI * radius / 2.0 / length);
+
+    let mut shape = Shape::new();
+
+    shape.revolve(segments, rings, (0.0, top), |v| {
+        let (sin, cos) = half_turn(v / 2.0);
+        (radius * sin, height / 2.0 + radius * cos, sin, cos)
+    });
+
+    shape.revolve(segments, 1, (top, bottom), |v| (radius, height * (0.5 - v), 1.0, 0.0));
+
+    shape.revolve(segments, rings, (bottom, 1.0), |v| {
+        let (sin, cos) = half_turn(0.5 + v / 2.0);
+        (radius * sin, -height / 2.0 + radius * cos, sin, cos)
+    });
+
+    shape
+}
+
+/// Create a torus centered on the origin, around the Y axis, whose
+/// tube of the specified radius is `radius` away from the center. It
+/// is made of `segments` around its axis by `tube_segments` around
+/// the tube, whose inside is at the middle of the texture
+pub fn torus(radius: f32, tube_radius: f32, segments: u32, tube_segments: u32) -> Shape {
+    let mut shape = Shape::new();
+
+    // Go around the tube from its top, outwards first
+    shape.revolve(segments.max(3), tube_segments.max(3), (0.0, 1.0), |v| {
+        let (sin, cos) = turn(0.25 - v);
+        (radius + tube_radius * cos, tube_radius * sin, cos, sin)
+    });
+
+    shape
+}
+
+// Sine and cosine of a fraction of a turn, exact at the quarters
+// so that the vertices at the poles and seams match
+fn turn(fraction: f32) -> (f32, f32) {
+    let quarters = fraction * 4.0;
+
+    if quarters == quarters.round() {
+        match (quarters as i32).rem_euclid(4) {
+            0 => (0.0, 1.0),
+            1 => (1.0, 0.0),
+            2 => (0.0, -1.0),
+            _ => (-1.0, 0.0)
+        }
+    }
+    else {
+        (fraction * 2.0 * f32::consts::PI).sin_cos()
+    }
+}
+
+// Sine and cosine of a fraction of a half turn
+fn half_turn(fraction: f32) -> (f32, f32) {
+    turn(fraction / 2.0)
+}
@@ -0,0 +1,601 @@
+use std::io::{self, BufRead, BufReader};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{Error, Result};
+use mesh::{Vertex, Texture, PrimitiveType, Mesh};
+use material::{Material, AlphaMode};
+
+/// Represents a Wavefront OBJ model, loaded in main memory. Its faces
+/// are split by object, group and material into indexed triangle lists
+pub struct Obj {
+    pub meshes: Vec<ObjMesh>,
+
+    /// Materials of the libraries referenced by the model
+    pub materials: Vec<ObjMaterial>
+}
+
+/// Part of an OBJ model using a single material
+pub struct ObjMesh {
+    /// Name of the object or group the faces belong to, the
+    /// group being used when both are specified
+    pub name: String,
+
+    /// Name of the material used by the faces, if any
+    pub material: Option<String>,
+
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>
+}
+
+/// Represents a material of an MTL library. Besides the usual Phong
+/// parameters, the physically based extension (Pr, Pm, Ke) is read
+pub struct ObjMaterial {
+    pub name: String,
+
+    /// Ambient (Ka), diffuse (Kd), specular (Ks) and emissive (Ke) colors
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub emissive: [f32; 3],
+
+    /// Specular exponent (Ns)
+    pub shininess: f32,
+
+    /// Opacity, from the dissolve (d) or the transparency (Tr)
+    pub opacity: f32,
+
+    /// Physically based roughness (Pr) and metalness (Pm)
+    pub roughness: Option<f32>,
+    pub metallic: Option<f32>,
+
+    /// Paths of the diffuse (map_Kd), normal (norm, map_Bump, bump),
+    /// emissive (map_Ke) and opacity (map_d) textures, relative to the
+    /// library when it is parsed, to the working directory once loaded
+    pub diffuse_texture: Option<PathBuf>,
+    pub normal_texture: Option<PathBuf>,
+    pub emissive_texture: Option<PathBuf>,
+    pub opacity_texture: Option<PathBuf>
+}
+
+impl Obj {
+    /// Load an OBJ model, along with the MTL libraries it references,
+    /// which are searched next to it. The paths of the textures are
+    /// relative to the working directory
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Obj> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        let file = try!(File::open(path).map_err(Error::Io));
+        let (mut obj, libraries) = try!(parse_obj(BufReader::new(file)).map_err(Error::Io));
+
+        for library in libraries {
+            let file = try!(File::open(dir.join(&library)).map_err(Error::Io));
+            let mut materials = try!(ObjMaterial::parse(BufReader::new(file)).map_err(Error::Io));
+
+            for material in materials.iter_mut() {
+                for texture in material.textures_mut() {
+                    *texture = dir.join(&*texture);
+                }
+            }
+
+            obj.materials.extend(materials);
+        }
+
+        Ok(obj)
+    }
+
+    /// Parse the geometry of an OBJ model. The material libraries it
+    /// references are ignored, their materials can be added afterwards
+    pub fn parse<R: BufRead>(reader: R) -> io::Result<Obj> {
+        parse_obj(reader).map(|(obj, _)| obj)
+    }
+
+    /// Find a material by its name
+    pub fn material(&self, name: &str) -> Option<&ObjMaterial> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+}
+
+impl ObjMesh {
+    /// Upload the mesh to the graphics card
+    pub fn to_mesh(&self) -> Mesh {
+        Mesh::new(PrimitiveType::Triangles, &self.vertices, Some(&self.indices))
+    }
+}
+
+impl ObjMaterial {
+    /// Create a new material with the default values of MTL
+    pub fn new<S: Into<String>>(name: S) -> ObjMaterial {
+        ObjMaterial {
+            name: name.into(),
+            ambient: [0.0, 0.0, 0.0],
+            diffuse: [0.8, 0.8, 0.8],
+            specular: [0.0, 0.0, 0.0],
+            emissive: [0.0, 0.0, 0.0],
+            shininess: 0.0,
+            opacity: 1.0,
+            roughness: None,
+            metallic: None,
+            diffuse_texture: None,
+            normal_texture: None,
+            emissive_texture: None,
+            opacity_texture: None
+        }
+    }
+
+    /// Parse the materials of an MTL library
+    pub fn parse<R: BufRead>(reader: R) -> io::Result<Vec<ObjMaterial>> {
+        let mut materials: Vec<ObjMaterial> = Vec::new();
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = try!(line);
+            let mut words = line.split_whitespace();
+
+            let keyword = match words.next() {
+                Some(keyword) if !keyword.starts_with('#') => keyword,
+                _ => continue
+            };
+
+            let args: Vec<&str> = words.collect();
+
+            if keyword == "newmtl" {
+                materials.push(ObjMaterial::new(args.join(" ")));
+                continue;
+            }
+
+            let material = match materials.last_mut() {
+                Some(material) => material,
+                None => return Err(parse_error(number, "material statement before newmtl"))
+            };
+
+            match keyword {
+                "Ka" => material.ambient = try!(parse_color(number, &args)),
+                "Kd" => material.diffuse = try!(parse_color(number, &args)),
+                "Ks" => material.specular = try!(parse_color(number, &args)),
+                "Ke" => material.emissive = try!(parse_color(number, &args)),
+                "Ns" => material.shininess = try!(parse_float(number, args.first())),
+                "d" => material.opacity = try!(parse_float(number, args.last())),
+                "Tr" => material.opacity = 1.0 - try!(parse_float(number, args.last())),
+                "Pr" => material.roughness = Some(try!(parse_float(number, args.first()))),
+                "Pm" => material.metallic = Some(try!(parse_float(number, args.first()))),
+                "map_Kd" => material.diffuse_texture = Some(try!(parse_map(number, &args))),
+                "norm" | "map_Bump" | "map_bump" | "bump" => material.normal_texture = Some(try!(parse_map(number, &args))),
+                "map_Ke" => material.emissive_texture = Some(try!(parse_map(number, &args))),
+                "map_d" => material.opacity_texture = Some(try!(parse_map(number, &args))),
+
+                // Other statements, such as illumination models, are ignored
+                _ => ()
+            }
+        }
+
+        Ok(materials)
+    }
+
+    /// Convert the material into a physically based one. Without the
+    /// physically based extension, the material is a dielectric whose
+    /// roughness is derived from the specular exponent. Textures are
+    /// loaded by the specified function, and left out if it fails
+    pub fn to_material<F: FnMut(&Path) -> Option<Rc<Texture>>>(&self, mut load_texture: F) -> Material {
+        let roughness = match self.roughness {
+            Some(roughness) => roughness,
+            None => (2.0 / (self.shininess + 2.0)).sqrt()
+        };
+
+        let mut material = Material::new()
+            .base_color(self.diffuse[0], self.diffuse[1], self.diffuse[2], self.opacity)
+            .metallic_roughness(self.metallic.unwrap_or(0.0), roughness)
+            .emissive(self.emissive[0], self.emissive[1], self.emissive[2]);
+
+        if self.opacity < 1.0 || self.opacity_texture.is_some() {
+            material = material.alpha_mode(AlphaMode::Blend);
+        }
+
+        if let Some(texture) = self.diffuse_texture.as_ref().and_then(|path| load_texture(path)) {
+            material = material.base_color_texture(texture);
+        }
+
+        if let Some(texture) = self.normal_texture.as_ref().and_then(|path| load_texture(path)) {
+            material = material.normal_texture(texture, 1.0);
+        }
+
+        if let Some(texture) = self.emissive_texture.as_ref().and_then(|path| load_texture(path)) {
+            // Emissive textures are multiplied by the emissive factor
+            if self.emissive == [0.0, 0.0, 0.0] {
+                material = material.emissive(1.0, 1.0, 1.0);
+            }
+
+            material = material.emissive_texture(texture);
+        }
+
+        material
+    }
+
+    // Paths of all the textures of the material
+    fn textures_mut(&mut self) -> Vec<&mut PathBuf> {
+        vec![&mut self.diffuse_texture, &mut self.normal_texture, &mut self.emissive_texture, &mut self.opacity_texture]
+            .into_iter()
+            .filter_map(|texture| texture.as_mut())
+            .collect()
+    }
+}
+
+// Indices of the position, texture coordinates and normal of
+// a face vertex, starting at 0. Missing ones are negative
+type FaceVertex = (i64, i64, i64);
+
+// Mesh being built, with the indices of its vertices
+struct MeshBuilder {
+    mesh: ObjMesh,
+    indices: HashMap<FaceVertex, u32>,
+
+    // Vertices without normals, which are computed from their faces
+    missing_normals: Vec<u32>
+}
+
+impl MeshBuilder {
+    fn new(name: &str, material: Option<&String>) -> MeshBuilder {
+        MeshBuilder {
+            mesh: ObjMesh {
+                name: name.to_owned(),
+                material: material.cloned(),
+                vertices: Vec::new(),
+                indices: Vec::new()
+            },
+            indices: HashMap::new(),
+            missing_normals: Vec::new()
+        }
+    }
+
+    // Smooth normals of the vertices without one
+    fn finish(mut self) -> ObjMesh {
+        for &index in self.missing_normals.iter() {
+            let normal = &mut self.mesh.vertices[index as usize].normal;
+            let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+
+            if length > 0.0 {
+                for c in normal.iter_mut() {
+                    *c /= length;
+                }
+            }
+        }
+
+        self.mesh
+    }
+}
+
+// Attributes of the whole file, shared by every mesh
+struct ObjAttributes {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 3]>,
+    texcoords: Vec<[f32; 2]>,
+    normals: Vec<[f32; 3]>
+}
+
+// Parse an OBJ model, and the names of the material libraries it references
+fn parse_obj<R: BufRead>(reader: R) -> io::Result<(Obj, Vec<String>)> {
+    let mut attributes = ObjAttributes {
+        positions: Vec::new(),
+        colors: Vec::new(),
+        texcoords: Vec::new(),
+        normals: Vec::new()
+    };
+
+    let mut libraries = Vec::new();
+    let mut meshes: Vec<ObjMesh> = Vec::new();
+
+    let mut name = String::new();
+    let mut material: Option<String> = None;
+    let mut current: Option<MeshBuilder> = None;
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = try!(line);
+        let mut words = line.split_whitespace();
+
+        let keyword = match words.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue
+        };
+
+        let args: Vec<&str> = words.collect();
+
+        match keyword {
+            "v" => {
+                let values = try!(parse_floats(number, &args));
+
+                if values.len() < 3 {
+                    return Err(parse_error(number, "vertex position needs 3 coordinates"));
+                }
+
+                attributes.positions.push([values[0], values[1], values[2]]);
+
+                // Vertex colors extension, after the coordinates
+                if values.len() >= 6 {
+                    attributes.colors.push([values[3], values[4], values[5]]);
+                }
+                else {
+                    attributes.colors.push([1.0, 1.0, 1.0]);
+                }
+            },
+            "vt" => {
+                let values = try!(parse_floats(number, &args));
+
+                if values.is_empty() {
+                    return Err(parse_error(number, "texture coordinates need at least 1 value"));
+                }
+
+                // OBJ images start at the bottom, textures at the top
+                attributes.texcoords.push([values[0], 1.0 - values.get(1).cloned().unwrap_or(0.0)]);
+            },
+            "vn" => {
+                let values = try!(parse_floats(number, &args));
+
+                if values.len() < 3 {
+                    return Err(parse_error(number, "normal needs 3 coordinates"));
+                }
+
+                attributes.normals.push([values[0], values[1], values[2]]);
+            },
+            "f" => {
+                let mut face = Vec::with_capacity(args.len());
+
+                for arg in args.iter() {
+                    face.push(try!(parse_face_vertex(number, arg, &attributes)));
+                }
+
+                if face.len() < 3 {
+                    return Err(parse_error(number, "face needs at least 3 vertices"));
+                }
+
+                let builder = current.get_or_insert_with(|| MeshBuilder::new(&name, material.as_ref()));
+                add_face(builder, &face, &attributes);
+            },
+            "o" | "g" | "usemtl" => {
+                let value = args.join(" ");
+
+                if keyword == "usemtl" {
+                    if material.as_ref() == Some(&value) {
+                        continue;
+                    }
+
+                    material = Some(value);
+                }
+                else {
+                    if name == value {
+                        continue;
+                    }
+
+                    name = value;
+                }
+
+                if let Some(builder) = current.take() {
+                    meshes.push(builder.finish());
+                }
+            },
+            "mtllib" => libraries.extend(args.iter().map(|library| library.to_string())),
+
+            // Other statements, such as smoothing groups, are ignored
+            _ => ()
+        }
+    }
+
+    if let Some(builder) = current.take() {
+        meshes.push(builder.finish());
+    }
+
+    // Parts of an object sharing the same name and material are merged
+    let mut merged: Vec<ObjMesh> = Vec::with_capacity(meshes.len());
+
+    for mesh in meshes {
+        let same = merged.iter().position(|other| other.name == mesh.name && other.material == mesh.material);
+
+        match same {
+            Some(i) => {
+                let other = &mut merged[i];
+                let offset = other.vertices.len() as u32;
+
+                other.indices.extend(mesh.indices.iter().map(|index| index + offset));
+                other.vertices.extend(mesh.vertices);
+            },
+            None => merged.push(mesh)
+        }
+    }
+
+    let obj = Obj {
+        meshes: merged,
+        materials: Vec::new()
+    };
+
+    Ok((obj, libraries))
+}
+
+// Parse a vertex of a face: v, v/vt, v//vn or v/vt/vn. Indices start at
+// 1, negative ones are relative to the last attributes defined so far
+fn parse_face_vertex(number: usize, arg: &str, attributes: &ObjAttributes) -> io::Result<FaceVertex> {
+    let mut parts = arg.split('/');
+
+    let mut index = |count: usize, required: bool| -> io::Result<i64> {
+        let part = parts.next().unwrap_or("");
+
+        if part.is_empty() {
+            return if required { Err(parse_error(number, "face vertex needs a position")) } else { Ok(-1) };
+        }
+
+        let value = try!(part.parse::<i64>().map_err(|_| parse_error(number, "invalid face index")));
+        let resolved = if value < 0 { count as i64 + value } else { value - 1 };
+
+        if resolved < 0 || resolved >= count as i64 {
+            return Err(parse_error(number, "face index out of range"));
+        }
+
+        Ok(resolved)
+    };
+
+    let position = try!(index(attributes.positions.len(), true));
+    let texcoords = try!(index(attributes.texcoords.len(), false));
+    let normal = try!(index(attributes.normals.len(), false));
+
+    Ok((position, texcoords, normal))
+}
+
+// Triangulate a face and add it to a mesh, reusing the
+// vertices already used by other faces of the mesh
+fn add_face(builder: &mut MeshBuilder, face: &[FaceVertex], attributes: &ObjAttributes) {
+    let positions: Vec<[f32; 3]> = face.iter().map(|v| attributes.positions[v.0 as usize]).collect();
+    let normal = polygon_normal(&positions);
+
+    let indices: Vec<u32> = face.iter().map(|&v| {
+        if let Some(&index) = builder.indices.get(&v) {
+            return index;
+        }
+
+        let (p, c) = (attributes.positions[v.0 as usize], attributes.colors[v.0 as usize]);
+        let mut vertex = Vertex::new(p[0], p[1], p[2]).color(c[0], c[1], c[2], 1.0);
+
+        if v.1 >= 0 {
+            let t = attributes.texcoords[v.1 as usize];
+            vertex = vertex.texcoords(t[0], t[1]);
+        }
+
+        let index = builder.mesh.vertices.len() as u32;
+
+        if v.2 >= 0 {
+            let n = attributes.normals[v.2 as usize];
+            vertex = vertex.normal(n[0], n[1], n[2]);
+        }
+        else {
+            builder.missing_normals.push(index);
+        }
+
+        builder.mesh.vertices.push(vertex);
+        builder.indices.insert(v, index);
+
+        index
+    }).collect();
+
+    // Accumulate the normal of the face, weighted by its area,
+    // into the vertices whose normal is computed
+    for (v, &index) in face.iter().zip(indices.iter()) {
+        if v.2 < 0 {
+            let vertex_normal = &mut builder.mesh.vertices[index as usize].normal;
+
+            for c in 0..3 {
+                vertex_normal[c] += normal[c];
+            }
+        }
+    }
+
+    for triangle in triangulate(&positions, normal) {
+        builder.mesh.indices.extend(triangle.iter().map(|&i| indices[i]));
+    }
+}
+
+// Normal of a polygon, whose length is twice its area (Newell's method)
+fn polygon_normal(positions: &[[f32; 3]]) -> [f32; 3] {
+    let mut normal = [0.0; 3];
+
+    for (i, a) in positions.iter().enumerate() {
+        let b = positions[(i + 1) % positions.len()];
+
+        normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+        normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+        normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+    }
+
+    normal
+}
+
+// Split a polygon into triangles by clipping its ears, so that concave
+// polygons are supported. Degenerate polygons fall back to a fan
+fn triangulate(positions: &[[f32; 3]], normal: [f32; 3]) -> Vec<[usize; 3]> {
+    let count = positions.len();
+
+    if count == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Project the polygon onto the plane of its dominant axis
+    let axis = (0..3).max_by(|&a, &b| normal[a].abs().partial_cmp(&normal[b].abs()).unwrap_or(Ordering::Equal)).unwrap();
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let sign = if normal[axis] < 0.0 { -1.0 } else { 1.0 };
+
+    let points: Vec<(f32, f32)> = positions.iter().map(|p| (p[u], p[v])).collect();
+    let cross = |a: usize, b: usize, c: usize| {
+        let (pa, pb, pc) = (points[a], points[b], points[c]);
+        ((pb.0 - pa.0) * (pc.1 - pa.1) - (pb.1 - pa.1) * (pc.0 - pa.0)) * sign
+    };
+
+    let mut remaining: Vec<usize> = (0..count).collect();
+    let mut triangles = Vec::with_capacity(count - 2);
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+
+            // The corner must be convex, and contain no other vertex
+            cross(a, b, c) > 0.0 && remaining.iter().all(|&p| {
+                p == a || p == b || p == c || cross(a, b, p) < 0.0 || cross(b, c, p) < 0.0 || cross(c, a, p) < 0.0
+            })
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]]);
+                remaining.remove(i);
+            },
+            None => {
+                // Degenerate or self-intersecting polygon
+                for i in 1..n - 1 {
+                    triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+                }
+
+                return triangles;
+            }
+        }
+    }
+
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+// Parse all the arguments of a statement as numbers
+fn parse_floats(number: usize, args: &[&str]) -> io::Result<Vec<f32>> {
+    args.iter().map(|arg| parse_float(number, Some(arg))).collect()
+}
+
+fn parse_float(number: usize, arg: Option<&&str>) -> io::Result<f32> {
+    // Infinite or NaN values would only break the meshes built from them
+    match arg.and_then(|arg| arg.parse::<f32>().ok()) {
+        Some(value) if value.is_finite() => Ok(value),
+        _ => Err(parse_error(number, "invalid number"))
+    }
+}
+
+// Parse a color, which may be given as a single value for gray
+fn parse_color(number: usize, args: &[&str]) -> io::Result<[f32; 3]> {
+    let values = try!(parse_floats(number, args));
+
+    match values.len() {
+        1 => Ok([values[0]; 3]),
+        3 => Ok([values[0], values[1], values[2]]),
+        _ => Err(parse_error(number, "color needs 1 or 3 values"))
+    }
+}
+
+// Parse a texture map statement, whose options come before the path
+fn parse_map(number: usize, args: &[&str]) -> io::Result<PathBuf> {
+    match args.last() {
+        Some(path) => Ok(PathBuf::from(path.replace('\\', "/"))),
+        None => Err(parse_error(number, "texture map needs a path"))
+    }
+}
+
+// Error for malformed files, with the line where it occurred
+fn parse_error(number: usize, msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number + 1, msg))
+}
@@ -0,0 +1,561 @@
+use std::io;
+use std::cell::RefCell;
+use std::time::Instant;
+
+use gl;
+use gl::types::*;
+
+use super::{Error, Result};
+use shaders::Program;
+use uniforms::Uniform;
+use mesh::{self, Texture, TextureFormat};
+use image::Image;
+use resources::{self, ContextId, Resource};
+use target::{RenderTarget, DepthBuffer};
+
+// Texture units used by the effects
+const SOURCE_UNIT: u32 = 0;
+const SCENE_UNIT: u32 = 1;
+const EXTRA_UNIT: u32 = 2;
+
+// Number of horizontal and vertical blur passes of the bloom
+const BLOOM_BLUR_PASSES: usize = 4;
+
+/// Operators mapping high dynamic range colors to displayable ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapping {
+    /// `c / (c + 1)`, preserving the hue of very bright colors
+    Reinhard,
+
+    /// Approximation of the ACES filmic curve, with more contrast
+    Aces
+}
+
+/// Represents a fullscreen fragment shader effect, applied to the
+/// result of the previous effect of a `PostProcess` chain
+///
+/// The source of an effect is a fragment shader, without any
+/// `#version` directive. The following interface is declared
+/// before it:
+///
+/// ```glsl
+/// uniform sampler2D source; // Result of the previous effect
+/// uniform sampler2D scene;  // Scene rendered before any effect
+/// uniform vec2 resolution;  // Size of the output, in pixels
+/// uniform float time;       // Time since the creation of the chain
+///
+/// in vec2 frag_texcoords;
+/// out vec4 out_color;
+/// ```
+pub struct Effect {
+    program: Program,
+
+    // 3D texture of the color grading
+    lut: Option<GLuint>,
+
+    // Extra passes of the bloom
+    bloom: Option<Bloom>,
+
+    owner: ContextId
+}
+
+// Programs and intermediate targets of the bloom, which
+// are half the size of the output
+struct Bloom {
+    bright: Program,
+    blur: Program,
+    targets: RefCell<Vec<RenderTarget>>
+}
+
+impl Effect {
+    /// Create an effect from the source of a fragment shader
+    /// written against the interface of effects
+    pub fn new(source: &str) -> Result<Effect> {
+        Ok(Effect {
+            program: try!(effect_program(source)),
+            lut: None,
+            bloom: None,
+            owner: resources::current_context()
+        })
+    }
+
+    /// Map high dynamic range colors to the displayable range,
+    /// after multiplying them by the exposure
+    pub fn tone_mapping(operator: ToneMapping, exposure: f32) -> Result<Effect> {
+        let effect = try!(Effect::new(include_str!("glsl/post/tone_mapping.frag")));
+
+        try!(effect.set_tone_mapping(operator));
+        try!(effect.set_exposure(exposure));
+        try!(effect.set_gamma(1.0));
+
+        Ok(effect)
+    }
+
+    /// Apply a gamma correction, usually 2.2 to convert
+    /// linear colors for display
+    pub fn gamma(gamma: f32) -> Result<Effect> {
+        let effect = try!(Effect::new(include_str!("glsl/post/gamma.frag")));
+        try!(effect.set_uniform("gamma", &gamma));
+
+        Ok(effect)
+    }
+
+    /// Darken the corners of the image. The radius is the distance
+    /// from the center where the darkening starts, relative to the
+    /// height of the image
+    pub fn vignette(strength: f32, radius: f32) -> Result<Effect> {
+        let effect = try!(Effect::new(include_str!("glsl/post/vignette.frag")));
+
+        try!(effect.set_uniform("strength", &strength));
+        try!(effect.set_uniform("radius", &radius));
+
+        Ok(effect)
+    }
+
+    /// Smooth the aliased edges of the image (FXAA). It should
+    /// be applied to colors that are ready for display
+    pub fn fxaa() -> Result<Effect> {
+        Effect::new(include_str!("glsl/post/fxaa.frag"))
+    }
+
+    /// Make the parts of the image brighter than the threshold glow,
+    /// by adding a blurred copy of them, multiplied by the strength
+    pub fn bloom(threshold: f32, strength: f32) -> Result<Effect> {
+        let mut effect = try!(Effect::new(include_str!("glsl/post/bloom.frag")));
+
+        let bright = try!(effect_program(include_str!("glsl/post/bloom_bright.frag")));
+        let blur = try!(effect_program(include_str!("glsl/post/bloom_blur.frag")));
+
+        try!(set_program_uniform(&bright, "threshold", &threshold));
+        try!(effect.set_uniform("bloom", &(EXTRA_UNIT as i32)));
+        try!(effect.set_uniform("strength", &strength));
+
+        effect.bloom = Some(Bloom {
+            bright: bright,
+            blur: blur,
+            targets: RefCell::new(Vec::new())
+        });
+
+        Ok(effect)
+    }
+
+    /// Remap colors using a 3D lookup table. The table is an image
+    /// made of `size` squares of `size` by `size` pixels, laid out
+    /// horizontally, with red increasing to the right in each square,
+    /// green increasing downwards, and blue increasing from one square
+    /// to the next. The intensity blends between the original and
+    /// the remapped colors
+    pub fn color_grading(lut: &Image, intensity: f32) -> Result<Effect> {
+        let size = lut.height;
+
+        if size == 0 || lut.width != size * size {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid color grading table of {}x{} pixels", lut.width, lut.height)
+            )));
+        }
+
+        let mut effect = try!(Effect::new(include_str!("glsl/post/color_grading.frag")));
+
+        try!(effect.set_uniform("lut", &(EXTRA_UNIT as i32)));
+        try!(effect.set_uniform("lut_size", &(size as f32)));
+        try!(effect.set_uniform("intensity", &intensity));
+
+        // Reorder the squares of the image into the slices of a 3D texture
+        let mut data = Vec::with_capacity((size * size * size * 4) as usize);
+
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    data.extend_from_slice(&lut.pixel(b * size + r, g));
+                }
+            }
+        }
+
+        unsafe {
+            let mut id: GLuint = 0;
+
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_3D, id);
+
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as GLint);
+
+            gl::TexImage3D(
+                gl::TEXTURE_3D,
+                0,
+                gl::RGBA8 as GLint,
+                size as GLsizei,
+                size as GLsizei,
+                size as GLsizei,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                data.as_ptr() as *const _
+            );
+
+            gl::BindTexture(gl::TEXTURE_3D, 0);
+
+            effect.lut = Some(id);
+        }
+
+        Ok(effect)
+    }
+
+    /// Set a uniform of the effect, such as one of its parameters
+    pub fn set_uniform<T: Uniform + ?Sized>(&self, name: &str, value: &T) -> Result<()> {
+        set_program_uniform(&self.program, name, value)
+    }
+
+    /// Change the exposure of a tone mapping effect, for instance
+    /// to adapt it to the average brightness of the scene
+    pub fn set_exposure(&self, exposure: f32) -> Result<()> {
+        self.set_uniform("exposure", &exposure)
+    }
+
+    /// Change the gamma correction applied by a tone mapping effect
+    /// after mapping the colors, such as 2.2 for the linear colors of
+    /// physically based materials. It is 1 by default
+    pub fn set_gamma(&self, gamma: f32) -> Result<()> {
+        self.set_uniform("gamma", &gamma)
+    }
+
+    /// Change the operator of a tone mapping effect
+    pub fn set_tone_mapping(&self, operator: ToneMapping) -> Result<()> {
+        let curve = match operator {
+            ToneMapping::Reinhard => 0,
+            ToneMapping::Aces => 1
+        };
+
+        self.set_uniform("curve", &curve)
+    }
+
+    // Apply the effect to the source texture, rendering
+    // into the currently bound framebuffer
+    fn apply(&self, chain: &PostProcess, source: &Texture) {
+        if let Some(ref bloom) = self.bloom {
+            bloom.render(chain, source);
+        }
+
+        if let Some(lut) = self.lut {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + EXTRA_UNIT);
+                gl::BindTexture(gl::TEXTURE_3D, lut);
+                gl::ActiveTexture(gl::TEXTURE0);
+            }
+        }
+
+        chain.draw(&self.program, source, chain.width, chain.height);
+    }
+}
+
+impl Drop for Effect {
+    fn drop(&mut self) {
+        if let Some(id) = self.lut {
+            resources::release(self.owner, Resource::Texture(id));
+        }
+    }
+}
+
+impl Bloom {
+    // Extract and blur the bright parts of the source, and
+    // bind the result for the combining pass
+    fn render(&self, chain: &PostProcess, source: &Texture) {
+        let (width, height) = ((chain.width / 2).max(1), (chain.height / 2).max(1));
+        let mut targets = self.targets.borrow_mut();
+
+        if targets.len() != 2 || targets[0].width != width || targets[0].height != height || targets[0].format() != chain.format {
+            targets.clear();
+
+            for _ in 0..2 {
+                match RenderTarget::with_format(width, height, 1, chain.format, DepthBuffer::None) {
+                    Ok(target) => targets.push(target),
+                    Err(_) => return
+                }
+            }
+        }
+
+        targets[0].render(|| chain.draw(&self.bright, source, width, height));
+
+        // Alternate horizontal and vertical blurs between the two targets
+        for _ in 0..BLOOM_BLUR_PASSES {
+            let _ = set_program_uniform(&self.blur, "direction", &[1.0f32, 0.0]);
+            targets[1].render(|| chain.draw(&self.blur, targets[0].color(0), width, height));
+
+            let _ = set_program_uniform(&self.blur, "direction", &[0.0f32, 1.0]);
+            targets[0].render(|| chain.draw(&self.blur, targets[1].color(0), width, height));
+        }
+
+        targets[0].color(0).bind_to(EXTRA_UNIT);
+    }
+}
+
+/// Represents a chain of effects applied to a rendered scene. The
+/// scene is rendered into an offscreen target, then each effect is
+/// applied in turn, alternating between two targets. The last one
+/// renders into the framebuffer that was bound before
+///
+/// With a floating point format, the scene and the intermediate
+/// results keep colors brighter than white. They are clamped when
+/// resolved to the screen, unless a tone mapping effect is used
+pub struct PostProcess {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+
+    effects: Vec<Effect>,
+
+    // Tone mapping of high dynamic range chains,
+    // applied when resolving to the screen
+    tone_mapping: Option<Effect>,
+
+    scene: RenderTarget,
+    ping_pong: Vec<RenderTarget>,
+
+    copy: Program,
+    vao: GLuint,
+    start: Instant,
+    owner: ContextId
+}
+
+impl PostProcess {
+    /// Create an empty chain, whose output has the specified size
+    pub fn new(width: u32, height: u32) -> Result<PostProcess> {
+        PostProcess::with_format(width, height, TextureFormat::Rgba8)
+    }
+
+    /// Create an empty chain for high dynamic range rendering, using
+    /// 16 bit floating point targets. The result of the effects is tone
+    /// mapped when it is resolved to the current framebuffer. The
+    /// exposure and the operator can be changed through `tone_mapping`
+    pub fn hdr(width: u32, height: u32, operator: ToneMapping, exposure: f32) -> Result<PostProcess> {
+        let mut chain = try!(PostProcess::with_format(width, height, TextureFormat::Rgba16F));
+        chain.tone_mapping = Some(try!(Effect::tone_mapping(operator, exposure)));
+
+        Ok(chain)
+    }
+
+    /// Create an empty chain whose intermediate
+    /// targets use the specified format
+    pub fn with_format(width: u32, height: u32, format: TextureFormat) -> Result<PostProcess> {
+        let copy = try!(effect_program(include_str!("glsl/post/copy.frag")));
+
+        // The fullscreen triangle has no attributes, but
+        // a vertex array must still be bound to draw it
+        let mut vao: GLuint = 0;
+
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+
+        let mut chain = PostProcess {
+            width: 0,
+            height: 0,
+            format: format,
+
+            effects: Vec::new(),
+            tone_mapping: None,
+
+            scene: try!(RenderTarget::with_format(width, height, 1, format, DepthBuffer::DepthStencil)),
+            ping_pong: Vec::new(),
+
+            copy: copy,
+            vao: vao,
+            start: Instant::now(),
+            owner: resources::current_context()
+        };
+
+        try!(chain.resize(width, height));
+        Ok(chain)
+    }
+
+    /// Add an effect at the end of the chain
+    pub fn push(&mut self, effect: Effect) {
+        self.effects.push(effect);
+    }
+
+    /// Get the effects of the chain, to change their settings
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    /// Get the tone mapping applied after the effects, if any
+    pub fn tone_mapping(&self) -> Option<&Effect> {
+        self.tone_mapping.as_ref()
+    }
+
+    /// Remove all the effects of the chain
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    /// Change the size of the output, for instance
+    /// when the window is resized
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        if width == self.width && height == self.height {
+            return Ok(());
+        }
+
+        try!(self.scene.resize(width, height));
+
+        self.ping_pong = vec![
+            try!(RenderTarget::with_format(width, height, 1, self.format, DepthBuffer::None)),
+            try!(RenderTarget::with_format(width, height, 1, self.format, DepthBuffer::None))
+        ];
+
+        self.width = width;
+        self.height = height;
+
+        Ok(())
+    }
+
+    /// Get the target the scene is rendered into, for
+    /// instance to render it without `render`
+    pub fn scene(&self) -> &RenderTarget {
+        &self.scene
+    }
+
+    /// Render the scene with the specified closure, then apply the
+    /// effects and render the result into the current framebuffer
+    pub fn render<F: FnOnce()>(&self, render: F) {
+        self.scene.render(render);
+        self.apply();
+    }
+
+    /// Apply the effects to the content of the scene target, and
+    /// render the result into the current framebuffer
+    pub fn apply(&self) {
+        let mut program: GLint = 0;
+        let mut textures: [GLint; 2] = [0; 2];
+
+        // Remember the state shared with the main rendering
+        unsafe {
+            gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut program);
+
+            for (i, texture) in textures.iter_mut().enumerate() {
+                gl::ActiveTexture(gl::TEXTURE0 + SCENE_UNIT + i as u32);
+                gl::GetIntegerv(gl::TEXTURE_BINDING_2D, texture);
+            }
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+
+        self.scene.color(0).bind_to(SCENE_UNIT);
+
+        let effects: Vec<&Effect> = self.effects.iter().chain(self.tone_mapping.iter()).collect();
+
+        if effects.is_empty() {
+            self.draw(&self.copy, self.scene.color(0), self.width, self.height);
+        }
+
+        let mut source = self.scene.color(0);
+
+        for (i, effect) in effects.iter().enumerate() {
+            if i + 1 == effects.len() {
+                effect.apply(self, source);
+            }
+            else {
+                let target = &self.ping_pong[i % 2];
+
+                target.render(|| effect.apply(self, source));
+                source = target.color(0);
+            }
+        }
+
+        // Restore the state
+        unsafe {
+            for (i, texture) in textures.iter().enumerate() {
+                gl::ActiveTexture(gl::TEXTURE0 + SCENE_UNIT + i as u32);
+                gl::BindTexture(gl::TEXTURE_2D, *texture as GLuint);
+            }
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::UseProgram(program as GLuint);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        mesh::BLANK_TEXTURE.bind();
+    }
+
+    // Run a fullscreen pass of a program, sampling the source texture
+    fn draw(&self, program: &Program, source: &Texture, width: u32, height: u32) {
+        program.bind();
+
+        let _ = program.set_uniform("source", &(SOURCE_UNIT as i32));
+        let _ = program.set_uniform("scene", &(SCENE_UNIT as i32));
+        let _ = program.set_uniform("resolution", &[width as f32, height as f32]);
+        let _ = program.set_uniform("time", &duration_seconds(self.start));
+
+        source.bind_to(SOURCE_UNIT);
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+impl Drop for PostProcess {
+    fn drop(&mut self) {
+        resources::release(self.owner, Resource::VertexArray(self.vao));
+    }
+}
+
+/// Create a color grading table that leaves colors unchanged, in the
+/// layout expected by `Effect::color_grading`. It can be saved, edited
+/// in an image editor along with a screenshot, and loaded back
+pub fn neutral_lut(size: u32) -> Image {
+    let max = (size.max(2) - 1) as f32;
+    let mut data = Vec::with_capacity((size * size * size * 4) as usize);
+
+    for g in 0..size {
+        for b in 0..size {
+            for r in 0..size {
+                data.push((r as f32 / max * 255.0).round() as u8);
+                data.push((g as f32 / max * 255.0).round() as u8);
+                data.push((b as f32 / max * 255.0).round() as u8);
+                data.push(255);
+            }
+        }
+    }
+
+    Image::new(size * size, size, data)
+}
+
+// Build the program of an effect, declaring the interface of effects
+fn effect_program(source: &str) -> Result<Program> {
+    let vert = format!("#version 140\n{}", include_str!("glsl/post/fullscreen.vert"));
+    let frag = format!("#version 140\n{}{}", include_str!("glsl/post/interface.glsl"), source);
+
+    Program::new(&vert, &frag)
+}
+
+// Set a uniform of a program that may not be bound,
+// keeping the current program bound
+fn set_program_uniform<T: Uniform + ?Sized>(program: &Program, name: &str, value: &T) -> Result<()> {
+    let mut current: GLint = 0;
+
+    unsafe {
+        gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut current);
+    }
+
+    program.bind();
+    let result = program.set_uniform(name, value);
+
+    unsafe {
+        gl::UseProgram(current as GLuint);
+    }
+
+    result
+}
+
+// Seconds elapsed since an instant
+fn duration_seconds(start: Instant) -> f32 {
+    let elapsed = start.elapsed();
+    elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 / 1_000_000_000.0
+}
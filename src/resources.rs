@@ -0,0 +1,120 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use gl;
+use gl::types::*;
+
+/// Identifies an OpenGL context, which owns the objects
+/// created while it is current
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContextId(usize);
+
+// Objects that have been released but not deleted yet,
+// for each context currently alive
+lazy_static! {
+    static ref PENDING: Mutex<HashMap<ContextId, Vec<Resource>>> = Mutex::new(HashMap::new());
+}
+
+// Identifier of the next context, 0 standing for no context
+static NEXT_CONTEXT: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+    // Context current on this thread
+    static CURRENT: Cell<ContextId> = Cell::new(ContextId(0));
+}
+
+/// Represents an OpenGL object owned by the library
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resource {
+    Buffer(GLuint),
+    VertexArray(GLuint),
+    Texture(GLuint),
+    Framebuffer(GLuint),
+    Renderbuffer(GLuint),
+    Shader(GLuint),
+    Program(GLuint)
+}
+
+impl Resource {
+    // Delete the object, the context owning it must be current
+    unsafe fn delete(&self) {
+        match *self {
+            Resource::Buffer(id) => gl::DeleteBuffers(1, &id),
+            Resource::VertexArray(id) => gl::DeleteVertexArrays(1, &id),
+            Resource::Texture(id) => gl::DeleteTextures(1, &id),
+            Resource::Framebuffer(id) => gl::DeleteFramebuffers(1, &id),
+            Resource::Renderbuffer(id) => gl::DeleteRenderbuffers(1, &id),
+            Resource::Shader(id) => gl::DeleteShader(id),
+            Resource::Program(id) => gl::DeleteProgram(id)
+        }
+    }
+}
+
+/// Get the context current on the calling thread, which
+/// owns the objects being created
+pub fn current_context() -> ContextId {
+    CURRENT.with(|current| current.get())
+}
+
+/// Schedule the deletion of an OpenGL object owned by the specified
+/// context. This can be called from any thread, the object is actually
+/// deleted the next time the queue of its context is collected. If its
+/// context is not alive anymore, the object has already been destroyed
+/// with it and nothing is done
+pub fn release(owner: ContextId, res: Resource) {
+    if let Ok(mut pending) = PENDING.lock() {
+        if let Some(queue) = pending.get_mut(&owner) {
+            queue.push(res);
+        }
+    }
+}
+
+/// Delete the objects that have been released by the context current
+/// on the calling thread. The objects of other contexts wait until
+/// they are current. This is done automatically by `rendust::clear`
+/// at the start of every frame
+pub fn collect() {
+    let pending = match PENDING.lock() {
+        Ok(mut pending) => match pending.get_mut(&current_context()) {
+            Some(queue) => queue.split_off(0),
+            None => return
+        },
+        Err(_) => return
+    };
+
+    for res in pending {
+        unsafe {
+            res.delete();
+        }
+    }
+}
+
+// Register the creation of an OpenGL context, and get its identifier
+pub(crate) fn context_created() -> ContextId {
+    let id = ContextId(NEXT_CONTEXT.fetch_add(1, Ordering::SeqCst));
+
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.insert(id, Vec::new());
+    }
+
+    id
+}
+
+// Register that a context has been made current on the calling thread
+pub(crate) fn context_current(id: ContextId) {
+    CURRENT.with(|current| current.set(id));
+}
+
+// Register the destruction of an OpenGL context. Its
+// pending objects do not exist anymore
+pub(crate) fn context_destroyed(id: ContextId) {
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.remove(&id);
+    }
+
+    CURRENT.with(|current| if current.get() == id {
+        current.set(ContextId(0));
+    });
+}
@@ -1,138 +1,496 @@
-use std::ffi::{CStr, CString};
+use std::ffi::CString;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::error;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use gl;
 use gl::types::*;
 
 use super::{Result, Error};
+use resources::{self, ContextId, Resource};
+use mesh::{Vertex, VertexLayout, Attribute, AttributeType};
+use uniforms::{self, Uniform};
+
+/// Represents a stage of the shader pipeline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Vertex,
+    Fragment
+}
+
+impl Stage {
+    // Get the corresponding GL constant
+    fn to_gl_const(&self) -> GLenum {
+        match *self {
+            Stage::Vertex => gl::VERTEX_SHADER,
+            Stage::Fragment => gl::FRAGMENT_SHADER
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Stage::Vertex => write!(f, "vertex"),
+            Stage::Fragment => write!(f, "fragment")
+        }
+    }
+}
+
+/// Describes why a shader program could not be built
+#[derive(Debug, Clone)]
+pub struct ShaderError {
+    /// Short description of the failure
+    pub message: String,
+
+    /// Stage of the shader that failed to compile,
+    /// none if the program failed to link
+    pub stage: Option<Stage>,
+
+    /// Info log reported by the driver
+    pub log: String,
+
+    /// Source code of the shader that failed to compile
+    pub source: Option<String>
+}
+
+impl ShaderError {
+    /// Create a new error that is not related
+    /// to any shader stage
+    pub fn new<S: Into<String>>(message: S) -> ShaderError {
+        ShaderError {
+            message: message.into(),
+            stage: None,
+            log: String::new(),
+            source: None
+        }
+    }
+
+    /// Get the source line numbers (starting at 1) mentioned
+    /// in the info log. Drivers use various formats, such as `0:12(3)`
+    /// (Mesa), `0(12)` (Nvidia) or `ERROR: 0:12:` (AMD, Intel)
+    pub fn error_lines(&self) -> Vec<usize> {
+        let mut lines = Vec::new();
+
+        for entry in self.log.lines() {
+            if let Some(line) = parse_log_line(entry) {
+                if !lines.contains(&line) {
+                    lines.push(line);
+                }
+            }
+        }
+
+        lines
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}", self.message));
+
+        if !self.log.trim().is_empty() {
+            try!(write!(f, "\n{}", self.log.trim_end()));
+        }
+
+        // Print the source with line numbers, marking
+        // the lines mentioned in the log
+        if let Some(ref source) = self.source {
+            let errors = self.error_lines();
+            let width = source.lines().count().to_string().len();
+
+            try!(write!(f, "\n"));
+
+            for (i, line) in source.lines().enumerate() {
+                let marker = if errors.contains(&(i + 1)) { ">" } else { " " };
+                try!(write!(f, "\n{} {:>width$} | {}", marker, i + 1, line, width = width));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl error::Error for ShaderError {}
 
 /// Represents an OpenGL shader program
 pub struct Program {
-    id: GLuint
+    id: GLuint,
+    files: Option<ShaderFiles>,
+
+    // Active uniforms, blocks and attributes, queried after linking
+    reflection: Reflection,
+
+    // Uniforms that can be set, by name
+    uniforms: HashMap<String, UniformSlot>,
+
+    // Locations of uniform array elements, resolved on first use
+    locations: RefCell<HashMap<String, GLint>>,
+
+    owner: ContextId
+}
+
+// Location and type of an active uniform
+#[derive(Debug, Clone, Copy)]
+struct UniformSlot {
+    location: GLint,
+    kind: GLenum
+}
+
+/// Describes an active uniform of a program
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformInfo {
+    /// Name of the uniform. Arrays are named after their
+    /// first element, such as `lights[0]`
+    pub name: String,
+
+    /// GL type of the uniform, see `uniforms::type_name`
+    pub kind: GLenum,
+
+    /// Number of elements, 1 if the uniform is not an array
+    pub size: GLint,
+
+    /// Location of the uniform, -1 if it is part of a block
+    pub location: GLint,
+
+    /// Index of the block the uniform belongs to, if any
+    pub block: Option<GLuint>
+}
+
+/// Describes an active uniform block of a program
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformBlockInfo {
+    pub name: String,
+    pub index: GLuint,
+
+    /// Minimum size of the buffer backing the block, in bytes
+    pub data_size: GLint,
+
+    /// Names of the uniforms declared in the block
+    pub uniforms: Vec<String>
+}
+
+/// Describes an active vertex attribute of a program
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeInfo {
+    pub name: String,
+
+    /// GL type of the attribute, see `uniforms::type_name`
+    pub kind: GLenum,
+
+    /// Number of elements, 1 if the attribute is not an array
+    pub size: GLint,
+    pub location: GLint
+}
+
+// Everything that is queried from a program after linking
+#[derive(Debug, Clone, Default)]
+struct Reflection {
+    uniforms: Vec<UniformInfo>,
+    blocks: Vec<UniformBlockInfo>,
+    attributes: Vec<AttributeInfo>
+}
+
+impl Reflection {
+    // Index the uniforms that have a location by name
+    fn uniform_slots(&self) -> HashMap<String, UniformSlot> {
+        let mut slots = HashMap::new();
+
+        for info in self.uniforms.iter().filter(|info| info.location >= 0) {
+            let slot = UniformSlot { location: info.location, kind: info.kind };
+
+            // Arrays are reported with the name of their first element,
+            // make them reachable with the name of the array as well
+            if info.name.ends_with("[0]") {
+                slots.insert(info.name[..info.name.len() - 3].to_owned(), slot);
+            }
+
+            slots.insert(info.name.clone(), slot);
+        }
+
+        slots
+    }
+}
+
+// Source files of a program built from disk
+struct ShaderFiles {
+    vert: PathBuf,
+    frag: PathBuf,
+
+    // Modification times of the files when they were last read
+    modified: (Option<SystemTime>, Option<SystemTime>),
+
+    // Vertex layout the program is built for
+    attributes: Vec<Attribute>
+}
+
+impl ShaderFiles {
+    // Check whether any of the files has been modified since
+    // the last time it has been read
+    fn changed(&self) -> bool {
+        (modified_time(&self.vert), modified_time(&self.frag)) != self.modified
+    }
+
+    // Read the files and build a new program from them
+    fn build(&mut self) -> Result<GLuint> {
+        // Remember the files as they are now, so that a broken
+        // version is not rebuilt until it changes again
+        self.modified = (modified_time(&self.vert), modified_time(&self.frag));
+
+        let vert_src = try!(read_file(&self.vert));
+        let frag_src = try!(read_file(&self.frag));
+
+        link_program(&vert_src, &frag_src, &self.attributes).map_err(|err| match err {
+            // Tell which file the error comes from
+            Error::GlShader(mut err) => {
+                err.message = match err.stage {
+                    Some(Stage::Vertex) => format!("{}: {}", self.vert.display(), err.message),
+                    Some(Stage::Fragment) => format!("{}: {}", self.frag.display(), err.message),
+                    None => format!("{}, {}: {}", self.vert.display(), self.frag.display(), err.message)
+                };
+
+                Error::GlShader(err)
+            },
+            err => err
+        })
+    }
 }
 
 impl Program {
     /// Create a new shader program based on a vertex shader
-    /// and a fragment shader
+    /// and a fragment shader. The vertex shader inputs are bound
+    /// to the attributes of the default `mesh::Vertex` type
     pub fn new(vert_src: &str, frag_src: &str) -> Result<Program> {
+        Program::with_layout::<Vertex>(vert_src, frag_src)
+    }
+
+    /// Create a new shader program whose vertex shader inputs are
+    /// bound to the locations of the attributes of the specified vertex
+    /// type, matched by name
+    pub fn with_layout<V: VertexLayout>(vert_src: &str, frag_src: &str) -> Result<Program> {
+        let id = try!(link_program(vert_src, frag_src, &V::attributes()));
+        Ok(Program::from_id(id, None))
+    }
+
+    /// Create a new shader program from a vertex shader file and a
+    /// fragment shader file. The files can then be watched for
+    /// changes with `reload_if_changed`
+    pub fn from_files<P: AsRef<Path>>(vert_path: P, frag_path: P) -> Result<Program> {
+        Program::from_files_with_layout::<Vertex, P>(vert_path, frag_path)
+    }
+
+    /// Create a new shader program from files, binding the vertex
+    /// shader inputs to the attributes of the specified vertex type
+    pub fn from_files_with_layout<V: VertexLayout, P: AsRef<Path>>(vert_path: P, frag_path: P) -> Result<Program> {
+        let mut files = ShaderFiles {
+            vert: vert_path.as_ref().to_path_buf(),
+            frag: frag_path.as_ref().to_path_buf(),
+            modified: (None, None),
+            attributes: V::attributes()
+        };
+
+        let id = try!(files.build());
+        Ok(Program::from_id(id, Some(files)))
+    }
+
+    /// Check whether the source files of the program have been modified
+    /// since they were last loaded, and rebuild the program in place
+    /// if so. Returns whether the program has been replaced.
+    ///
+    /// If the new sources fail to build, the error is returned and the
+    /// previous program is kept in use until the files change again.
+    /// Programs not created from files are never reloaded
+    pub fn reload_if_changed(&mut self) -> Result<bool> {
+        let id = match self.files {
+            Some(ref mut files) if files.changed() => try!(files.build()),
+            _ => return Ok(false)
+        };
+
         unsafe {
-            // Create both shaders, return an error if it fails
-            let (vert, frag) = match (gl::CreateShader(gl::VERTEX_SHADER), gl::CreateShader(gl::FRAGMENT_SHADER)) {
-                (0, 0) | (0, _) | (_, 0) => return Err(Error::GlShader("Failed to create shader object".to_owned())),
-                (vert, frag) => (vert, frag)
-            };
-
-            // Bind the source code of each shader
-            gl::ShaderSource(
-                vert,
-                1 as GLsizei,
-                &CStr::from_ptr(vert_src.as_ptr() as *const i8).as_ptr(),
-                &(vert_src.len() as GLint)
-            );
-
-            gl::ShaderSource(
-                frag,
-                1 as GLsizei,
-                &CStr::from_ptr(frag_src.as_ptr() as *const i8).as_ptr(),
-                &(frag_src.len() as GLint)
-            );
-
-            // Compile both shaders
-            gl::CompileShader(vert);
-            gl::CompileShader(frag);
-
-            // Check for compilation errors in the vertex shader
-            let mut compiled: GLint = 42;
-            gl::GetShaderiv(vert, gl::COMPILE_STATUS, &mut compiled as *mut GLint);
-
-            if compiled <= 0 {
-                return Err(Error::GlShader("Failed to compile vertex shader".to_owned()));
+            // Keep using the program if it was bound
+            let mut current: GLint = 0;
+            gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut current);
+
+            if current as GLuint == self.id {
+                gl::UseProgram(id);
             }
+        }
 
-            // Check for compilation errors in the fragment shader
-            let mut compiled: GLint = 0;
-            gl::GetShaderiv(frag, gl::COMPILE_STATUS, &mut compiled as *mut GLint);
+        resources::release(self.owner, Resource::Program(self.id));
 
-            if compiled <= 0 {
-                return Err(Error::GlShader("Failed to compile fragment shader".to_owned()));
+        // Uniforms and attributes may have changed as well
+        self.id = id;
+        self.owner = resources::current_context();
+        self.reflection = unsafe { reflect(id) };
+        self.uniforms = self.reflection.uniform_slots();
+        self.locations.borrow_mut().clear();
+
+        Ok(true)
+    }
+
+    // Wrap a linked program object
+    fn from_id(id: GLuint, files: Option<ShaderFiles>) -> Program {
+        let reflection = unsafe { reflect(id) };
+
+        Program {
+            id: id,
+            files: files,
+            uniforms: reflection.uniform_slots(),
+            reflection: reflection,
+            locations: RefCell::new(HashMap::new()),
+            owner: resources::current_context()
+        }
+    }
+
+    /// List the active uniforms of the program, including
+    /// the ones declared in uniform blocks
+    pub fn uniforms(&self) -> &[UniformInfo] {
+        &self.reflection.uniforms
+    }
+
+    /// Find an active uniform by name
+    pub fn uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.reflection.uniforms.iter().find(|info| info.name == name || info.name == format!("{}[0]", name))
+    }
+
+    /// List the active uniform blocks of the program
+    pub fn uniform_blocks(&self) -> &[UniformBlockInfo] {
+        &self.reflection.blocks
+    }
+
+    /// List the active vertex attributes of the program
+    pub fn attributes(&self) -> &[AttributeInfo] {
+        &self.reflection.attributes
+    }
+
+    /// Check that the specified vertex type provides every attribute
+    /// read by the vertex shader, at the right location and with a
+    /// compatible type
+    pub fn check_layout<V: VertexLayout>(&self) -> Result<()> {
+        let layout = V::attributes();
+        let mut problems = Vec::new();
+
+        for info in &self.reflection.attributes {
+            // Built-in inputs such as gl_VertexID
+            if info.location < 0 {
+                continue;
             }
 
-            // Link the two shaders into a program
-            let program = match gl::CreateProgram() {
-                0 => return Err(Error::GlShader("Failed to create shader program".to_owned())),
-                p => p
-            };
+            // Matrices take one location per column
+            let (integer, components, columns) = attribute_shape(info.kind);
 
-            gl::AttachShader(program, vert);
-            gl::AttachShader(program, frag);
-            gl::LinkProgram(program);
+            for location in info.location..info.location + columns {
+                let attr = match layout.iter().find(|attr| attr.location as GLint == location) {
+                    Some(attr) => attr,
+                    None => {
+                        problems.push(format!("{} (location {}) is not provided", info.name, location));
+                        continue;
+                    }
+                };
 
-            // Check link success
-            let mut linked: GLint = 0;
-            gl::GetProgramiv(program, gl::LINK_STATUS, &mut linked as *mut GLint);
+                let provides_integer = attr.kind != AttributeType::Float && !attr.normalized;
 
-            if linked <= 0 {
-                return Err(Error::GlShader("Failed to link shader program".to_owned()));
+                if integer != provides_integer {
+                    problems.push(format!("{} is read as {} but {} provides {} values", info.name, uniforms::type_name(info.kind),
+                        attr.name, if provides_integer { "integer" } else { "floating point" }));
+                }
+                else if attr.components > components {
+                    problems.push(format!("{} is read as {} but {} has {} components", info.name, uniforms::type_name(info.kind),
+                        attr.name, attr.components));
+                }
             }
+        }
 
-            // Detach the shaders
-            gl::DetachShader(program, vert);
-            gl::DetachShader(program, frag);
+        if problems.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(Error::VertexLayout(problems.join(", ")))
+        }
+    }
+
+    /// Set the value of the uniform defined by the specified name. The
+    /// program must be bound. Elements of uniform arrays can be set
+    /// individually (`lights[2]`), or several at once by passing a slice.
+    ///
+    /// Setting a uniform that is not used by the program does nothing,
+    /// but an error is returned if the type of the value does not match
+    /// the type of the uniform
+    pub fn set_uniform<T: Uniform + ?Sized>(&self, name: &str, value: &T) -> Result<()> {
+        let slot = match self.uniform_slot(name) {
+            Some(slot) => slot,
+            None => return Ok(())
+        };
 
-            // Return
-            Ok(Program {
-                id: program
-            })
+        if !T::accepts(slot.kind) {
+            return Err(Error::UniformType(name.to_owned(), slot.kind));
+        }
+
+        value.set(slot.location);
+        Ok(())
+    }
+
+    // Find the location and type of a uniform
+    fn uniform_slot(&self, name: &str) -> Option<UniformSlot> {
+        if let Some(slot) = self.uniforms.get(name) {
+            return Some(*slot);
+        }
+
+        // Elements of arrays other than the first one are not listed,
+        // but they have the type of the array
+        let base = match name.rfind('[') {
+            Some(i) if name.ends_with(']') => &name[..i],
+            _ => return None
+        };
+
+        let kind = match self.uniforms.get(base) {
+            Some(slot) => slot.kind,
+            None => return None
+        };
+
+        if let Some(&location) = self.locations.borrow().get(name) {
+            return Some(UniformSlot { location: location, kind: kind });
+        }
+
+        let location = unsafe {
+            gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr())
+        };
+
+        self.locations.borrow_mut().insert(name.to_owned(), location);
+
+        if location >= 0 {
+            Some(UniformSlot { location: location, kind: kind })
+        }
+        else {
+            None
         }
     }
 
     /// Set the value of the uniform matrix defined by the specified
     /// name.
     pub fn set_uniform_matrix(&self, name: &str, matrix: &[f32; 16]) {
-        unsafe {
-            // Get the location of the uniform
-            let loc = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
-
-            // If the uniform exists
-            if loc >= 0 {
-                // Set the uniform's value
-                gl::UniformMatrix4fv(loc, 1, gl::FALSE, matrix.as_ptr() as *const GLfloat);
-            }
-        }
+        let _ = self.set_uniform(name, matrix);
     }
 
     /// Set the value of the uniform float defined by the specified
     /// name
     pub fn set_uniform_float(&self, name: &str, float: f32) {
-        unsafe {
-            // Get the location of the uniform
-            let loc = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
-
-            // If the uniform exists
-            if loc >= 0 {
-                // Set the uniform's value
-                gl::Uniform1f(loc, float);
-            }
-        }
+        let _ = self.set_uniform(name, &float);
     }
 
     /// Set the value of the uniform vector defined by the specified
     /// name.
     pub fn set_uniform_vector(&self, name: &str, num: u32, vals: &[f32]) {
-        unsafe {
-            // Get the location of the uniform
-            let loc = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
-
-            // If the uniform exists
-            if loc >= 0 {
-                // Set the uniform's value
-                match num {
-                    2 => gl::Uniform2f(loc, vals[0], vals[1]),
-                    3 => gl::Uniform3f(loc, vals[0], vals[1], vals[2]),
-                    4 => gl::Uniform4f(loc, vals[0], vals[1], vals[2], vals[3]),
-                    _ => ()
-                }
-            }
-        }
+        let _ = match num {
+            2 => self.set_uniform(name, &[vals[0], vals[1]]),
+            3 => self.set_uniform(name, &[vals[0], vals[1], vals[2]]),
+            4 => self.set_uniform(name, &[vals[0], vals[1], vals[2], vals[3]]),
+            _ => Ok(())
+        };
     }
 
     /// Bind the shader program in order to use it
@@ -142,3 +500,305 @@ impl Program {
         }
     }
 }
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        resources::release(self.owner, Resource::Program(self.id));
+    }
+}
+
+// Compile and link a program from the source of its shaders, binding
+// the vertex attributes to their locations
+fn link_program(vert_src: &str, frag_src: &str, attributes: &[Attribute]) -> Result<GLuint> {
+    unsafe {
+        // Compile both shaders, return an error if it fails
+        let vert = try!(compile_shader(Stage::Vertex, vert_src));
+        let frag = match compile_shader(Stage::Fragment, frag_src) {
+            Ok(frag) => frag,
+            Err(err) => {
+                gl::DeleteShader(vert);
+                return Err(err);
+            }
+        };
+
+        // Link the two shaders into a program
+        let program = gl::CreateProgram();
+
+        if program != 0 {
+            gl::AttachShader(program, vert);
+            gl::AttachShader(program, frag);
+
+            // Explicit locations in the shader source take precedence
+            for attr in attributes {
+                let name = CString::new(attr.name).unwrap();
+                gl::BindAttribLocation(program, attr.location, name.as_ptr());
+            }
+
+            gl::LinkProgram(program);
+
+            // Detach the shaders
+            gl::DetachShader(program, vert);
+            gl::DetachShader(program, frag);
+        }
+
+        // The shader objects are not needed anymore
+        gl::DeleteShader(vert);
+        gl::DeleteShader(frag);
+
+        if program == 0 {
+            return Err(Error::GlShader(ShaderError::new("Failed to create shader program")));
+        }
+
+        // Check link success
+        let mut linked: GLint = 0;
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut linked as *mut GLint);
+
+        if linked <= 0 {
+            let mut err = ShaderError::new("Failed to link shader program");
+            err.log = program_info_log(program);
+
+            gl::DeleteProgram(program);
+            return Err(Error::GlShader(err));
+        }
+
+        Ok(program)
+    }
+}
+
+// Query the active uniforms, uniform blocks and attributes
+// of a linked program
+unsafe fn reflect(program: GLuint) -> Reflection {
+    let mut reflection = Reflection::default();
+
+    // Uniforms
+    let mut count: GLint = 0;
+    gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+
+    for i in 0..count as GLuint {
+        let (name, size, kind) = active_resource(program, i, gl::ACTIVE_UNIFORM_MAX_LENGTH, gl::GetActiveUniform);
+        let location = gl::GetUniformLocation(program, CString::new(name.clone()).unwrap().as_ptr());
+
+        let mut block: GLint = -1;
+        gl::GetActiveUniformsiv(program, 1, &i, gl::UNIFORM_BLOCK_INDEX, &mut block);
+
+        reflection.uniforms.push(UniformInfo {
+            name: name,
+            kind: kind,
+            size: size,
+            location: location,
+            block: if block >= 0 { Some(block as GLuint) } else { None }
+        });
+    }
+
+    // Uniform blocks
+    gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+
+    for i in 0..count as GLuint {
+        let mut len: GLint = 0;
+        gl::GetActiveUniformBlockiv(program, i, gl::UNIFORM_BLOCK_NAME_LENGTH, &mut len);
+
+        let mut buf = vec![0u8; len.max(1) as usize];
+        let mut written: GLsizei = 0;
+        gl::GetActiveUniformBlockName(program, i, buf.len() as GLsizei, &mut written, buf.as_mut_ptr() as *mut GLchar);
+
+        let mut data_size: GLint = 0;
+        gl::GetActiveUniformBlockiv(program, i, gl::UNIFORM_BLOCK_DATA_SIZE, &mut data_size);
+
+        let uniforms = reflection.uniforms.iter()
+            .filter(|info| info.block == Some(i))
+            .map(|info| info.name.clone())
+            .collect();
+
+        reflection.blocks.push(UniformBlockInfo {
+            name: String::from_utf8_lossy(&buf[..written as usize]).into_owned(),
+            index: i,
+            data_size: data_size,
+            uniforms: uniforms
+        });
+    }
+
+    // Vertex attributes
+    gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count);
+
+    for i in 0..count as GLuint {
+        let (name, size, kind) = active_resource(program, i, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, gl::GetActiveAttrib);
+        let location = gl::GetAttribLocation(program, CString::new(name.clone()).unwrap().as_ptr());
+
+        reflection.attributes.push(AttributeInfo {
+            name: name,
+            kind: kind,
+            size: size,
+            location: location
+        });
+    }
+
+    reflection.attributes.sort_by_key(|info| info.location);
+    reflection
+}
+
+// Signature shared by glGetActiveUniform and glGetActiveAttrib
+type GetActiveFn = unsafe fn(GLuint, GLuint, GLsizei, *mut GLsizei, *mut GLint, *mut GLenum, *mut GLchar);
+
+// Query the name, size and type of an active uniform or attribute. The
+// name buffer is sized from the longest name, given by `max_length`
+// (GL_ACTIVE_UNIFORM_MAX_LENGTH or GL_ACTIVE_ATTRIBUTE_MAX_LENGTH)
+unsafe fn active_resource(program: GLuint, index: GLuint, max_length: GLenum, get: GetActiveFn) -> (String, GLint, GLenum) {
+    let mut max: GLint = 0;
+    gl::GetProgramiv(program, max_length, &mut max);
+
+    let mut buf = vec![0u8; max.max(1) as usize];
+    let mut len: GLsizei = 0;
+    let mut size: GLint = 0;
+    let mut kind: GLenum = 0;
+
+    get(program, index, buf.len() as GLsizei, &mut len, &mut size, &mut kind, buf.as_mut_ptr() as *mut GLchar);
+
+    (String::from_utf8_lossy(&buf[..len as usize]).into_owned(), size, kind)
+}
+
+// Tell whether an attribute type is read as integers by the shader,
+// its number of components, and the number of locations it takes
+// (the columns of matrices, each made of as many components as rows)
+fn attribute_shape(kind: GLenum) -> (bool, GLint, GLint) {
+    match kind {
+        gl::INT | gl::UNSIGNED_INT => (true, 1, 1),
+        gl::INT_VEC2 | gl::UNSIGNED_INT_VEC2 => (true, 2, 1),
+        gl::INT_VEC3 | gl::UNSIGNED_INT_VEC3 => (true, 3, 1),
+        gl::INT_VEC4 | gl::UNSIGNED_INT_VEC4 => (true, 4, 1),
+        gl::FLOAT => (false, 1, 1),
+        gl::FLOAT_VEC2 => (false, 2, 1),
+        gl::FLOAT_VEC3 => (false, 3, 1),
+        gl::FLOAT_VEC4 => (false, 4, 1),
+        gl::FLOAT_MAT2 => (false, 2, 2),
+        gl::FLOAT_MAT3 => (false, 3, 3),
+        gl::FLOAT_MAT4 => (false, 4, 4),
+        gl::FLOAT_MAT2x3 => (false, 3, 2),
+        gl::FLOAT_MAT2x4 => (false, 4, 2),
+        gl::FLOAT_MAT3x2 => (false, 2, 3),
+        gl::FLOAT_MAT3x4 => (false, 4, 3),
+        gl::FLOAT_MAT4x2 => (false, 2, 4),
+        gl::FLOAT_MAT4x3 => (false, 3, 4),
+
+        // Types of later GL versions, such as doubles, are
+        // only checked to be provided
+        _ => (false, 4, 1)
+    }
+}
+
+// Read a whole source file
+fn read_file(path: &Path) -> Result<String> {
+    let mut src = String::new();
+
+    try!(File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut src))
+        .map_err(Error::Io));
+
+    Ok(src)
+}
+
+// Get the last modification time of a file, if it can be known
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// Create and compile a shader for the specified stage. The shader
+// object is deleted if the compilation fails
+unsafe fn compile_shader(stage: Stage, src: &str) -> Result<GLuint> {
+    let shader = match gl::CreateShader(stage.to_gl_const()) {
+        0 => return Err(Error::GlShader(ShaderError::new("Failed to create shader object"))),
+        shader => shader
+    };
+
+    // Bind the source code of the shader
+    gl::ShaderSource(
+        shader,
+        1 as GLsizei,
+        &(src.as_ptr() as *const GLchar),
+        &(src.len() as GLint)
+    );
+
+    gl::CompileShader(shader);
+
+    // Check for compilation errors
+    let mut compiled: GLint = 0;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut compiled as *mut GLint);
+
+    if compiled <= 0 {
+        let log = shader_info_log(shader);
+        gl::DeleteShader(shader);
+
+        return Err(Error::GlShader(ShaderError {
+            message: format!("Failed to compile {} shader", stage),
+            stage: Some(stage),
+            log: log,
+            source: Some(src.to_owned())
+        }));
+    }
+
+    Ok(shader)
+}
+
+// Retrieve the info log of a shader object
+unsafe fn shader_info_log(shader: GLuint) -> String {
+    let mut len: GLint = 0;
+    gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len as *mut GLint);
+
+    let mut buf = vec![0u8; len.max(1) as usize];
+    let mut written: GLsizei = 0;
+
+    gl::GetShaderInfoLog(shader, buf.len() as GLsizei, &mut written, buf.as_mut_ptr() as *mut GLchar);
+    buf.truncate(written.max(0) as usize);
+
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+// Retrieve the info log of a program object
+unsafe fn program_info_log(program: GLuint) -> String {
+    let mut len: GLint = 0;
+    gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len as *mut GLint);
+
+    let mut buf = vec![0u8; len.max(1) as usize];
+    let mut written: GLsizei = 0;
+
+    gl::GetProgramInfoLog(program, buf.len() as GLsizei, &mut written, buf.as_mut_ptr() as *mut GLchar);
+    buf.truncate(written.max(0) as usize);
+
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+// Extract the source line number from an entry of the info log,
+// looking for the first `<file>:<line>` or `<file>(<line>)` pattern
+fn parse_log_line(entry: &str) -> Option<usize> {
+    let bytes = entry.as_bytes();
+
+    for i in 0..bytes.len() {
+        // The source string index, usually 0
+        if !bytes[i].is_ascii_digit() || (i > 0 && bytes[i - 1].is_ascii_alphanumeric()) {
+            continue;
+        }
+
+        let mut j = i;
+
+        while j < bytes.len() && bytes[j].is_ascii_digit() {
+            j += 1;
+        }
+
+        if j + 1 >= bytes.len() || (bytes[j] != b':' && bytes[j] != b'(') {
+            continue;
+        }
+
+        // The line number itself
+        let start = j + 1;
+        let mut end = start;
+
+        while end < bytes.len() && bytes[end].is_ascii_digit() {
+            end += 1;
+        }
+
+        if end > start {
+            return entry[start..end].parse().ok();
+        }
+    }
+
+    None
+}
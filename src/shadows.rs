@@ -0,0 +1,202 @@
+use gl;
+use gl::types::*;
+
+use resources::{self, ContextId, Resource};
+use target::SavedTarget;
+
+/// Represents the depth textures of several lights, stored in the
+/// layers of one array texture so that they are all sampled through a
+/// single texture unit. Each layer can hold a smaller map in its
+/// lower left corner
+pub struct ShadowMapArray {
+    pub resolution: u32,
+    pub layers: u32,
+
+    texture: GLuint,
+    fbo: GLuint,
+    owner: ContextId
+}
+
+impl ShadowMapArray {
+    /// Create a new array of square shadow maps
+    pub fn new(resolution: u32, layers: u32) -> ShadowMapArray {
+        unsafe {
+            // Create the depth array texture
+            let mut texture: GLuint = 0;
+
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, texture);
+
+            gl::TexImage3D(
+                gl::TEXTURE_2D_ARRAY,
+                0,
+                gl::DEPTH_COMPONENT24 as GLint,
+                resolution as GLsizei,
+                resolution as GLsizei,
+                layers as GLsizei,
+                0,
+                gl::DEPTH_COMPONENT,
+                gl::FLOAT,
+                0 as *const _
+            );
+
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as GLint);
+
+            // Everything outside of the layers is lit
+            let border = [1.0f32, 1.0, 1.0, 1.0];
+
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as GLint);
+            gl::TexParameterfv(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_BORDER_COLOR, border.as_ptr());
+
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, 0);
+
+            // Create a framebuffer with only a depth attachment,
+            // the layer rendered to is attached by `begin`
+            let mut fbo: GLuint = 0;
+            let saved = SavedTarget::save();
+
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTextureLayer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, texture, 0, 0);
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+
+            saved.restore();
+
+            ShadowMapArray {
+                resolution: resolution,
+                layers: layers,
+                texture: texture,
+                fbo: fbo,
+                owner: resources::current_context()
+            }
+        }
+    }
+
+    /// Start rendering depth into one of the layers, restricted to
+    /// its lower left corner of the specified resolution. The whole
+    /// layer is cleared, so that the rest of it is lit
+    pub fn begin(&self, layer: u32, resolution: u32) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTextureLayer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, self.texture, 0, layer as GLint);
+            gl::Viewport(0, 0, resolution as GLsizei, resolution as GLsizei);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Bind the array texture to the specified texture unit
+    pub fn bind_texture(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.texture);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+}
+
+impl Drop for ShadowMapArray {
+    fn drop(&mut self) {
+        resources::release(self.owner, Resource::Framebuffer(self.fbo));
+        resources::release(self.owner, Resource::Texture(self.texture));
+    }
+}
+
+/// Represents a depth cube map rendered around a point light,
+/// storing in each texel the distance to the light divided by
+/// the range of its shadows
+pub struct CubeShadowMap {
+    pub resolution: u32,
+
+    texture: GLuint,
+    fbo: GLuint,
+    owner: ContextId
+}
+
+impl CubeShadowMap {
+    /// Create a new cube shadow map, whose faces are square
+    pub fn new(resolution: u32) -> CubeShadowMap {
+        unsafe {
+            // Create the depth cube map
+            let mut texture: GLuint = 0;
+
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture);
+
+            for face in 0..6 {
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                    0,
+                    gl::DEPTH_COMPONENT24 as GLint,
+                    resolution as GLsizei,
+                    resolution as GLsizei,
+                    0,
+                    gl::DEPTH_COMPONENT,
+                    gl::FLOAT,
+                    0 as *const _
+                );
+            }
+
+            // Distances are compared in the shader, so the
+            // texture is sampled without depth comparison
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as GLint);
+
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+
+            // Create a framebuffer with only a depth attachment,
+            // the face rendered to is attached by `begin`
+            let mut fbo: GLuint = 0;
+            let saved = SavedTarget::save();
+
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_CUBE_MAP_POSITIVE_X, texture, 0);
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+
+            saved.restore();
+
+            CubeShadowMap {
+                resolution: resolution,
+                texture: texture,
+                fbo: fbo,
+                owner: resources::current_context()
+            }
+        }
+    }
+
+    /// Start rendering depth into one of the faces of the
+    /// cube map, from 0 to 5 in the order of the GL faces
+    pub fn begin(&self, face: u32) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_CUBE_MAP_POSITIVE_X + face, self.texture, 0);
+            gl::Viewport(0, 0, self.resolution as GLsizei, self.resolution as GLsizei);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Bind the cube map to the specified texture unit
+    pub fn bind_texture(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.texture);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+}
+
+impl Drop for CubeShadowMap {
+    fn drop(&mut self) {
+        resources::release(self.owner, Resource::Framebuffer(self.fbo));
+        resources::release(self.owner, Resource::Texture(self.texture));
+    }
+}
@@ -0,0 +1,110 @@
+use std::rc::Rc;
+
+use gl;
+use gl::types::*;
+
+use super::Result;
+use math::{Mat4, Vec4, SquareMatrix};
+use camera::Camera;
+use mesh::CubeMap;
+use shaders::Program;
+use resources::{self, ContextId, Resource};
+
+/// Draws a cube map behind the scene, as if it were infinitely far away.
+/// It only depends on the orientation of the camera, not on its position
+pub struct Skybox {
+    /// Scale applied to the colors of the cube map, for
+    /// instance to match the exposure of an HDR scene
+    pub intensity: f32,
+
+    cube_map: Rc<CubeMap>,
+    program: Program,
+    vao: GLuint,
+    owner: ContextId
+}
+
+impl Skybox {
+    /// Create a new skybox drawing the specified cube map
+    pub fn new(cube_map: Rc<CubeMap>) -> Result<Skybox> {
+        let program = try!(Program::new(
+            &format!("#version 140\n{}", include_str!("glsl/skybox.vert")),
+            &format!("#version 140\n{}", include_str!("glsl/skybox.frag"))
+        ));
+
+        let mut vao: GLuint = 0;
+
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+
+        Ok(Skybox {
+            intensity: 1.0,
+
+            cube_map: cube_map,
+            program: program,
+            vao: vao,
+            owner: resources::current_context()
+        })
+    }
+
+    /// Set the scale applied to the colors of the cube map
+    pub fn intensity(mut self, intensity: f32) -> Skybox {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Get the cube map drawn by the skybox
+    pub fn cube_map(&self) -> &Rc<CubeMap> {
+        &self.cube_map
+    }
+
+    /// Draw the skybox as seen by the camera. It is drawn on the far plane,
+    /// without writing depth, so it can be drawn either before the scene or
+    /// after its opaque objects, to only fill the pixels left empty
+    pub fn render(&self, camera: &Camera, projection: Mat4) {
+        // Remove the translation of the view
+        let mut view = camera.view_matrix();
+        view.w = Vec4::new(0.0, 0.0, 0.0, 1.0);
+
+        let inverse = match (projection * view).invert() {
+            Some(inverse) => inverse,
+            None => return
+        };
+
+        unsafe {
+            let mut program: GLint = 0;
+            let mut depth_func: GLint = 0;
+            let mut depth_mask: GLboolean = 0;
+
+            gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut program);
+            gl::GetIntegerv(gl::DEPTH_FUNC, &mut depth_func);
+            gl::GetBooleanv(gl::DEPTH_WRITEMASK, &mut depth_mask);
+
+            self.program.bind();
+            let _ = self.program.set_uniform("inverse_view_projection", &inverse);
+            let _ = self.program.set_uniform("cube_map", &0);
+            let _ = self.program.set_uniform("intensity", &self.intensity);
+
+            self.cube_map.bind_to(0);
+
+            // The far plane passes the depth test against a cleared buffer
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(gl::LEQUAL);
+            gl::DepthMask(gl::FALSE);
+
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+
+            gl::DepthMask(depth_mask);
+            gl::DepthFunc(depth_func as GLenum);
+            gl::UseProgram(program as GLuint);
+        }
+    }
+}
+
+impl Drop for Skybox {
+    fn drop(&mut self) {
+        resources::release(self.owner, Resource::VertexArray(self.vao));
+    }
+}
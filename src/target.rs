@@ -0,0 +1,288 @@
+use std::cell::RefCell;
+
+use gl;
+use gl::types::*;
+
+use super::{Error, Result};
+use mesh::{Texture, TextureFormat};
+use image::Image;
+use resources::{self, ContextId, Resource};
+
+/// Kinds of depth and stencil buffers of a render target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthBuffer {
+    /// No depth testing in the target
+    None,
+
+    /// A depth buffer, which cannot be sampled
+    Depth,
+
+    /// A packed depth and stencil buffer, which cannot be sampled
+    DepthStencil,
+
+    /// A depth texture, which can be sampled once rendered
+    Texture
+}
+
+/// Represents the framebuffer and viewport being rendered to,
+/// so that they can be restored after rendering a pass elsewhere
+pub(crate) struct SavedTarget {
+    framebuffer: GLint,
+    viewport: [GLint; 4]
+}
+
+impl SavedTarget {
+    /// Remember the current framebuffer and viewport
+    pub fn save() -> SavedTarget {
+        let mut target = SavedTarget {
+            framebuffer: 0,
+            viewport: [0; 4]
+        };
+
+        unsafe {
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut target.framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, target.viewport.as_mut_ptr());
+        }
+
+        target
+    }
+
+    /// Render to the saved framebuffer and viewport again
+    pub fn restore(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer as GLuint);
+            gl::Viewport(self.viewport[0], self.viewport[1], self.viewport[2], self.viewport[3]);
+        }
+    }
+}
+
+/// Represents an offscreen framebuffer, rendering into textures
+/// that can be sampled afterwards. It has any number of color
+/// attachments, and optionally a depth or depth-stencil buffer
+pub struct RenderTarget {
+    pub width: u32,
+    pub height: u32,
+
+    fbo: GLuint,
+    colors: Vec<Texture>,
+    format: TextureFormat,
+    depth: DepthBuffer,
+    depth_renderbuffer: Option<GLuint>,
+    depth_texture: Option<Texture>,
+
+    // Target to restore when unbinding
+    saved: RefCell<Option<SavedTarget>>,
+
+    owner: ContextId
+}
+
+impl RenderTarget {
+    /// Create a new render target, with the specified number
+    /// of 8 bit RGBA color attachments
+    pub fn new(width: u32, height: u32, colors: usize, depth: DepthBuffer) -> Result<RenderTarget> {
+        RenderTarget::with_format(width, height, colors, TextureFormat::Rgba8, depth)
+    }
+
+    /// Create a new render target whose color attachments use the
+    /// specified format. Floating point formats keep colors brighter
+    /// than white, to be tone mapped afterwards
+    pub fn with_format(width: u32, height: u32, colors: usize, format: TextureFormat, depth: DepthBuffer) -> Result<RenderTarget> {
+        let colors: Vec<Texture> = (0..colors).map(|_| Texture::empty_with_format(width, height, format)).collect();
+
+        unsafe {
+            let mut fbo: GLuint = 0;
+            let saved = SavedTarget::save();
+
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+
+            // Attach the color textures, and render to all of them
+            let mut buffers = Vec::with_capacity(colors.len());
+
+            for (i, texture) in colors.iter().enumerate() {
+                let attachment = gl::COLOR_ATTACHMENT0 + i as GLenum;
+
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture.id(), 0);
+                buffers.push(attachment);
+            }
+
+            if buffers.is_empty() {
+                gl::DrawBuffer(gl::NONE);
+                gl::ReadBuffer(gl::NONE);
+            }
+            else {
+                gl::DrawBuffers(buffers.len() as GLsizei, buffers.as_ptr());
+            }
+
+            // Attach the depth buffer
+            let (depth_renderbuffer, depth_texture) = match depth {
+                DepthBuffer::None => (None, None),
+                DepthBuffer::Depth => {
+                    (Some(create_renderbuffer(width, height, gl::DEPTH_COMPONENT24, gl::DEPTH_ATTACHMENT)), None)
+                },
+                DepthBuffer::DepthStencil => {
+                    (Some(create_renderbuffer(width, height, gl::DEPTH24_STENCIL8, gl::DEPTH_STENCIL_ATTACHMENT)), None)
+                },
+                DepthBuffer::Texture => {
+                    let texture = create_depth_texture(width, height);
+                    gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_2D, texture.id(), 0);
+
+                    (None, Some(texture))
+                }
+            };
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            saved.restore();
+
+            let target = RenderTarget {
+                width: width,
+                height: height,
+
+                fbo: fbo,
+                colors: colors,
+                format: format,
+                depth: depth,
+                depth_renderbuffer: depth_renderbuffer,
+                depth_texture: depth_texture,
+
+                saved: RefCell::new(None),
+
+                owner: resources::current_context()
+            };
+
+            // The objects are released by dropping the target
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(Error::Framebuffer(status));
+            }
+
+            Ok(target)
+        }
+    }
+
+    /// Recreate the attachments of the target with a new size,
+    /// for instance when the window is resized. Their previous
+    /// content is lost
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        if width != self.width || height != self.height {
+            *self = try!(RenderTarget::with_format(width, height, self.colors.len(), self.format, self.depth));
+        }
+
+        Ok(())
+    }
+
+    /// Render into the target, with a viewport covering it
+    /// entirely, until `unbind` is called
+    pub fn bind(&self) {
+        let mut saved = self.saved.borrow_mut();
+
+        if saved.is_none() {
+            *saved = Some(SavedTarget::save());
+        }
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+        }
+    }
+
+    /// Render into the framebuffer and viewport that
+    /// were used before the target was bound again
+    pub fn unbind(&self) {
+        if let Some(saved) = self.saved.borrow_mut().take() {
+            saved.restore();
+        }
+    }
+
+    /// Render a pass into the target. It is bound while the
+    /// specified closure is called, and unbound afterwards
+    pub fn render<F: FnOnce()>(&self, render: F) {
+        self.bind();
+        render();
+        self.unbind();
+    }
+
+    /// Get the format of the color attachments
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Get one of the color attachments, to be sampled
+    pub fn color(&self, index: usize) -> &Texture {
+        &self.colors[index]
+    }
+
+    /// Get all the color attachments, in the order of their
+    /// fragment shader outputs
+    pub fn colors(&self) -> &[Texture] {
+        &self.colors
+    }
+
+    /// Get the depth attachment, if the target
+    /// was created with a depth texture
+    pub fn depth_texture(&self) -> Option<&Texture> {
+        self.depth_texture.as_ref()
+    }
+
+    /// Read back one of the color attachments, for instance
+    /// to save it or to find which object is under the mouse
+    pub fn capture(&self, index: usize) -> Image {
+        let saved = SavedTarget::save();
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0 + index as GLenum);
+        }
+
+        let image = Image::from_framebuffer(0, 0, self.width, self.height);
+        saved.restore();
+
+        image
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        resources::release(self.owner, Resource::Framebuffer(self.fbo));
+
+        if let Some(id) = self.depth_renderbuffer {
+            resources::release(self.owner, Resource::Renderbuffer(id));
+        }
+    }
+}
+
+// Create a renderbuffer and attach it to the bound framebuffer
+unsafe fn create_renderbuffer(width: u32, height: u32, format: GLenum, attachment: GLenum) -> GLuint {
+    let mut id: GLuint = 0;
+
+    gl::GenRenderbuffers(1, &mut id);
+    gl::BindRenderbuffer(gl::RENDERBUFFER, id);
+    gl::RenderbufferStorage(gl::RENDERBUFFER, format, width as GLsizei, height as GLsizei);
+    gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+    gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment, gl::RENDERBUFFER, id);
+
+    id
+}
+
+// Create a depth texture that can be sampled as a regular texture
+unsafe fn create_depth_texture(width: u32, height: u32) -> Texture {
+    let texture = Texture::empty(width, height);
+
+    gl::BindTexture(gl::TEXTURE_2D, texture.id());
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        gl::DEPTH_COMPONENT24 as GLint,
+        width as GLsizei,
+        height as GLsizei,
+        0,
+        gl::DEPTH_COMPONENT,
+        gl::FLOAT,
+        0 as *const _
+    );
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+    gl::BindTexture(gl::TEXTURE_2D, 0);
+
+    texture
+}
@@ -0,0 +1,158 @@
+use gl;
+use gl::types::*;
+
+use math::{Vec2, Vec3, Vec4, Mat2, Mat3, Mat4, P3};
+
+/// Implemented by the types that can be assigned to a shader uniform.
+/// Slices set the successive elements of uniform arrays
+pub trait Uniform {
+    /// Set the value of the uniform at the specified location,
+    /// the program owning it must be bound
+    fn set(&self, location: GLint);
+
+    /// Check whether the value can be assigned to a uniform
+    /// of the specified GL type
+    fn accepts(kind: GLenum) -> bool;
+}
+
+// Implement Uniform for a type, given the GL types it is compatible
+// with and the way to upload `count` values starting at `ptr`
+macro_rules! uniform {
+    ($ty:ty, $elem:ty, |$kind:ident| $accepts:expr, |$loc:ident, $count:ident, $ptr:ident| $set:expr) => {
+        impl Uniform for $ty {
+            fn set(&self, $loc: GLint) {
+                let $count = 1;
+                let $ptr = self as *const $ty as *const $elem;
+
+                unsafe {
+                    $set;
+                }
+            }
+
+            fn accepts($kind: GLenum) -> bool {
+                $accepts
+            }
+        }
+
+        impl Uniform for [$ty] {
+            fn set(&self, $loc: GLint) {
+                let $count = self.len() as GLsizei;
+                let $ptr = self.as_ptr() as *const $elem;
+
+                unsafe {
+                    $set;
+                }
+            }
+
+            fn accepts($kind: GLenum) -> bool {
+                $accepts
+            }
+        }
+    };
+}
+
+uniform!(f32, GLfloat, |k| k == gl::FLOAT, |loc, n, ptr| gl::Uniform1fv(loc, n, ptr));
+uniform!([f32; 2], GLfloat, |k| k == gl::FLOAT_VEC2, |loc, n, ptr| gl::Uniform2fv(loc, n, ptr));
+uniform!([f32; 3], GLfloat, |k| k == gl::FLOAT_VEC3, |loc, n, ptr| gl::Uniform3fv(loc, n, ptr));
+uniform!([f32; 4], GLfloat, |k| k == gl::FLOAT_VEC4, |loc, n, ptr| gl::Uniform4fv(loc, n, ptr));
+uniform!(Vec2, GLfloat, |k| k == gl::FLOAT_VEC2, |loc, n, ptr| gl::Uniform2fv(loc, n, ptr));
+uniform!(Vec3, GLfloat, |k| k == gl::FLOAT_VEC3, |loc, n, ptr| gl::Uniform3fv(loc, n, ptr));
+uniform!(Vec4, GLfloat, |k| k == gl::FLOAT_VEC4, |loc, n, ptr| gl::Uniform4fv(loc, n, ptr));
+uniform!(P3, GLfloat, |k| k == gl::FLOAT_VEC3, |loc, n, ptr| gl::Uniform3fv(loc, n, ptr));
+
+// Integers also set booleans and the texture unit of samplers
+uniform!(i32, GLint, |k| k == gl::INT || k == gl::BOOL || is_sampler(k), |loc, n, ptr| gl::Uniform1iv(loc, n, ptr));
+uniform!([i32; 2], GLint, |k| k == gl::INT_VEC2 || k == gl::BOOL_VEC2, |loc, n, ptr| gl::Uniform2iv(loc, n, ptr));
+uniform!([i32; 3], GLint, |k| k == gl::INT_VEC3 || k == gl::BOOL_VEC3, |loc, n, ptr| gl::Uniform3iv(loc, n, ptr));
+uniform!([i32; 4], GLint, |k| k == gl::INT_VEC4 || k == gl::BOOL_VEC4, |loc, n, ptr| gl::Uniform4iv(loc, n, ptr));
+uniform!(u32, GLuint, |k| k == gl::UNSIGNED_INT || k == gl::BOOL, |loc, n, ptr| gl::Uniform1uiv(loc, n, ptr));
+uniform!([u32; 2], GLuint, |k| k == gl::UNSIGNED_INT_VEC2, |loc, n, ptr| gl::Uniform2uiv(loc, n, ptr));
+uniform!([u32; 3], GLuint, |k| k == gl::UNSIGNED_INT_VEC3, |loc, n, ptr| gl::Uniform3uiv(loc, n, ptr));
+uniform!([u32; 4], GLuint, |k| k == gl::UNSIGNED_INT_VEC4, |loc, n, ptr| gl::Uniform4uiv(loc, n, ptr));
+
+// Matrices are stored in column major order, both by cgmath and OpenGL
+uniform!(Mat2, GLfloat, |k| k == gl::FLOAT_MAT2, |loc, n, ptr| gl::UniformMatrix2fv(loc, n, gl::FALSE, ptr));
+uniform!(Mat3, GLfloat, |k| k == gl::FLOAT_MAT3, |loc, n, ptr| gl::UniformMatrix3fv(loc, n, gl::FALSE, ptr));
+uniform!(Mat4, GLfloat, |k| k == gl::FLOAT_MAT4, |loc, n, ptr| gl::UniformMatrix4fv(loc, n, gl::FALSE, ptr));
+uniform!([f32; 16], GLfloat, |k| k == gl::FLOAT_MAT4, |loc, n, ptr| gl::UniformMatrix4fv(loc, n, gl::FALSE, ptr));
+
+impl Uniform for bool {
+    fn set(&self, location: GLint) {
+        unsafe {
+            gl::Uniform1i(location, *self as GLint);
+        }
+    }
+
+    fn accepts(kind: GLenum) -> bool {
+        kind == gl::BOOL
+    }
+}
+
+/// Check whether a GL type is a sampler type
+pub fn is_sampler(kind: GLenum) -> bool {
+    match kind {
+        gl::SAMPLER_1D | gl::SAMPLER_2D | gl::SAMPLER_3D | gl::SAMPLER_CUBE |
+        gl::SAMPLER_1D_SHADOW | gl::SAMPLER_2D_SHADOW | gl::SAMPLER_CUBE_SHADOW |
+        gl::SAMPLER_1D_ARRAY | gl::SAMPLER_2D_ARRAY |
+        gl::SAMPLER_1D_ARRAY_SHADOW | gl::SAMPLER_2D_ARRAY_SHADOW |
+        gl::SAMPLER_2D_MULTISAMPLE | gl::SAMPLER_2D_RECT | gl::SAMPLER_BUFFER |
+        gl::INT_SAMPLER_2D | gl::INT_SAMPLER_3D | gl::INT_SAMPLER_CUBE | gl::INT_SAMPLER_2D_ARRAY |
+        gl::UNSIGNED_INT_SAMPLER_2D | gl::UNSIGNED_INT_SAMPLER_3D |
+        gl::UNSIGNED_INT_SAMPLER_CUBE | gl::UNSIGNED_INT_SAMPLER_2D_ARRAY => true,
+        _ => false
+    }
+}
+
+/// Get the GLSL name of a GL type, as used in shader sources
+pub fn type_name(kind: GLenum) -> &'static str {
+    match kind {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::UNSIGNED_INT_VEC4 => "uvec4",
+        gl::BOOL => "bool",
+        gl::BOOL_VEC2 => "bvec2",
+        gl::BOOL_VEC3 => "bvec3",
+        gl::BOOL_VEC4 => "bvec4",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::FLOAT_MAT2x3 => "mat2x3",
+        gl::FLOAT_MAT2x4 => "mat2x4",
+        gl::FLOAT_MAT3x2 => "mat3x2",
+        gl::FLOAT_MAT3x4 => "mat3x4",
+        gl::FLOAT_MAT4x2 => "mat4x2",
+        gl::FLOAT_MAT4x3 => "mat4x3",
+        gl::SAMPLER_1D => "sampler1D",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_3D => "sampler3D",
+        gl::SAMPLER_CUBE => "samplerCube",
+        gl::SAMPLER_1D_SHADOW => "sampler1DShadow",
+        gl::SAMPLER_2D_SHADOW => "sampler2DShadow",
+        gl::SAMPLER_CUBE_SHADOW => "samplerCubeShadow",
+        gl::SAMPLER_1D_ARRAY => "sampler1DArray",
+        gl::SAMPLER_2D_ARRAY => "sampler2DArray",
+        gl::SAMPLER_1D_ARRAY_SHADOW => "sampler1DArrayShadow",
+        gl::SAMPLER_2D_ARRAY_SHADOW => "sampler2DArrayShadow",
+        gl::SAMPLER_2D_MULTISAMPLE => "sampler2DMS",
+        gl::SAMPLER_2D_RECT => "sampler2DRect",
+        gl::SAMPLER_BUFFER => "samplerBuffer",
+        gl::INT_SAMPLER_2D => "isampler2D",
+        gl::INT_SAMPLER_3D => "isampler3D",
+        gl::INT_SAMPLER_CUBE => "isamplerCube",
+        gl::INT_SAMPLER_2D_ARRAY => "isampler2DArray",
+        gl::UNSIGNED_INT_SAMPLER_2D => "usampler2D",
+        gl::UNSIGNED_INT_SAMPLER_3D => "usampler3D",
+        gl::UNSIGNED_INT_SAMPLER_CUBE => "usamplerCube",
+        gl::UNSIGNED_INT_SAMPLER_2D_ARRAY => "usampler2DArray",
+        _ => "unknown"
+    }
+}
@@ -1,6 +1,8 @@
 use gl;
 use glutin::{self, GlContext};
 use super::{Result, Error};
+use image::Image;
+use resources::{self, ContextId};
 
 /// Represents a window
 pub struct Window {
@@ -9,7 +11,8 @@ pub struct Window {
     pub should_exit: bool,
 
     evt_loop: glutin::EventsLoop,
-    gl_win: glutin::GlWindow
+    gl_win: glutin::GlWindow,
+    id: ContextId
 }
 
 impl Window {
@@ -35,15 +38,11 @@ impl Window {
             Err(err) => return Err(Error::WindowCreation(err))
         };
 
-        unsafe {
-            // Try to use the created OpenGL context
-            if let Err(err) = win.make_current() {
-                return Err(Error::GlContext(err))
-            }
+        // Use the context and load the OpenGL symbols
+        try!(load_context(&win));
 
-            // Load OpenGL symbols
-            gl::load_with(|symbol| win.get_proc_address(symbol) as *const _);
-        }
+        let id = resources::context_created();
+        resources::context_current(id);
 
         // Success, return the window representation to the caller
         Ok(Window {
@@ -52,7 +51,8 @@ impl Window {
             should_exit: false,
 
             evt_loop: evt,
-            gl_win: win
+            gl_win: win,
+            id: id
         })
     }
 
@@ -77,4 +77,103 @@ impl Window {
     pub fn swap_buffers(&self) {
         self.gl_win.swap_buffers().unwrap();
     }
+
+    /// Capture the frame that is currently being rendered. Must be
+    /// called before swapping the buffers
+    pub fn capture_frame(&self) -> Image {
+        Image::from_framebuffer(0, 0, self.width, self.height)
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        resources::context_destroyed(self.id);
+    }
+}
+
+/// Represents an offscreen rendering surface, backed by
+/// a headless OpenGL context (OSMesa or pbuffer depending
+/// on the platform). It does not need a display, which makes
+/// it suitable for tests and batch rendering
+pub struct Headless {
+    pub width: u32,
+    pub height: u32,
+
+    gl_ctx: glutin::HeadlessContext,
+    id: ContextId
+}
+
+impl Headless {
+    /// Create a new headless surface with the specified
+    /// width and height
+    pub fn new(width: u32, height: u32) -> Result<Headless> {
+        // Create the OpenGL context, without any window
+        let ctx = match glutin::HeadlessRendererBuilder::new(width, height)
+            .with_gl_profile(glutin::GlProfile::Compatibility)
+            .build() {
+            Ok(ctx) => ctx,
+            Err(err) => return Err(Error::WindowCreation(err))
+        };
+
+        // Use the context and load the OpenGL symbols
+        try!(load_context(&ctx));
+
+        let id = resources::context_created();
+        resources::context_current(id);
+
+        unsafe {
+            // Make sure the whole surface is being rendered to
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+
+        Ok(Headless {
+            width: width,
+            height: height,
+
+            gl_ctx: ctx,
+            id: id
+        })
+    }
+
+    /// Make this surface's context the current one, in case another
+    /// context has been used in the meantime
+    pub fn make_current(&self) -> Result<()> {
+        try!(load_context(&self.gl_ctx));
+        resources::context_current(self.id);
+
+        Ok(())
+    }
+
+    /// Read back the whole content of the surface, as 8 bit RGBA
+    /// pixels ordered from the bottom row to the top row
+    pub fn read_pixels(&self) -> Vec<u8> {
+        super::read_pixels(0, 0, self.width, self.height)
+    }
+
+    /// Capture the frame that has been rendered to the surface
+    pub fn capture_frame(&self) -> Image {
+        Image::from_framebuffer(0, 0, self.width, self.height)
+    }
+}
+
+impl Drop for Headless {
+    fn drop(&mut self) {
+        resources::context_destroyed(self.id);
+    }
+}
+
+// Make the specified context current and load the OpenGL
+// function pointers from it
+fn load_context<C: GlContext>(ctx: &C) -> Result<()> {
+    unsafe {
+        // Try to use the created OpenGL context
+        if let Err(err) = ctx.make_current() {
+            return Err(Error::GlContext(err))
+        }
+
+        // Load OpenGL symbols
+        gl::load_with(|symbol| ctx.get_proc_address(symbol) as *const _);
+    }
+
+    Ok(())
 }
@@ -0,0 +1,211 @@
+extern crate rendust;
+extern crate cgmath;
+
+use std::env;
+use std::f32;
+
+use cgmath::SquareMatrix;
+
+use rendust::animation::{Skin, Animation, Channel, Interpolation, Property, MAX_JOINTS};
+use rendust::context::Context;
+use rendust::window::Headless;
+use rendust::gltf::{self, Gltf, GltfNode, Transform};
+use rendust::math::{self, Vec3, Vec4, Mat4, Quat};
+
+fn channel(property: Property, interpolation: Interpolation, times: &[f32], values: &[f32]) -> Channel {
+    Channel {
+        node: 0,
+        property: property,
+        interpolation: interpolation,
+        times: times.to_vec(),
+        values: values.to_vec()
+    }
+}
+
+fn node(transform: Transform) -> GltfNode {
+    GltfNode {
+        name: None,
+        transform: transform,
+        children: Vec::new(),
+        mesh: None,
+        skin: None,
+        camera: None,
+        light: None,
+        weights: Vec::new()
+    }
+}
+
+fn assert_close(a: &[f32], b: &[f32]) {
+    assert_eq!(a.len(), b.len(), "{:?} != {:?}", a, b);
+
+    for i in 0..a.len() {
+        assert!((a[i] - b[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+    }
+}
+
+#[test]
+fn step_and_linear() {
+    let values = [0.0, 0.0, 0.0, 2.0, 4.0, 0.0];
+
+    let step = channel(Property::Translation, Interpolation::Step, &[1.0, 2.0], &values);
+    assert_close(&step.sample(1.5), &[0.0, 0.0, 0.0]);
+    assert_close(&step.sample(2.0), &[2.0, 4.0, 0.0]);
+
+    let linear = channel(Property::Translation, Interpolation::Linear, &[1.0, 2.0], &values);
+    assert_close(&linear.sample(1.25), &[0.5, 1.0, 0.0]);
+
+    // The first and last keyframes hold outside of the animation
+    assert_close(&linear.sample(0.0), &[0.0, 0.0, 0.0]);
+    assert_close(&linear.sample(5.0), &[2.0, 4.0, 0.0]);
+}
+
+#[test]
+fn rotations_take_the_shortest_path() {
+    let half = 0.5f32.sqrt();
+
+    // A quarter turn around Y, given by the opposite quaternion
+    let rotation = channel(Property::Rotation, Interpolation::Linear, &[0.0, 1.0], &[
+        0.0, 0.0, 0.0, 1.0,
+        0.0, -half, 0.0, -half
+    ]);
+
+    // Half way is an eighth of a turn, rather than going the other way around
+    let angle = f32::consts::PI / 8.0;
+    assert_close(&rotation.sample(0.5), &[0.0, angle.sin(), 0.0, angle.cos()]);
+}
+
+#[test]
+fn cubic_spline() {
+    // In-tangent, value, out-tangent for each keyframe
+    let spline = channel(Property::Translation, Interpolation::CubicSpline, &[0.0, 2.0], &[
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0,
+        1.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0
+    ]);
+
+    // Keyframes are interpolated exactly
+    assert_close(&spline.sample(0.0), &[0.0, 0.0, 0.0]);
+    assert_close(&spline.sample(2.0), &[2.0, 0.0, 0.0]);
+
+    // Hermite basis at the middle: (v0 + v1) / 2 + delta * (b0 - a1) / 8
+    assert_close(&spline.sample(1.0), &[1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn morph_weights() {
+    // Two morph targets, swapping their weights
+    let weights = channel(Property::Weights, Interpolation::Linear, &[0.0, 1.0], &[
+        1.0, 0.0,
+        0.0, 1.0
+    ]);
+
+    assert_close(&weights.sample(0.25), &[0.75, 0.25]);
+
+    // Weights do not change the transform of the node
+    let animation = Animation {
+        name: None,
+        channels: vec![weights]
+    };
+
+    let translation = Mat4::from_translation(Vec3::new(5.0, 0.0, 0.0));
+    let mut nodes = vec![node(Transform::Matrix(translation))];
+    animation.apply(0.5, &mut nodes);
+
+    assert_close(&nodes[0].weights, &[0.5, 0.5]);
+    assert_eq!(nodes[0].transform, Transform::Matrix(translation));
+}
+
+#[test]
+fn apply_pose() {
+    let animation = Animation {
+        name: None,
+        channels: vec![
+            channel(Property::Translation, Interpolation::Linear, &[0.0, 1.0], &[0.0, 0.0, 0.0, 0.0, 2.0, 0.0]),
+            channel(Property::Scale, Interpolation::Step, &[0.0, 3.0], &[1.0, 1.0, 1.0, 2.0, 2.0, 2.0])
+        ]
+    };
+
+    assert_eq!(animation.duration(), 3.0);
+
+    // Nodes given by a matrix get a decomposed transform
+    let mut nodes = vec![node(Transform::Matrix(Mat4::from_translation(Vec3::new(5.0, 0.0, 0.0))))];
+    animation.apply(0.5, &mut nodes);
+
+    match nodes[0].transform {
+        Transform::Decomposed { translation, rotation, scale } => {
+            assert_eq!(translation, Vec3::new(0.0, 1.0, 0.0));
+            assert_eq!(rotation, Quat::new(1.0, 0.0, 0.0, 0.0));
+            assert_eq!(scale, Vec3::new(1.0, 1.0, 1.0));
+        },
+        Transform::Matrix(_) => panic!("expected a decomposed transform")
+    }
+}
+
+#[test]
+fn joint_matrices() {
+    let bind = Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0));
+
+    let skin = Skin {
+        name: None,
+        joints: vec![1, 2],
+        inverse_bind_matrices: vec![bind.invert().unwrap(), math::mat4_identity()],
+        skeleton: None
+    };
+
+    // The first joint moved up by one from its bind pose,
+    // the second one is not part of the scene
+    let transforms = vec![
+        Some(math::mat4_identity()),
+        Some(Mat4::from_translation(Vec3::new(0.0, 2.0, 0.0))),
+        None
+    ];
+
+    let matrices = skin.joint_matrices(&transforms, math::mat4_identity());
+    assert_eq!(matrices.len(), 2);
+    assert_eq!(matrices[0], Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0)));
+    assert_eq!(matrices[1], math::mat4_identity());
+
+    // The mesh transform is applied after skinning, so it is removed
+    let mesh = Mat4::from_translation(Vec3::new(3.0, 0.0, 0.0));
+    let matrices = skin.joint_matrices(&transforms, mesh);
+    assert_eq!(matrices[0], Mat4::from_translation(Vec3::new(-3.0, 1.0, 0.0)));
+}
+
+#[test]
+fn skinned_example() {
+    let gltf = Gltf::load("examples/models/arm.gltf").unwrap();
+    let animation = &gltf.animations[0];
+
+    assert_eq!(animation.duration(), 4.0);
+
+    // Half way to the first keyframe, the elbow is bent by 30 degrees around Z
+    let mut nodes = gltf.nodes.clone();
+    animation.apply(0.5, &mut nodes);
+
+    let transforms = gltf::world_transforms(&nodes, &gltf.default_scene());
+    let matrices = gltf.skins[0].joint_matrices(&transforms, transforms[0].unwrap());
+
+    // The top of the arm only follows the elbow
+    let top = matrices[1] * Vec4::new(0.0, 2.0, 0.0, 1.0);
+    let angle = f32::consts::PI / 6.0;
+    assert_close(&[top.x, top.y, top.z, top.w], &[-angle.sin(), 1.0 + angle.cos(), 0.0, 1.0]);
+
+    // The shoulder turns in steps, and has not turned yet
+    let bottom = matrices[0] * Vec4::new(0.0, 0.5, 0.0, 1.0);
+    assert_close(&[bottom.x, bottom.y, bottom.z, bottom.w], &[0.0, 0.5, 0.0, 1.0]);
+}
+
+#[test]
+fn skins_over_the_joint_limit() {
+    // Skipped like the golden image tests when RENDUST_SKIP_GOLDEN is set
+    let _surface = match Headless::new(16, 16) {
+        Ok(surface) => surface,
+        Err(_) if env::var_os("RENDUST_SKIP_GOLDEN").is_some() => return,
+        Err(err) => panic!("no headless context available: {:?}, set RENDUST_SKIP_GOLDEN=1 to skip", err)
+    };
+
+    let ctx = Context::new().unwrap();
+
+    assert!(ctx.set_skin(Some(&vec![math::mat4_identity(); MAX_JOINTS])).is_ok());
+    assert!(ctx.set_skin(Some(&vec![math::mat4_identity(); MAX_JOINTS + 1])).is_err());
+    assert!(ctx.set_skin(None).is_ok());
+}
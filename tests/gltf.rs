@@ -0,0 +1,537 @@
+extern crate rendust;
+
+use rendust::gltf::{Gltf, GltfModel, Transform, Projection, LightKind};
+use rendust::animation::MAX_JOINTS;
+use rendust::mesh::PrimitiveType;
+use rendust::material::AlphaMode;
+use rendust::lighting::Light;
+use rendust::Error;
+
+// Binary data of a triangle: positions, then indices
+fn triangle_buffer() -> Vec<u8> {
+    let mut data = Vec::new();
+
+    for &value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0].iter() {
+        push_u32(&mut data, value.to_bits());
+    }
+
+    for &index in [0u16, 1, 2].iter() {
+        data.push(index as u8);
+        data.push((index >> 8) as u8);
+    }
+
+    // Chunks and buffer views are aligned to 4 bytes
+    data.extend_from_slice(&[0, 0]);
+    data
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+fn base64(data: &[u8]) -> String {
+    let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+
+    for chunk in data.chunks(3) {
+        let bits = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(alphabet[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            }
+            else {
+                text.push('=');
+            }
+        }
+    }
+
+    text
+}
+
+// An asset with a single triangle, whose buffer is described by `buffer`
+fn triangle_json(buffer: &str, extra: &str) -> String {
+    format!(r#"{{
+        "asset": {{ "version": "2.0" }},
+        "buffers": [{}],
+        "bufferViews": [
+            {{ "buffer": 0, "byteLength": 36 }},
+            {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}
+        ],
+        "accessors": [
+            {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+            {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+        ],
+        "meshes": [{{
+            "name": "triangle",
+            "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1 }}]
+        }}]{}
+    }}"#, buffer, extra)
+}
+
+fn embedded_triangle(extra: &str) -> String {
+    let buffer = format!(
+        r#"{{ "byteLength": 44, "uri": "data:application/octet-stream;base64,{}" }}"#,
+        base64(&triangle_buffer())
+    );
+
+    triangle_json(&buffer, extra)
+}
+
+fn gltf_error(source: &str) -> String {
+    match Gltf::from_slice(source.as_bytes(), None) {
+        Err(Error::Gltf(msg)) => msg,
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("malformed asset was accepted")
+    }
+}
+
+#[test]
+fn embedded_buffer() {
+    let gltf = Gltf::from_slice(embedded_triangle("").as_bytes(), None).unwrap();
+
+    assert_eq!(gltf.meshes.len(), 1);
+    assert_eq!(gltf.meshes[0].name, Some("triangle".to_string()));
+
+    let primitive = &gltf.meshes[0].primitives[0];
+    assert_eq!(primitive.primitive, PrimitiveType::Triangles);
+    assert_eq!(primitive.vertices.len(), 3);
+    assert_eq!(primitive.vertices[1].position, [1.0, 0.0, 0.0]);
+    assert_eq!(primitive.material, None);
+
+    // Flat normals are computed when missing
+    for vertex in primitive.vertices.iter() {
+        assert_eq!(vertex.normal, [0.0, 0.0, 1.0]);
+    }
+}
+
+#[test]
+fn binary_container() {
+    let json = triangle_json(r#"{ "byteLength": 44 }"#, "");
+    let mut json = json.into_bytes();
+
+    while json.len() % 4 != 0 {
+        json.push(b' ');
+    }
+
+    let bin = triangle_buffer();
+
+    let mut glb = Vec::new();
+    push_u32(&mut glb, 0x46546C67);
+    push_u32(&mut glb, 2);
+    push_u32(&mut glb, (12 + 8 + json.len() + 8 + bin.len()) as u32);
+    push_u32(&mut glb, json.len() as u32);
+    push_u32(&mut glb, 0x4E4F534A);
+    glb.extend_from_slice(&json);
+    push_u32(&mut glb, bin.len() as u32);
+    push_u32(&mut glb, 0x004E4942);
+    glb.extend_from_slice(&bin);
+
+    let gltf = Gltf::from_slice(&glb, None).unwrap();
+    assert_eq!(gltf.meshes[0].primitives[0].vertices[2].position, [0.0, 1.0, 0.0]);
+
+    // Truncated containers are rejected
+    glb.truncate(glb.len() - 8);
+    assert!(Gltf::from_slice(&glb, None).is_err());
+}
+
+#[test]
+fn node_hierarchy() {
+    let gltf = Gltf::from_slice(embedded_triangle(r#",
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [
+            { "name": "root", "translation": [1, 0, 0], "children": [1] },
+            { "mesh": 0, "scale": [2, 2, 2], "rotation": [0, 0.7071068, 0, 0.7071068] },
+            { "mesh": 0 }
+        ]
+    "#).as_bytes(), None).unwrap();
+
+    assert_eq!(gltf.default_scene(), vec![0]);
+    assert_eq!(gltf.nodes[0].name, Some("root".to_string()));
+
+    match gltf.nodes[1].transform {
+        Transform::Decomposed { scale, .. } => assert_eq!(scale.x, 2.0),
+        Transform::Matrix(_) => panic!("expected a decomposed transform")
+    }
+
+    // Nodes outside of the scene are not placed
+    let transforms = gltf.world_transforms(&gltf.default_scene());
+    assert!(transforms[2].is_none());
+
+    // The child is rotated a quarter turn around Y, scaled, then moved
+    let world = transforms[1].unwrap();
+    let x = world.x;
+    let t = world.w;
+    assert!(x.x.abs() < 1e-5 && (x.z + 2.0).abs() < 1e-5);
+    assert_eq!((t.x, t.y, t.z), (1.0, 0.0, 0.0));
+}
+
+#[test]
+fn invalid_hierarchy() {
+    let cycle = embedded_triangle(r#",
+        "nodes": [{ "children": [1] }, { "children": [0] }]
+    "#);
+    assert!(gltf_error(&cycle).contains("ancestor"));
+
+    let shared = embedded_triangle(r#",
+        "nodes": [{ "children": [2] }, { "children": [2] }, {}]
+    "#);
+    assert!(gltf_error(&shared).contains("several parents"));
+
+    let missing = embedded_triangle(r#",
+        "nodes": [{ "mesh": 3 }]
+    "#);
+    assert!(gltf_error(&missing).contains("mesh 3"));
+}
+
+#[test]
+fn materials() {
+    let gltf = Gltf::from_slice(embedded_triangle(r#",
+        "materials": [{
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [1, 0.5, 0, 1],
+                "metallicFactor": 0.25
+            },
+            "emissiveFactor": [0, 0, 1],
+            "alphaMode": "MASK",
+            "alphaCutoff": 0.25,
+            "doubleSided": true
+        }]
+    "#).as_bytes(), None).unwrap();
+
+    let material = &gltf.materials[0];
+    assert_eq!(material.base_color, [1.0, 0.5, 0.0, 1.0]);
+    assert_eq!(material.metallic, 0.25);
+    assert_eq!(material.roughness, 1.0);
+    assert_eq!(material.emissive, [0.0, 0.0, 1.0]);
+    assert_eq!(material.alpha_mode, AlphaMode::Mask(0.25));
+    assert!(material.double_sided);
+    assert_eq!(material.base_color_texture, None);
+
+    let material = material.to_material(&[]);
+    assert_eq!(material.base_color, [1.0, 0.5, 0.0, 1.0]);
+}
+
+#[test]
+fn cameras_and_lights() {
+    let gltf = Gltf::from_slice(embedded_triangle(r#",
+        "cameras": [
+            { "type": "perspective", "perspective": { "yfov": 0.8, "znear": 0.1 } },
+            { "type": "orthographic", "orthographic": { "xmag": 2, "ymag": 1, "znear": 0.1, "zfar": 10 } }
+        ],
+        "extensions": {
+            "KHR_lights_punctual": {
+                "lights": [
+                    { "type": "point", "color": [1, 0, 0], "intensity": 20 },
+                    { "type": "spot", "spot": { "outerConeAngle": 0.5 } }
+                ]
+            }
+        },
+        "nodes": [
+            { "camera": 0, "translation": [0, 1, 5] },
+            { "translation": [0, 3, 0], "extensions": { "KHR_lights_punctual": { "light": 0 } } },
+            { "extensions": { "KHR_lights_punctual": { "light": 1 } } }
+        ]
+    "#).as_bytes(), None).unwrap();
+
+    assert_eq!(gltf.cameras[0].projection, Projection::Perspective {
+        yfov: 0.8,
+        aspect_ratio: None,
+        znear: 0.1,
+        zfar: None
+    });
+
+    // Infinite perspective: the far plane goes to -1
+    let projection = gltf.cameras[0].projection_matrix(2.0);
+    assert_eq!(projection.z.z, -1.0);
+
+    let cameras = gltf.scene_cameras();
+    assert_eq!(cameras.len(), 1);
+    assert_eq!(cameras[0].1.position.z, 5.0);
+
+    assert_eq!(gltf.lights[1].kind, LightKind::Spot { inner_cone: 0.0, outer_cone: 0.5 });
+
+    let lights = gltf.scene_lights();
+    assert_eq!(lights.len(), 2);
+
+    match lights[0] {
+        Light::Point(ref point) => {
+            assert_eq!(point.position.y, 3.0);
+            assert_eq!(point.color, [1.0, 0.0, 0.0]);
+            assert_eq!(point.strength, 20.0);
+        },
+        _ => panic!("expected a point light")
+    }
+
+    match lights[1] {
+        Light::Spot(ref spot) => assert_eq!(spot.direction.z, -1.0),
+        _ => panic!("expected a spot light")
+    }
+}
+
+#[test]
+fn primitive_modes_and_attributes() {
+    // Four positions, then colors as normalized bytes
+    let mut data = Vec::new();
+
+    for &value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0].iter() {
+        push_u32(&mut data, value.to_bits());
+    }
+
+    data.extend_from_slice(&[255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 0]);
+
+    let source = format!(r#"{{
+        "buffers": [{{ "byteLength": 64, "uri": "data:application/octet-stream;base64,{}" }}],
+        "bufferViews": [
+            {{ "buffer": 0, "byteLength": 48 }},
+            {{ "buffer": 0, "byteOffset": 48, "byteLength": 16 }}
+        ],
+        "accessors": [
+            {{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3" }},
+            {{ "bufferView": 1, "componentType": 5121, "normalized": true, "count": 4, "type": "VEC4" }}
+        ],
+        "meshes": [{{ "primitives": [
+            {{ "attributes": {{ "POSITION": 0, "COLOR_0": 1 }}, "mode": 6 }},
+            {{ "attributes": {{ "POSITION": 0 }}, "mode": 2 }}
+        ] }}]
+    }}"#, base64(&data));
+
+    let gltf = Gltf::from_slice(source.as_bytes(), None).unwrap();
+
+    // A fan of two triangles, unwelded for flat normals
+    let fan = &gltf.meshes[0].primitives[0];
+    assert_eq!(fan.primitive, PrimitiveType::Triangles);
+    assert_eq!(fan.indices.len(), 6);
+    assert_eq!(fan.vertices[4].color, [0.0, 0.0, 1.0, 1.0]);
+    assert_eq!(fan.vertices[5].color, [1.0, 1.0, 1.0, 0.0]);
+
+    // A loop of four lines
+    let lines = &gltf.meshes[0].primitives[1];
+    assert_eq!(lines.primitive, PrimitiveType::Lines);
+    assert_eq!(lines.indices, vec![0, 1, 1, 2, 2, 3, 3, 0]);
+}
+
+#[test]
+fn malformed_assets() {
+    match Gltf::from_slice(b"{ not json", None) {
+        Err(Error::Json(_)) => (),
+        _ => panic!("expected a JSON error")
+    }
+
+    // Buffer shorter than declared
+    let short = triangle_json(r#"{ "byteLength": 100, "uri": "data:application/octet-stream;base64,AAAA" }"#, "");
+    assert!(gltf_error(&short).contains("buffer 0"));
+
+    // Accessor reading past its buffer view
+    let overflow = embedded_triangle("").replace(r#""count": 3, "type": "VEC3""#, r#""count": 4, "type": "VEC3""#);
+    assert!(gltf_error(&overflow).contains("accessor 0"));
+
+    // Counts whose size overflows are rejected before anything is allocated
+    let huge = embedded_triangle("").replace(r#""count": 3, "type": "VEC3""#, r#""count": 18446744073709551615, "type": "VEC3""#);
+    assert!(gltf_error(&huge).contains("exceeds its buffer view"));
+
+    let huge = embedded_triangle("").replace(r#""bufferView": 0, "componentType": 5126, "count": 3"#, r#""componentType": 5126, "count": 9223372036854775807"#);
+    assert!(gltf_error(&huge).contains("too many elements"));
+
+    // Overlapping elements
+    let stride = embedded_triangle("").replace(r#""byteLength": 36 }"#, r#""byteLength": 36, "byteStride": 4 }"#);
+    assert!(gltf_error(&stride).contains("byte stride"));
+
+    // External buffer without a directory to search it in
+    let external = triangle_json(r#"{ "byteLength": 44, "uri": "triangle.bin" }"#, "");
+    assert!(gltf_error(&external).contains("external"));
+}
+
+#[test]
+fn example_scene() {
+    let gltf = Gltf::load("examples/models/cubes.gltf").unwrap();
+
+    assert_eq!(gltf.default_scene(), vec![0, 3, 4]);
+    assert_eq!(gltf.scene_cameras().len(), 1);
+    assert_eq!(gltf.scene_lights().len(), 1);
+
+    // The top cube sits on the base, through the scale of its parent
+    let transforms = gltf.world_transforms(&gltf.default_scene());
+    let top = transforms[2].unwrap().w;
+    assert!((top.y - 0.625).abs() < 1e-4);
+}
+
+#[test]
+fn skin_and_animation() {
+    let mut data = triangle_buffer();
+
+    // Joints as bytes, then weights
+    data.extend_from_slice(&[0, 1, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0]);
+
+    for &value in [1.0f32, 0.0, 0.0, 0.0, 0.5, 0.5, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0].iter() {
+        push_u32(&mut data, value.to_bits());
+    }
+
+    // Keyframe times, then translations
+    for &value in [0.0f32, 1.0, 0.0, 0.0, 0.0, 0.0, 3.0, 0.0].iter() {
+        push_u32(&mut data, value.to_bits());
+    }
+
+    let source = format!(r#"{{
+        "buffers": [{{ "byteLength": {}, "uri": "data:application/octet-stream;base64,{}" }}],
+        "bufferViews": [
+            {{ "buffer": 0, "byteLength": 36 }},
+            {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }},
+            {{ "buffer": 0, "byteOffset": 44, "byteLength": 12 }},
+            {{ "buffer": 0, "byteOffset": 56, "byteLength": 48 }},
+            {{ "buffer": 0, "byteOffset": 104, "byteLength": 8 }},
+            {{ "buffer": 0, "byteOffset": 112, "byteLength": 24 }}
+        ],
+        "accessors": [
+            {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+            {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+            {{ "bufferView": 2, "componentType": 5121, "count": 3, "type": "VEC4" }},
+            {{ "bufferView": 3, "componentType": 5126, "count": 3, "type": "VEC4" }},
+            {{ "bufferView": 4, "componentType": 5126, "count": 2, "type": "SCALAR" }},
+            {{ "bufferView": 5, "componentType": 5126, "count": 2, "type": "VEC3" }}
+        ],
+        "meshes": [{{ "primitives": [{{
+            "attributes": {{ "POSITION": 0, "JOINTS_0": 2, "WEIGHTS_0": 3 }},
+            "indices": 1
+        }}] }}],
+        "skins": [{{ "joints": [1, 2] }}],
+        "animations": [{{
+            "samplers": [{{ "input": 4, "output": 5 }}],
+            "channels": [
+                {{ "sampler": 0, "target": {{ "node": 2, "path": "translation" }} }},
+                {{ "sampler": 0, "target": {{ "path": "translation" }} }}
+            ]
+        }}],
+        "nodes": [
+            {{ "mesh": 0, "skin": 0 }},
+            {{ "children": [2] }},
+            {{ "translation": [0, 1, 0] }}
+        ]
+    }}"#, data.len(), base64(&data));
+
+    let gltf = Gltf::from_slice(source.as_bytes(), None).unwrap();
+
+    // Joints and weights follow the flat shaded vertices
+    let primitive = &gltf.meshes[0].primitives[0];
+    assert_eq!(primitive.joints, vec![[0, 1, 0, 0], [0, 1, 0, 0], [1, 0, 0, 0]]);
+    assert_eq!(primitive.weights[1], [0.5, 0.5, 0.0, 0.0]);
+
+    assert_eq!(gltf.nodes[0].skin, Some(0));
+    assert_eq!(gltf.skins[0].joints, vec![1, 2]);
+    assert_eq!(gltf.skins[0].inverse_bind_matrices.len(), 2);
+
+    // Channels without a node are skipped
+    let animation = &gltf.animations[0];
+    assert_eq!(animation.channels.len(), 1);
+    assert_eq!(animation.duration(), 1.0);
+
+    let mut nodes = gltf.nodes.clone();
+    animation.apply(0.5, &mut nodes);
+
+    match nodes[2].transform {
+        Transform::Decomposed { translation, .. } => assert_eq!(translation.y, 1.5),
+        Transform::Matrix(_) => panic!("expected a decomposed transform")
+    }
+
+    // Joints must reference nodes
+    let invalid = source.replace(r#""joints": [1, 2]"#, r#""joints": [1, 5]"#);
+    assert!(gltf_error(&invalid).contains("joint 5"));
+
+    // Vertices must use the joints of the skin
+    let invalid = source.replace(r#""joints": [1, 2]"#, r#""joints": [1]"#);
+    assert!(gltf_error(&invalid).contains("node 0 has a mesh using joints missing from its skin"));
+
+    // The shaders deform meshes by a limited number of joints
+    let joints: Vec<String> = (0..MAX_JOINTS + 1).map(|_| "1".to_string()).collect();
+    let invalid = source.replace(r#""joints": [1, 2]"#, &format!(r#""joints": [{}]"#, joints.join(", ")));
+    assert!(gltf_error(&invalid).contains(&format!("skin 0 has more than {} joints", MAX_JOINTS)));
+
+    // Skins edited after loading are checked before uploading anything
+    let mut edited = Gltf::from_slice(source.as_bytes(), None).unwrap();
+    edited.skins[0].joints = vec![1; MAX_JOINTS + 1];
+
+    match GltfModel::new(&edited) {
+        Err(Error::Gltf(msg)) => assert!(msg.contains("skin 0")),
+        _ => panic!("expected a skin error")
+    }
+}
+
+#[test]
+fn morph_targets() {
+    let mut data = triangle_buffer();
+
+    // Position offsets, keyframe times, then weights of both targets
+    for &value in [0.0f32, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0].iter() {
+        push_u32(&mut data, value.to_bits());
+    }
+
+    for &value in [0.0f32, 2.0, 0.0, 0.0, 1.0, 0.5].iter() {
+        push_u32(&mut data, value.to_bits());
+    }
+
+    let source = format!(r#"{{
+        "buffers": [{{ "byteLength": {}, "uri": "data:application/octet-stream;base64,{}" }}],
+        "bufferViews": [
+            {{ "buffer": 0, "byteLength": 36 }},
+            {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }},
+            {{ "buffer": 0, "byteOffset": 44, "byteLength": 36 }},
+            {{ "buffer": 0, "byteOffset": 80, "byteLength": 8 }},
+            {{ "buffer": 0, "byteOffset": 88, "byteLength": 16 }}
+        ],
+        "accessors": [
+            {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+            {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+            {{ "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC3" }},
+            {{ "bufferView": 3, "componentType": 5126, "count": 2, "type": "SCALAR" }},
+            {{ "bufferView": 4, "componentType": 5126, "count": 4, "type": "SCALAR" }}
+        ],
+        "meshes": [{{
+            "primitives": [{{
+                "attributes": {{ "POSITION": 0 }},
+                "indices": 1,
+                "targets": [{{ "POSITION": 2 }}, {{}}]
+            }}],
+            "weights": [0.5, 0]
+        }}],
+        "animations": [{{
+            "samplers": [{{ "input": 3, "output": 4 }}],
+            "channels": [{{ "sampler": 0, "target": {{ "node": 0, "path": "weights" }} }}]
+        }}],
+        "nodes": [
+            {{ "mesh": 0 }},
+            {{ "mesh": 0, "weights": [1, 1] }}
+        ]
+    }}"#, data.len(), base64(&data));
+
+    let gltf = Gltf::from_slice(source.as_bytes(), None).unwrap();
+
+    // Targets without positions do not move the vertices
+    let targets = &gltf.meshes[0].primitives[0].targets;
+    assert_eq!(targets.len(), 2);
+    assert_eq!(targets[0].positions[1], [0.0, 1.0, 0.0]);
+    assert_eq!(targets[1].positions[1], [0.0, 0.0, 0.0]);
+    assert!(targets[0].normals.is_empty());
+
+    // Nodes use the weights of their mesh, unless they have their own
+    assert_eq!(gltf.nodes[0].weights, vec![0.5, 0.0]);
+    assert_eq!(gltf.nodes[1].weights, vec![1.0, 1.0]);
+
+    let mut nodes = gltf.nodes.clone();
+    gltf.animations[0].apply(1.0, &mut nodes);
+    assert_eq!(nodes[0].weights, vec![0.5, 0.25]);
+
+    // Weights must match the number of targets
+    let invalid = source.replace(r#""weights": [1, 1]"#, r#""weights": [1]"#);
+    assert!(gltf_error(&invalid).contains("node 1"));
+
+    let invalid = source.replace(r#""count": 4"#, r#""count": 2"#);
+    assert!(gltf_error(&invalid).contains("animation 0"));
+
+    // Primitives edited after loading fail to upload instead of panicking
+    let mut edited = Gltf::from_slice(source.as_bytes(), None).unwrap();
+    edited.meshes[0].primitives[0].targets[0].positions.pop();
+    assert!(edited.meshes[0].primitives[0].to_mesh().is_err());
+}
@@ -0,0 +1,811 @@
+//! Golden image regression tests
+//!
+//! Each scene is rendered into a headless surface, then compared against
+//! the reference image stored in `tests/reference/<scene>.png`. On
+//! failure, the rendered frame and a diff image are written to the
+//! target directory.
+//!
+//! When the `RENDUST_BLESS` environment variable is set, the rendered
+//! frames are saved as the new references instead of being compared.
+//! Otherwise a missing reference image fails its test.
+//!
+//! The tests fail when no headless context can be created, unless the
+//! `RENDUST_SKIP_GOLDEN` environment variable is set to skip them.
+//!
+//! The references were rendered by Mesa 22.3.6 llvmpipe (LLVM 15.0.6,
+//! 256 bits), OpenGL 4.5 compatibility profile. Other drivers may
+//! differ beyond the tolerance, in which case the references should be
+//! compared and blessed again with the same renderer.
+
+extern crate rendust;
+extern crate imagefmt;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+
+use rendust::Context;
+use rendust::math::{self, Vec3, Mat4};
+use rendust::window::Headless;
+use rendust::mesh::{self, Vertex, Texture, TextureOptions, Wrap, Filter, CubeMap, PrimitiveType, Mesh, MorphMode};
+use rendust::camera::Camera;
+use rendust::image::{Image, HdrImage};
+use rendust::lighting::{Ambient, Directional, Point, Spot, Shadow};
+use rendust::material::Material;
+use rendust::environment::Environment;
+use rendust::skybox::Skybox;
+use rendust::obj::Obj;
+use rendust::gltf::{Gltf, GltfModel};
+use rendust::target::{RenderTarget, DepthBuffer};
+use rendust::postprocess::{self, PostProcess, Effect, ToneMapping};
+
+// Size of the rendered frames
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 192;
+
+// Maximum difference allowed on each channel of a pixel
+const TOLERANCE: u8 = 3;
+
+// Maximum number of pixels allowed to exceed the tolerance,
+// to account for rasterization differences along edges
+const MAX_BAD_PIXELS: usize = 16;
+
+/// Every scene of the test suite
+const SCENES: &'static [(&'static str, fn(&Context))] = &[
+    ("triangle", triangle),
+    ("texture", texture),
+    ("scene", scene),
+    ("lighting", lighting),
+    ("shadows", shadows),
+    ("cascades", cascades),
+    ("point_shadows", point_shadows),
+    ("render_target", render_target),
+    ("postprocess", postprocess),
+    ("hdr", hdr),
+    ("pbr", pbr),
+    ("ibl", ibl),
+    ("skybox", skybox),
+    ("texture_options", texture_options),
+    ("obj", obj),
+    ("gltf", gltf),
+    ("skinning", skinning),
+    ("morph", morph),
+    ("shapes", shapes),
+];
+
+fn main() {
+    let surface = match Headless::new(WIDTH, HEIGHT) {
+        Ok(surface) => surface,
+        Err(err) if env::var_os("RENDUST_SKIP_GOLDEN").is_some() => {
+            println!("skipping golden image tests, no headless context available: {:?}", err);
+            return;
+        },
+        Err(err) => {
+            println!("no headless context available for the golden image tests: {:?}", err);
+            println!("set RENDUST_SKIP_GOLDEN=1 to skip them");
+            process::exit(1);
+        }
+    };
+
+    let bless = env::var_os("RENDUST_BLESS").is_some();
+
+    if bless {
+        fs::create_dir_all(reference_dir()).unwrap();
+    }
+    let mut failures = 0;
+
+    println!("\nrunning {} golden image tests", SCENES.len());
+
+    for &(name, render) in SCENES {
+        let ctx = Context::new().unwrap();
+
+        rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+        rendust::clear();
+
+        render(&ctx);
+
+        let frame = surface.capture_frame();
+        let reference = reference_dir().join(format!("{}.png", name));
+
+        if bless {
+            frame.save(&reference).unwrap();
+            println!("test {} ... blessed", name);
+            continue;
+        }
+
+        let result = if reference.exists() {
+            compare(name, &frame, &reference)
+        }
+        else {
+            Err(format!("missing reference {}, run with RENDUST_BLESS=1 to create it", reference.display()))
+        };
+
+        match result {
+            Ok(()) => println!("test {} ... ok", name),
+            Err(msg) => {
+                println!("test {} ... FAILED\n    {}", name, msg);
+                failures += 1;
+            }
+        }
+    }
+
+    println!("\ntest result: {} passed, {} failed\n", SCENES.len() - failures, failures);
+
+    if failures > 0 {
+        process::exit(1);
+    }
+}
+
+// Directory holding the reference images
+fn reference_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("reference")
+}
+
+// Directory where failed frames and diff images are written
+fn output_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden")
+}
+
+// Compare a rendered frame to its reference image, writing
+// the frame and a diff image on mismatch
+fn compare(name: &str, frame: &Image, reference: &Path) -> Result<(), String> {
+    let expected = try!(imagefmt::read(reference, imagefmt::ColFmt::RGBA)
+        .map_err(|err| format!("failed to read {}: {:?}", reference.display(), err)));
+
+    if expected.w as u32 != frame.width || expected.h as u32 != frame.height {
+        return Err(format!("size mismatch: expected {}x{}, got {}x{}", expected.w, expected.h, frame.width, frame.height));
+    }
+
+    // Highlight the pixels exceeding the tolerance in red
+    let mut diff = Vec::with_capacity(frame.data.len());
+    let mut bad = 0;
+
+    for (got, want) in frame.data.chunks(4).zip(expected.buf.chunks(4)) {
+        let delta = got.iter().zip(want.iter())
+            .map(|(&a, &b)| (a as i32 - b as i32).abs() as u8)
+            .max()
+            .unwrap_or(0);
+
+        if delta > TOLERANCE {
+            bad += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        }
+        else {
+            // Dimmed version of the expected pixel
+            diff.extend_from_slice(&[want[0] / 4, want[1] / 4, want[2] / 4, 255]);
+        }
+    }
+
+    if bad <= MAX_BAD_PIXELS {
+        return Ok(());
+    }
+
+    let out = output_dir();
+    std::fs::create_dir_all(&out).unwrap();
+
+    let actual = out.join(format!("{}.png", name));
+    let diff_path = out.join(format!("{}-diff.png", name));
+
+    frame.save(&actual).unwrap();
+    Image::new(frame.width, frame.height, diff).save(&diff_path).unwrap();
+
+    Err(format!("{} pixels differ from the reference, see {} and {}", bad, actual.display(), diff_path.display()))
+}
+
+// Same as the triangle example
+fn triangle(_: &Context) {
+    let triangle = Mesh::new(PrimitiveType::Triangles, &[
+        Vertex::new(-1.0, -1.0, 0.0).color(1.0, 0.0, 0.0, 1.0),
+        Vertex::new( 0.0,  1.0, 0.0).color(0.0, 1.0, 0.0, 1.0),
+        Vertex::new( 1.0, -1.0, 0.0).color(0.0, 0.0, 1.0, 1.0),
+    ], None);
+
+    triangle.render();
+}
+
+// Same as the texture example
+fn texture(_: &Context) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples").join("image.png");
+    let image = imagefmt::read(path, imagefmt::ColFmt::RGBA).unwrap();
+    let texture = Texture::new(image.w as u32, image.h as u32, image.buf.as_ref());
+
+    let triangle = Mesh::new(PrimitiveType::Triangles, &[
+        Vertex::new(-1.0, -1.0, 0.0).texcoords(0.0, 0.0),
+        Vertex::new( 0.0,  1.0, 0.0).texcoords(0.5, 1.0),
+        Vertex::new( 1.0, -1.0, 0.0).texcoords(1.0, 0.0),
+    ], None);
+
+    texture.bind();
+    triangle.render();
+    texture.unbind();
+}
+
+// Same as the scene example: a cube on a floor, with ambient light
+fn scene(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(90.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([0.1, 0.1, 0.1, 1.0], 0.5));
+
+    let camera = Camera::new(Vec3::new(1.0, 1.0, 3.0));
+    ctx.set_view(camera.view_matrix());
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, 0.0,  25.0).color(0.4, 0.4, 0.4, 1.0),
+        Vertex::new(-25.0, 0.0, -25.0).color(0.4, 0.4, 0.4, 1.0),
+        Vertex::new( 25.0, 0.0, -25.0).color(0.4, 0.4, 0.4, 1.0),
+        Vertex::new( 25.0, 0.0,  25.0).color(0.4, 0.4, 0.4, 1.0),
+    ], None);
+
+    let cube = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-0.5, -0.5, 0.5).color(1.0, 1.0, 1.0, 1.0),
+        Vertex::new(-0.5,  0.5, 0.5).color(1.0, 1.0, 1.0, 1.0),
+        Vertex::new( 0.5,  0.5, 0.5).color(1.0, 1.0, 1.0, 1.0),
+        Vertex::new( 0.5, -0.5, 0.5).color(1.0, 1.0, 1.0, 1.0),
+
+        Vertex::new(-0.5, -0.5, -0.5).color(1.0, 1.0, 1.0, 1.0),
+        Vertex::new(-0.5,  0.5, -0.5).color(1.0, 1.0, 1.0, 1.0),
+        Vertex::new( 0.5,  0.5, -0.5).color(1.0, 1.0, 1.0, 1.0),
+        Vertex::new( 0.5, -0.5, -0.5).color(1.0, 1.0, 1.0, 1.0),
+    ], Some(&[
+        0, 1, 2, 3,
+        4, 5, 6, 7,
+
+        0, 1, 5, 4,
+        3, 2, 6, 7,
+
+        0, 4, 7, 3,
+        1, 5, 6, 2
+    ]));
+
+    floor.render();
+    cube.render();
+}
+
+// Build a unit cube with one normal per face
+fn lit_cube() -> Mesh {
+    let faces = [
+        ([ 0.0,  0.0,  1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([ 0.0,  0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([ 1.0,  0.0,  0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0,  0.0,  0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([ 0.0,  1.0,  0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([ 0.0, -1.0,  0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ];
+
+    let mut vertices = Vec::new();
+
+    for &(n, u, v) in faces.iter() {
+        for &(a, b) in [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)].iter() {
+            vertices.push(Vertex::new(
+                n[0] * 0.5 + u[0] * a + v[0] * b,
+                n[1] * 0.5 + u[1] * a + v[1] * b,
+                n[2] * 0.5 + u[2] * a + v[2] * b
+            ).normal(n[0], n[1], n[2]).color(0.9, 0.6, 0.3, 1.0));
+        }
+    }
+
+    Mesh::new(PrimitiveType::Quads, &vertices, None)
+}
+
+// Same as the lighting example: every kind of light on a cube
+fn lighting(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(90.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.3, -1.0, -0.5), [1.0, 1.0, 0.9], 0.6).into(),
+        Point::new(Vec3::new(-1.5, 1.0, 1.0), [0.2, 0.4, 1.0], 1.0).into(),
+        Spot::new(Vec3::new(1.5, 2.0, 1.5), Vec3::new(-1.0, -1.5, -1.0), [1.0, 0.2, 0.2], 2.0).cone(15.0, 25.0).into(),
+    ]);
+
+    ctx.set_camera(&Camera::new(Vec3::new(1.0, 1.0, 3.0)));
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, -0.5,  25.0).color(0.4, 0.4, 0.4, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-25.0, -0.5, -25.0).color(0.4, 0.4, 0.4, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, -0.5, -25.0).color(0.4, 0.4, 0.4, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, -0.5,  25.0).color(0.4, 0.4, 0.4, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    floor.render();
+    lit_cube().render();
+}
+
+// Same as the shadows example: cubes casting shadows on a floor
+fn shadows(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(90.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.2));
+
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.5, -1.0, -0.3), [1.0, 1.0, 0.9], 0.6)
+            .shadow(Shadow::new().distance(10.0).resolution(2048))
+            .into(),
+        Spot::new(Vec3::new(2.0, 3.0, 2.0), Vec3::new(-1.0, -1.5, -1.0), [0.3, 0.5, 1.0], 2.0)
+            .cone(20.0, 30.0)
+            .shadow(Shadow::new().distance(20.0).bias(0.0005))
+            .into(),
+    ]);
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    let cube = lit_cube();
+
+    let render = |ctx: &Context| {
+        ctx.set_model(math::mat4_identity());
+        floor.render();
+
+        ctx.set_model(Mat4::from_translation(Vec3::new(0.0, 0.5, 0.0)));
+        cube.render();
+
+        ctx.set_model(Mat4::from_translation(Vec3::new(-1.5, 1.5, -1.0)) * Mat4::from_scale(0.5));
+        cube.render();
+    };
+
+    ctx.render_shadows(&render);
+
+    rendust::clear();
+
+    ctx.set_camera(&Camera::new(Vec3::new(1.0, 2.0, 4.0)));
+    render(ctx);
+}
+
+// A directional light with cascaded shadows over a large floor,
+// with cubes spread along the view of the camera
+fn cascades(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(90.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.2));
+
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.5, -1.0, -0.3), [1.0, 1.0, 0.9], 0.8)
+            .shadow(Shadow::new().distance(50.0).cascades(3))
+            .into(),
+    ]);
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    let cube = lit_cube();
+
+    let render = |ctx: &Context| {
+        ctx.set_model(math::mat4_identity());
+        floor.render();
+
+        for i in 0..10 {
+            let x = (i % 2) as f32 * 4.0 - 2.0;
+            let z = -(i / 2) as f32 * 5.0;
+
+            ctx.set_model(Mat4::from_translation(Vec3::new(x, 0.5, z)));
+            cube.render();
+        }
+    };
+
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 2.0, 4.0)));
+    ctx.render_shadows(&render);
+
+    rendust::clear();
+    render(ctx);
+}
+
+// A point light between cubes, casting shadows in every direction
+fn point_shadows(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(90.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+
+    ctx.set_lights(&[
+        Point::new(Vec3::new(0.0, 1.0, 0.0), [1.0, 0.9, 0.8], 2.0)
+            .shadow(Shadow::new().distance(20.0).resolution(512).bias(0.002))
+            .into(),
+    ]);
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    let cube = lit_cube();
+
+    let render = |ctx: &Context| {
+        ctx.set_model(math::mat4_identity());
+        floor.render();
+
+        for &(x, z) in [(2.0, 0.0), (-2.0, 0.0), (0.0, -2.0), (1.5, 1.5)].iter() {
+            ctx.set_model(Mat4::from_translation(Vec3::new(x, 0.5, z)));
+            cube.render();
+        }
+    };
+
+    ctx.render_shadows(&render);
+
+    rendust::clear();
+
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 4.0, 5.0)));
+    render(ctx);
+}
+
+// Same as the render target example: a triangle rendered
+// offscreen, then displayed on a quad
+fn render_target(_: &Context) {
+    let target = RenderTarget::new(128, 128, 1, DepthBuffer::Depth).unwrap();
+
+    let triangle = Mesh::new(PrimitiveType::Triangles, &[
+        Vertex::new(-1.0, -1.0, 0.0).color(1.0, 0.0, 0.0, 1.0),
+        Vertex::new( 0.0,  1.0, 0.0).color(0.0, 1.0, 0.0, 1.0),
+        Vertex::new( 1.0, -1.0, 0.0).color(0.0, 0.0, 1.0, 1.0),
+    ], None);
+
+    let quad = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-0.5, -0.5, 0.0).texcoords(0.0, 0.0),
+        Vertex::new( 0.5, -0.5, 0.0).texcoords(1.0, 0.0),
+        Vertex::new( 0.5,  0.5, 0.0).texcoords(1.0, 1.0),
+        Vertex::new(-0.5,  0.5, 0.0).texcoords(0.0, 1.0),
+    ], None);
+
+    target.render(|| {
+        rendust::set_clear_color(0.2, 0.2, 0.2, 1.0);
+        rendust::clear();
+
+        triangle.render();
+    });
+
+    rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+    rendust::clear();
+
+    let texture = target.color(0);
+
+    texture.bind();
+    quad.render();
+    texture.unbind();
+}
+
+// The lighting scene through every built-in effect, with
+// a neutral color grading that should not change anything
+fn postprocess(ctx: &Context) {
+    let mut post = PostProcess::new(WIDTH, HEIGHT).unwrap();
+    post.push(Effect::bloom(0.8, 0.6).unwrap());
+    post.push(Effect::tone_mapping(ToneMapping::Reinhard, 1.5).unwrap());
+    post.push(Effect::color_grading(&postprocess::neutral_lut(16), 1.0).unwrap());
+    post.push(Effect::vignette(0.5, 0.8).unwrap());
+    post.push(Effect::gamma(1.2).unwrap());
+    post.push(Effect::fxaa().unwrap());
+
+    post.render(|| {
+        rendust::clear();
+        lighting(ctx);
+    });
+}
+
+// Lights brighter than white, rendered into a floating
+// point target and tone mapped
+fn hdr(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(90.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.05));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 2.0, 4.0)));
+
+    ctx.set_lights(&[
+        Point::new(Vec3::new(-2.0, 1.0, 0.0), [1.0, 0.5, 0.2], 20.0).into(),
+        Point::new(Vec3::new( 2.0, 1.0, 0.0), [0.2, 0.5, 1.0], 5.0).into(),
+    ]);
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0, -25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 25.0, 0.0,  25.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    let post = PostProcess::hdr(WIDTH, HEIGHT, ToneMapping::Aces, 1.0).unwrap();
+
+    post.render(|| {
+        rendust::clear();
+        floor.render();
+    });
+}
+
+// Cubes with physically based materials of increasing
+// roughness, dielectric on the bottom row and metallic on top
+fn pbr(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(60.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.03));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 0.0, 6.0)));
+
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.3, -0.5, -1.0), [1.0, 1.0, 1.0], 2.0).into(),
+        Point::new(Vec3::new(2.0, 2.0, 3.0), [1.0, 0.8, 0.6], 20.0).into(),
+    ]);
+
+    let post = PostProcess::hdr(WIDTH, HEIGHT, ToneMapping::Aces, 1.0).unwrap();
+    post.tone_mapping().unwrap().set_gamma(2.2).unwrap();
+
+    let cube = lit_cube();
+
+    post.render(|| {
+        rendust::clear();
+
+        for row in 0..2 {
+            for col in 0..4 {
+                ctx.set_material(&Material::new()
+                    .base_color(0.8, 0.1, 0.1, 1.0)
+                    .metallic_roughness(row as f32, (col as f32 + 1.0) / 4.0));
+
+                ctx.set_model(
+                    Mat4::from_translation(Vec3::new((col as f32 - 1.5) * 1.5, (row as f32 - 0.5) * 1.5, 0.0)) *
+                    Mat4::from_angle_y(math::Deg(30.0)) * Mat4::from_angle_x(math::Deg(20.0))
+                );
+                cube.render();
+            }
+        }
+
+        ctx.clear_material();
+    });
+}
+
+// Equirectangular sky: a blue gradient above a brown ground,
+// with a small and very bright sun
+fn sky() -> HdrImage {
+    let (width, height) = (64, 32);
+    let mut data = Vec::with_capacity((width * height * 3) as usize);
+
+    for y in 0..height {
+        for x in 0..width {
+            let elevation = 0.5 - (y as f32 + 0.5) / height as f32;
+
+            let color = if (x as i32 - 40).abs() <= 1 && (y as i32 - 8).abs() <= 1 {
+                [50.0, 45.0, 40.0]
+            }
+            else if elevation > 0.0 {
+                [0.3 + 0.4 * elevation, 0.5 + 0.5 * elevation, 1.0]
+            }
+            else {
+                [0.3, 0.2, 0.1]
+            };
+
+            data.extend_from_slice(&color);
+        }
+    }
+
+    HdrImage::new(width, height, data)
+}
+
+fn ibl(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(60.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 0.0, 6.0)));
+
+    let environment = Environment::with_size(&sky(), 64).unwrap();
+    ctx.set_environment(Some(&environment));
+
+    let post = PostProcess::hdr(WIDTH, HEIGHT, ToneMapping::Aces, 1.0).unwrap();
+    post.tone_mapping().unwrap().set_gamma(2.2).unwrap();
+
+    let cube = lit_cube();
+
+    post.render(|| {
+        rendust::clear();
+
+        for row in 0..2 {
+            for col in 0..4 {
+                ctx.set_material(&Material::new()
+                    .base_color(0.9, 0.9, 0.9, 1.0)
+                    .metallic_roughness(row as f32, col as f32 / 3.0));
+
+                ctx.set_model(
+                    Mat4::from_translation(Vec3::new((col as f32 - 1.5) * 1.5, (row as f32 - 0.5) * 1.5, 0.0)) *
+                    Mat4::from_angle_y(math::Deg(30.0)) * Mat4::from_angle_x(math::Deg(20.0))
+                );
+                cube.render();
+            }
+        }
+
+        ctx.clear_material();
+    });
+
+    ctx.set_environment(None);
+}
+
+// Horizontal cross whose faces have distinct colors, with a
+// gradient towards the top of each face to show its orientation
+fn cross() -> Image {
+    let size = 16;
+    let colors = [[255, 0, 0], [0, 255, 255], [0, 255, 0], [255, 0, 255], [0, 0, 255], [255, 255, 0]];
+    let cells = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+
+    let mut data = vec![0u8; (size * 4 * size * 3 * 4) as usize];
+
+    for (color, &(col, row)) in colors.iter().zip(cells.iter()) {
+        for y in 0..size {
+            for x in 0..size {
+                let i = (((row * size + y) * size * 4 + col * size + x) * 4) as usize;
+                let shade = 128 + y * 127 / (size - 1);
+
+                for c in 0..3 {
+                    data[i + c] = (color[c] * shade / 255) as u8;
+                }
+
+                data[i + 3] = 255;
+            }
+        }
+    }
+
+    Image::new(size * 4, size * 3, data)
+}
+
+fn skybox(ctx: &Context) {
+    let projection = math::mat4_perspective(90.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0);
+    ctx.set_projection(projection);
+
+    // Look at a corner of the cube, to see three of its faces
+    let mut camera = Camera::new(Vec3::new(5.0, 2.0, 5.0));
+    camera.target = Vec3::new(1.0, 0.8, -1.0);
+    ctx.set_camera(&camera);
+
+    let skybox = Skybox::new(Rc::new(CubeMap::from_cross(&cross()).unwrap())).unwrap();
+
+    // The cube is drawn in front of the skybox, whatever the order
+    ctx.set_lights(&[Directional::new(Vec3::new(-0.3, -0.5, -1.0), [1.0, 1.0, 1.0], 1.0).into()]);
+    ctx.set_model(Mat4::from_translation(Vec3::new(5.5, 2.5, 3.0)));
+    lit_cube().render();
+
+    skybox.render(&camera, projection);
+}
+
+fn texture_options(_: &Context) {
+    // 4x4 checkerboard, magnified on quads
+    let data: Vec<u8> = (0..16)
+        .flat_map(|i| if (i % 4 + i / 4) % 2 == 0 { vec![255, 255, 255, 255] } else { vec![40, 40, 160, 255] })
+        .collect();
+
+    let quad = |x: f32, uv: f32| Mesh::new(PrimitiveType::Triangles, &[
+        Vertex::new(x - 0.45, -0.8, 0.0).texcoords(-uv, -uv),
+        Vertex::new(x + 0.45, -0.8, 0.0).texcoords(1.0 + uv, -uv),
+        Vertex::new(x + 0.45,  0.8, 0.0).texcoords(1.0 + uv, 1.0 + uv),
+        Vertex::new(x - 0.45, -0.8, 0.0).texcoords(-uv, -uv),
+        Vertex::new(x + 0.45,  0.8, 0.0).texcoords(1.0 + uv, 1.0 + uv),
+        Vertex::new(x - 0.45,  0.8, 0.0).texcoords(-uv, 1.0 + uv),
+    ], None);
+
+    // Nearest filtering, repeated
+    let nearest = Texture::with_options(4, 4, &data, TextureOptions::new().filter(Filter::Nearest));
+    nearest.bind();
+    quad(-0.5, 0.0).render();
+
+    // Linear filtering, with a border around the texture
+    let linear = Texture::with_options(4, 4, &data, TextureOptions::new()
+        .wrap(Wrap::ClampToBorder)
+        .border_color(0.8, 0.2, 0.2, 1.0));
+
+    linear.bind();
+    quad(0.5, 0.25).render();
+
+    linear.unbind();
+}
+
+fn obj(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(60.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 1.0, 3.5)));
+    ctx.set_lights(&[Directional::new(Vec3::new(-0.3, -0.5, -1.0), [1.0, 1.0, 1.0], 3.0).into()]);
+
+    let model = Obj::load("examples/models/shapes.obj").unwrap();
+
+    ctx.set_model(Mat4::from_angle_y(math::Deg(30.0)));
+
+    for mesh in model.meshes.iter() {
+        let material = model.material(mesh.material.as_ref().unwrap()).unwrap();
+
+        ctx.set_material(&material.to_material(|_| None));
+        mesh.to_mesh().render();
+    }
+
+    ctx.clear_material();
+}
+
+fn gltf(ctx: &Context) {
+    let gltf = Gltf::load("examples/models/cubes.gltf").unwrap();
+
+    // The camera and the light are placed by the scene
+    let (camera, placed) = gltf.scene_cameras().into_iter().next().unwrap();
+    ctx.set_projection(camera.projection_matrix(WIDTH as f32 / HEIGHT as f32));
+    ctx.set_camera(&placed);
+
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_lights(&gltf.scene_lights());
+
+    GltfModel::new(&gltf).unwrap().render(ctx, math::mat4_identity());
+}
+
+fn skinning(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(60.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 1.2, 4.0)));
+    ctx.set_lights(&[
+        Directional::new(Vec3::new(-0.4, -1.0, -0.6), [1.0, 1.0, 1.0], 3.0)
+            .shadow(Shadow::new().distance(10.0).resolution(2048))
+            .into()
+    ]);
+
+    let gltf = Gltf::load("examples/models/arm.gltf").unwrap();
+    let mut model = GltfModel::new(&gltf).unwrap();
+
+    // Bent elbow, with a turned shoulder
+    gltf.animations[0].apply(1.5, &mut model.nodes);
+
+    let floor = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-5.0, 0.0,  5.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new(-5.0, 0.0, -5.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 5.0, 0.0, -5.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+        Vertex::new( 5.0, 0.0,  5.0).color(0.6, 0.6, 0.6, 1.0).normal(0.0, 1.0, 0.0),
+    ], None);
+
+    let render = |ctx: &Context| {
+        model.render(ctx, math::mat4_identity());
+
+        ctx.set_model(math::mat4_identity());
+        floor.render();
+    };
+
+    ctx.render_shadows(&render);
+    render(ctx);
+}
+
+fn morph(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(60.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 2.0, 3.0)));
+    ctx.set_lights(&[Directional::new(Vec3::new(-0.4, -1.0, -0.6), [1.0, 1.0, 1.0], 3.0).into()]);
+
+    let gltf = Gltf::load("examples/models/cloth.gltf").unwrap();
+    let mut model = GltfModel::new(&gltf).unwrap();
+
+    // Both targets half blended, on the GPU
+    gltf.animations[0].apply(1.5, &mut model.nodes);
+    model.render(ctx, Mat4::from_translation(Vec3::new(-1.1, 0.0, 0.0)));
+
+    // The same blend on the CPU
+    let primitive = &gltf.meshes[0].primitives[0];
+    let cpu = Mesh::with_morph_targets(PrimitiveType::Triangles, &primitive.vertices, Some(&primitive.indices),
+                                       &primitive.targets, MorphMode::Cpu).unwrap();
+    cpu.set_morph_weights(&model.nodes[0].weights);
+
+    ctx.set_model(Mat4::from_translation(Vec3::new(1.1, 0.0, 0.0)));
+    cpu.render();
+}
+
+fn shapes(ctx: &Context) {
+    ctx.set_projection(math::mat4_perspective(60.0, WIDTH as f32 / HEIGHT as f32, 0.1, 1000.0));
+    ctx.set_ambient_light(Ambient::new([1.0, 1.0, 1.0, 1.0], 0.1));
+    ctx.set_camera(&Camera::new(Vec3::new(0.0, 3.0, 6.0)));
+    ctx.set_lights(&[Directional::new(Vec3::new(-0.4, -1.0, -0.6), [1.0, 1.0, 1.0], 3.0).into()]);
+
+    let shapes = [
+        mesh::shapes::plane(1.0, 1.0, 4, 4),
+        mesh::shapes::cuboid(0.9, 0.9, 0.9, 1),
+        mesh::shapes::uv_sphere(0.5, 32, 16),
+        mesh::shapes::icosphere(0.5, 2),
+        mesh::shapes::cylinder(0.4, 1.0, 32, 1),
+        mesh::shapes::cone(0.45, 1.0, 32, 1),
+        mesh::shapes::capsule(0.3, 0.5, 32, 8),
+        mesh::shapes::torus(0.35, 0.12, 48, 16)
+    ];
+
+    ctx.set_material(&Material::new().base_color(0.8, 0.5, 0.3, 1.0).metallic_roughness(0.0, 0.5));
+
+    for (i, shape) in shapes.iter().enumerate() {
+        let position = Vec3::new((i % 4) as f32 * 1.5 - 2.25, 0.0, (i / 4) as f32 * 1.5 - 1.0);
+
+        ctx.set_model(Mat4::from_translation(position) * Mat4::from_angle_y(math::Rad(0.5)));
+        shape.to_mesh().render();
+    }
+
+    ctx.clear_material();
+}
@@ -0,0 +1,159 @@
+extern crate rendust;
+extern crate imagefmt;
+
+use std::env;
+use std::fs;
+
+use rendust::image::{Image, HdrImage};
+
+// Build a test pattern where each pixel encodes its coordinates
+fn pattern(width: u32, height: u32) -> Image {
+    let mut data = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            data.extend_from_slice(&[x as u8, y as u8, (x ^ y) as u8, 255]);
+        }
+    }
+
+    Image::new(width, height, data)
+}
+
+#[test]
+fn flip_vertical() {
+    let mut image = pattern(3, 5);
+    image.flip_vertical();
+
+    for y in 0..5 {
+        assert_eq!(image.pixel(1, y), [1, (4 - y) as u8, (1 ^ (4 - y)) as u8, 255]);
+    }
+}
+
+#[test]
+fn crop() {
+    let image = pattern(6, 4).crop(2, 1, 3, 2);
+
+    assert_eq!((image.width, image.height), (3, 2));
+    assert_eq!(image.pixel(0, 0), [2, 1, 3, 255]);
+    assert_eq!(image.pixel(2, 1), [4, 2, 6, 255]);
+}
+
+#[test]
+fn rotate_half_turn() {
+    let mut image = pattern(3, 2);
+    image.rotate_half_turn();
+
+    assert_eq!(image.pixel(0, 0), [2, 1, 3, 255]);
+    assert_eq!(image.pixel(1, 0), [1, 1, 0, 255]);
+    assert_eq!(image.pixel(2, 1), [0, 0, 0, 255]);
+}
+
+#[test]
+fn png_round_trip() {
+    // Large enough to span several uncompressed deflate blocks
+    let image = pattern(200, 150);
+    let path = env::temp_dir().join("rendust-round-trip.png");
+
+    image.save(&path).unwrap();
+
+    let read = imagefmt::read(&path, imagefmt::ColFmt::RGBA).unwrap();
+    assert_eq!((read.w, read.h), (200, 150));
+    assert!(read.buf == image.data);
+}
+
+#[test]
+fn ppm_header() {
+    let mut out = Vec::new();
+    pattern(4, 2).write_ppm(&mut out).unwrap();
+
+    assert!(out.starts_with(b"P6\n4 2\n255\n"));
+    assert_eq!(out.len(), 11 + 4 * 2 * 3);
+}
+
+#[test]
+fn unsupported_extension() {
+    let path = env::temp_dir().join("rendust-unsupported.xyz");
+    let _ = fs::remove_file(&path);
+
+    assert!(pattern(1, 1).save(&path).is_err());
+
+    // Nothing is left behind
+    assert!(!path.exists());
+}
+
+// Radiance header for an image of the specified size
+fn hdr_header(width: u32, height: u32) -> Vec<u8> {
+    format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", height, width).into_bytes()
+}
+
+#[test]
+fn hdr_flat_scanlines() {
+    let mut data = hdr_header(2, 1);
+
+    // 1.0 is 128 * 2^(129 - 136), 0.5 and 0.25 share the exponent
+    data.extend_from_slice(&[128, 64, 32, 129]);
+    data.extend_from_slice(&[0, 0, 0, 0]);
+
+    let image = HdrImage::decode(&data[..]).unwrap();
+    assert_eq!((image.width, image.height), (2, 1));
+    assert_eq!(image.pixel(0, 0), [1.0, 0.5, 0.25]);
+    assert_eq!(image.pixel(1, 0), [0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn hdr_rle_scanlines() {
+    let mut data = hdr_header(8, 2);
+
+    for y in 0..2u8 {
+        data.extend_from_slice(&[2, 2, 0, 8]);
+
+        // Red: a run of 8
+        data.extend_from_slice(&[128 + 8, 128]);
+
+        // Green: 8 literals
+        data.push(8);
+        data.extend((0..8).map(|x| x * 16 + y));
+
+        // Blue: a run of 3 and 5 literals
+        data.extend_from_slice(&[128 + 3, 0, 5, 1, 2, 3, 4, 5]);
+
+        // Exponent: a run of 8
+        data.extend_from_slice(&[128 + 8, 136]);
+    }
+
+    let image = HdrImage::decode(&data[..]).unwrap();
+    assert_eq!((image.width, image.height), (8, 2));
+    assert_eq!(image.pixel(0, 0), [128.0, 0.0, 0.0]);
+    assert_eq!(image.pixel(5, 1), [128.0, 81.0, 3.0]);
+    assert_eq!(image.pixel(7, 0), [128.0, 112.0, 5.0]);
+}
+
+#[test]
+fn hdr_invalid() {
+    assert!(HdrImage::decode(&b"P6\n1 1\n255\n"[..]).is_err());
+    assert!(HdrImage::decode(&b"#?RADIANCE\n\n+Y 1 +X 1\n"[..]).is_err());
+
+    // Truncated pixel data
+    let mut data = hdr_header(2, 2);
+    data.extend_from_slice(&[128, 64, 32, 129]);
+    assert!(HdrImage::decode(&data[..]).is_err());
+}
+
+#[test]
+fn hdr_zero_width() {
+    let mut data = hdr_header(0, 1);
+    data.extend_from_slice(&[128, 64, 32, 129]);
+    assert!(HdrImage::decode(&data[..]).is_err());
+}
+
+#[test]
+fn hdr_oversized_header() {
+    // Claims far more pixels than could be allocated
+    let mut data = hdr_header(4294967295, 4294967295);
+    data.extend_from_slice(&[128, 64, 32, 129]);
+    assert!(HdrImage::decode(&data[..]).is_err());
+
+    let mut data = hdr_header(0x7fff, 0x7fff);
+    data.extend_from_slice(&[128, 64, 32, 129]);
+    assert!(HdrImage::decode(&data[..]).is_err());
+}
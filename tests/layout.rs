@@ -0,0 +1,79 @@
+#[macro_use]
+extern crate rendust;
+
+use rendust::Error;
+use rendust::mesh::{self, Vertex, VertexLayout, Attribute, AttributeType, Mesh, MorphTarget, MorphMode, PrimitiveType};
+
+#[repr(C)]
+struct SkinnedVertex {
+    position: [f32; 3],
+    uv: [f32; 2],
+    joints: [u16; 4],
+    weights: [f32; 4]
+}
+
+vertex_layout!(SkinnedVertex {
+    position: 0,
+    uv: 2,
+    joints: 5,
+    weights: 6,
+});
+
+#[test]
+fn default_vertex_layout() {
+    let attrs = Vertex::attributes();
+
+    assert_eq!(attrs, vec![
+        Attribute::new("position", 0, AttributeType::Float, 3, 0),
+        Attribute::new("color", 1, AttributeType::Float, 4, 12),
+        Attribute::new("texcoords", 2, AttributeType::Float, 2, 28),
+        Attribute::new("normal", 3, AttributeType::Float, 3, 36),
+    ]);
+}
+
+#[test]
+fn skinned_vertex_layout() {
+    let attrs = mesh::SkinnedVertex::attributes();
+
+    // The default attributes keep their locations
+    assert_eq!(&attrs[..4], &Vertex::attributes()[..]);
+    assert_eq!(attrs[4], Attribute::new("joints", 4, AttributeType::UnsignedShort, 4, 48));
+    assert_eq!(attrs[5], Attribute::new("weights", 5, AttributeType::Float, 4, 56));
+}
+
+#[test]
+fn custom_vertex_layout() {
+    let attrs = SkinnedVertex::attributes();
+
+    assert_eq!(attrs.len(), 4);
+    assert_eq!(attrs[1], Attribute::new("uv", 2, AttributeType::Float, 2, 12));
+    assert_eq!(attrs[2], Attribute::new("joints", 5, AttributeType::UnsignedShort, 4, 20));
+    assert_eq!(attrs[3], Attribute::new("weights", 6, AttributeType::Float, 4, 28));
+}
+
+#[test]
+fn morph_target_validation() {
+    let vertices = [Vertex::new(0.0, 0.0, 0.0), Vertex::new(1.0, 0.0, 0.0), Vertex::new(0.0, 1.0, 0.0)];
+    let target = MorphTarget::new(vec![[0.0, 0.0, 1.0]; 3]);
+
+    // Each target moves every vertex
+    let short = MorphTarget::new(vec![[0.0, 0.0, 1.0]; 2]);
+    assert!(Mesh::with_morph_targets(PrimitiveType::Triangles, &vertices, None, &[short], MorphMode::Cpu).is_err());
+
+    let normals = target.clone().normals(vec![[0.0, 1.0, 0.0]; 3]);
+    assert!(Mesh::with_morph_targets(PrimitiveType::Triangles, &vertices, None, &[normals.clone(), target.clone()], MorphMode::Gpu).is_err());
+
+    // The GPU blends a limited number of targets
+    let targets = vec![target.clone(); mesh::MAX_MORPH_TARGETS + 1];
+    assert!(Mesh::with_morph_targets(PrimitiveType::Triangles, &vertices, None, &targets, MorphMode::Gpu).is_err());
+
+    // The CPU needs to find the normals it blends
+    let custom: Vec<SkinnedVertex> = (0..3).map(|_| {
+        SkinnedVertex { position: [0.0; 3], uv: [0.0; 2], joints: [0; 4], weights: [1.0, 0.0, 0.0, 0.0] }
+    }).collect();
+
+    match Mesh::with_morph_targets(PrimitiveType::Triangles, &custom, None, &[normals], MorphMode::Cpu) {
+        Err(Error::VertexLayout(msg)) => assert!(msg.contains("normal")),
+        _ => panic!("expected a vertex layout error")
+    }
+}
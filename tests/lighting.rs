@@ -0,0 +1,33 @@
+extern crate rendust;
+
+use rendust::lighting::{self, Shadow};
+
+#[test]
+fn uniform_splits() {
+    let splits = lighting::cascade_splits(1.0, 9.0, 4, 0.0);
+    assert_eq!(splits, vec![3.0, 5.0, 7.0, 9.0]);
+}
+
+#[test]
+fn logarithmic_splits() {
+    let splits = lighting::cascade_splits(1.0, 8.0, 3, 1.0);
+
+    for (split, expected) in splits.iter().zip([2.0, 4.0, 8.0].iter()) {
+        assert!((split - expected).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn splits_end_at_far_plane() {
+    let splits = lighting::cascade_splits(0.1, 50.0, 3, 0.75);
+
+    assert_eq!(splits.len(), 3);
+    assert!((splits[2] - 50.0).abs() < 1e-3);
+    assert!(splits[0] < splits[1] && splits[1] < splits[2]);
+}
+
+#[test]
+fn cascades_are_clamped() {
+    assert_eq!(Shadow::new().cascades(0).cascades, 1);
+    assert_eq!(Shadow::new().cascades(16).cascades, lighting::MAX_CASCADES as u32);
+}
@@ -0,0 +1,80 @@
+extern crate rendust;
+
+use std::env;
+
+use rendust::context::Context;
+use rendust::material::{Material, AlphaMode};
+use rendust::math;
+use rendust::mesh::{Mesh, PrimitiveType, Vertex};
+use rendust::window::Headless;
+
+// Render a quad covering the whole surface with the specified
+// material, over black, and return the color at its center
+fn render_quad(surface: &Headless, material: &Material) -> [u8; 4] {
+    let ctx = Context::new().unwrap();
+
+    ctx.set_projection(math::mat4_identity());
+    ctx.set_view(math::mat4_identity());
+    ctx.set_model(math::mat4_identity());
+
+    let quad = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-1.0, -1.0, 0.0).normal(0.0, 0.0, 1.0),
+        Vertex::new( 1.0, -1.0, 0.0).normal(0.0, 0.0, 1.0),
+        Vertex::new( 1.0,  1.0, 0.0).normal(0.0, 0.0, 1.0),
+        Vertex::new(-1.0,  1.0, 0.0).normal(0.0, 0.0, 1.0),
+    ], None);
+
+    rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+    rendust::clear();
+
+    ctx.set_material(material);
+    quad.render();
+    ctx.clear_material();
+
+    surface.capture_frame().pixel(8, 8)
+}
+
+// Skipped like the golden image tests when RENDUST_SKIP_GOLDEN is set
+fn context() -> Option<Headless> {
+    match Headless::new(16, 16) {
+        Ok(surface) => Some(surface),
+        Err(_) if env::var_os("RENDUST_SKIP_GOLDEN").is_some() => None,
+        Err(err) => panic!("no headless context available: {:?}, set RENDUST_SKIP_GOLDEN=1 to skip", err)
+    }
+}
+
+#[test]
+fn factors_are_clamped() {
+    let material = Material::new().metallic_roughness(1.5, -0.25);
+    assert_eq!((material.metallic, material.roughness), (1.0, 0.0));
+
+    let material = Material::new().metallic_roughness(-2.0, 4.0);
+    assert_eq!((material.metallic, material.roughness), (0.0, 1.0));
+}
+
+#[test]
+fn alpha_modes() {
+    let surface = match context() {
+        Some(surface) => surface,
+        None => return
+    };
+
+    // Unlit, so only the emission shows
+    let emissive = |alpha: f32, mode: AlphaMode| {
+        Material::new().base_color(0.0, 0.0, 0.0, alpha).emissive(1.0, 1.0, 1.0).alpha_mode(mode)
+    };
+
+    let opaque = render_quad(&surface, &emissive(1.0, AlphaMode::Opaque));
+    assert!(opaque[0] > 200);
+
+    // The alpha channel is ignored by opaque materials
+    assert_eq!(render_quad(&surface, &emissive(0.25, AlphaMode::Opaque)), opaque);
+
+    // Masked fragments are either kept whole or discarded
+    assert_eq!(render_quad(&surface, &emissive(0.75, AlphaMode::Mask(0.5)))[..3], opaque[..3]);
+    assert_eq!(render_quad(&surface, &emissive(0.25, AlphaMode::Mask(0.5)))[0], 0);
+
+    // Blended fragments are mixed with the black behind them
+    let blended = render_quad(&surface, &emissive(0.5, AlphaMode::Blend));
+    assert!(blended[0] > 0 && blended[0] < opaque[0]);
+}
@@ -0,0 +1,177 @@
+extern crate rendust;
+
+use std::path::Path;
+
+use rendust::obj::{Obj, ObjMaterial};
+use rendust::material::AlphaMode;
+
+const CUBE_FACES: &'static str = "
+# Two faces of a cube, sharing an edge
+mtllib cube.mtl
+o cube
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 1 0 -1
+v 1 1 -1
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+vn 1 0 0
+usemtl red
+f 1/1/1 2/2/1 3/3/1 4/4/1
+usemtl blue
+f -5/1/2 -2/2/2 -1/3/2 -4/4/2
+";
+
+#[test]
+fn quads_split_by_material() {
+    let obj = Obj::parse(CUBE_FACES.as_bytes()).unwrap();
+
+    assert_eq!(obj.meshes.len(), 2);
+    assert_eq!(obj.meshes[0].name, "cube");
+    assert_eq!(obj.meshes[0].material, Some("red".to_string()));
+    assert_eq!(obj.meshes[1].material, Some("blue".to_string()));
+
+    for mesh in obj.meshes.iter() {
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices.len(), 6);
+    }
+
+    // Relative indices, and flipped texture coordinates
+    let side = &obj.meshes[1];
+    assert_eq!(side.vertices[1].position, [1.0, 0.0, -1.0]);
+    assert_eq!(side.vertices[1].normal, [1.0, 0.0, 0.0]);
+    assert_eq!(side.vertices[2].texcoords, [1.0, 0.0]);
+}
+
+#[test]
+fn shared_vertices() {
+    let source = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+f 1 2 3
+f 1 3 4
+";
+    let obj = Obj::parse(source.as_bytes()).unwrap();
+    let mesh = &obj.meshes[0];
+
+    assert_eq!(mesh.vertices.len(), 4);
+    assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+
+    // Missing normals are computed from the faces
+    for vertex in mesh.vertices.iter() {
+        assert_eq!(vertex.normal, [0.0, 0.0, 1.0]);
+    }
+}
+
+#[test]
+fn concave_polygon() {
+    // Arrow shape with a notch at the fourth vertex, which
+    // a fan from the first vertex would cover
+    let source = "
+v 0 0 0
+v 2 0 0
+v 2 2 0
+v 1 0.5 0
+v 0 2 0
+f 1 2 3 4 5
+";
+    let obj = Obj::parse(source.as_bytes()).unwrap();
+    let mesh = &obj.meshes[0];
+
+    assert_eq!(mesh.indices.len(), 9);
+
+    // Every triangle faces the same way, and they cover the polygon exactly
+    let mut area = 0.0;
+
+    for triangle in mesh.indices.chunks(3) {
+        let p: Vec<[f32; 3]> = triangle.iter().map(|&i| mesh.vertices[i as usize].position).collect();
+        let signed = ((p[1][0] - p[0][0]) * (p[2][1] - p[0][1]) - (p[1][1] - p[0][1]) * (p[2][0] - p[0][0])) / 2.0;
+
+        assert!(signed > 0.0, "triangle {:?} is inverted", triangle);
+        area += signed;
+    }
+
+    assert_eq!(area, 2.5);
+}
+
+#[test]
+fn groups() {
+    let source = "
+v 0 0 0
+v 1 0 0
+v 0 1 0
+g first
+f 1 2 3
+g second
+f 1 2 3
+g first
+f 3 2 1
+";
+    let obj = Obj::parse(source.as_bytes()).unwrap();
+
+    assert_eq!(obj.meshes.len(), 2);
+    assert_eq!(obj.meshes[0].name, "first");
+    assert_eq!(obj.meshes[0].indices.len(), 6);
+    assert_eq!(obj.meshes[1].name, "second");
+}
+
+#[test]
+fn invalid() {
+    assert!(Obj::parse("v 0 0\n".as_bytes()).is_err());
+    assert!(Obj::parse("v 0 0 0\nf 1 2 3\n".as_bytes()).is_err());
+    assert!(Obj::parse("v 0 0 0\nv 1 0 0\nf 1 2\n".as_bytes()).is_err());
+
+    // Non-finite numbers are rejected with their line
+    for value in ["nan", "inf", "-inf", "1e39"].iter() {
+        let source = format!("v 0 0 0\nv {} 0 0\nv 0 1 0\nv 0 0 1\nf 1 2 3 4\n", value);
+
+        match Obj::parse(source.as_bytes()) {
+            Err(err) => assert!(err.to_string().contains("line 2: invalid number")),
+            Ok(_) => panic!("{} was accepted", value)
+        }
+    }
+}
+
+#[test]
+fn materials() {
+    let source = "
+newmtl red
+Kd 0.8 0.1 0.1
+Ns 48
+d 0.5
+map_Kd -s 1 1 1 textures\\red.png
+
+newmtl metal
+Kd 0.9
+Pr 0.2
+Pm 1
+Ke 1 0.5 0
+";
+    let materials = ObjMaterial::parse(source.as_bytes()).unwrap();
+    assert_eq!(materials.len(), 2);
+
+    let red = &materials[0];
+    assert_eq!(red.diffuse, [0.8, 0.1, 0.1]);
+    assert_eq!(red.opacity, 0.5);
+    assert_eq!(red.diffuse_texture.as_deref(), Some(Path::new("textures/red.png")));
+
+    let material = red.to_material(|_| None);
+    assert_eq!(material.base_color, [0.8, 0.1, 0.1, 0.5]);
+    assert_eq!(material.metallic, 0.0);
+    assert!((material.roughness - 0.2).abs() < 1e-6);
+    assert_eq!(material.alpha_mode, AlphaMode::Blend);
+    assert!(material.base_color_texture.is_none());
+
+    let metal = materials[1].to_material(|_| None);
+    assert_eq!(metal.base_color, [0.9, 0.9, 0.9, 1.0]);
+    assert_eq!((metal.metallic, metal.roughness), (1.0, 0.2));
+    assert_eq!(metal.emissive, [1.0, 0.5, 0.0]);
+    assert_eq!(metal.alpha_mode, AlphaMode::Opaque);
+}
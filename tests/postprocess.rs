@@ -0,0 +1,28 @@
+extern crate rendust;
+
+use rendust::postprocess;
+
+#[test]
+fn neutral_lut_layout() {
+    let lut = postprocess::neutral_lut(4);
+
+    assert_eq!((lut.width, lut.height), (16, 4));
+
+    // Red increases in each square, blue from one square to the next
+    assert_eq!(lut.pixel(0, 0), [0, 0, 0, 255]);
+    assert_eq!(lut.pixel(3, 0), [255, 0, 0, 255]);
+    assert_eq!(lut.pixel(12, 0), [0, 0, 255, 255]);
+
+    // Green increases downwards
+    assert_eq!(lut.pixel(0, 3), [0, 255, 0, 255]);
+    assert_eq!(lut.pixel(15, 3), [255, 255, 255, 255]);
+}
+
+#[test]
+fn neutral_lut_steps() {
+    let lut = postprocess::neutral_lut(16);
+
+    for r in 0..16 {
+        assert_eq!(lut.pixel(r, 0)[0], r as u8 * 17);
+    }
+}
@@ -0,0 +1,196 @@
+#[macro_use]
+extern crate rendust;
+
+use std::env;
+use std::iter;
+
+use rendust::Error;
+use rendust::window::Headless;
+use rendust::shaders::{Program, ShaderError, Stage};
+use rendust::uniforms;
+
+// Vertex providing a single column of the matrix read by `MATRIX_VERT`
+#[repr(C)]
+struct ColumnVertex {
+    position: [f32; 3],
+    model: [f32; 4]
+}
+
+vertex_layout!(ColumnVertex {
+    position: 0,
+    model: 4,
+});
+
+// Vertex providing every column of the matrix read by `MATRIX_VERT`
+#[repr(C)]
+struct MatrixVertex {
+    position: [f32; 3],
+    model: [f32; 4],
+    column1: [f32; 4],
+    column2: [f32; 4],
+    column3: [f32; 4]
+}
+
+vertex_layout!(MatrixVertex {
+    position: 0,
+    model: 4,
+    column1: 5,
+    column2: 6,
+    column3: 7,
+});
+
+const MATRIX_VERT: &'static str = "#version 140
+in vec3 position;
+in mat4 model;
+
+void main() {
+    gl_Position = model * vec4(position, 1.0);
+}
+";
+
+const FRAG: &'static str = "#version 140
+out vec4 out_color;
+
+void main() {
+    out_color = vec4(1.0);
+}
+";
+
+fn error_with_log(log: &str) -> ShaderError {
+    ShaderError {
+        message: "Failed to compile fragment shader".to_owned(),
+        stage: Some(Stage::Fragment),
+        log: log.to_owned(),
+        source: Some("#version 140\nvoid main() {\n    foo();\n}\n".to_owned())
+    }
+}
+
+#[test]
+fn mesa_log_lines() {
+    let err = error_with_log("0:3(5): error: no function with name 'foo'\n0:3(5): error: type mismatch\n");
+    assert_eq!(err.error_lines(), vec![3]);
+}
+
+#[test]
+fn nvidia_log_lines() {
+    let err = error_with_log("0(3) : error C1008: undefined variable \"foo\"\n");
+    assert_eq!(err.error_lines(), vec![3]);
+}
+
+#[test]
+fn amd_log_lines() {
+    let err = error_with_log("ERROR: 0:3: 'foo' : no matching overloaded function found\nERROR: 0:4: '' : syntax error\n");
+    assert_eq!(err.error_lines(), vec![3, 4]);
+}
+
+#[test]
+fn display_source_with_line_numbers() {
+    let err = error_with_log("0:3(5): error: no function with name 'foo'");
+    let text = err.to_string();
+
+    assert!(text.starts_with("Failed to compile fragment shader\n0:3(5): error"));
+    assert!(text.contains("  1 | #version 140"));
+    assert!(text.contains("> 3 |     foo();"));
+}
+
+// Create a context for the tests that render, which are skipped
+// like the golden image tests when RENDUST_SKIP_GOLDEN is set
+fn context() -> Option<Headless> {
+    match Headless::new(16, 16) {
+        Ok(surface) => Some(surface),
+        Err(_) if env::var_os("RENDUST_SKIP_GOLDEN").is_some() => None,
+        Err(err) => panic!("no headless context available: {:?}, set RENDUST_SKIP_GOLDEN=1 to skip", err)
+    }
+}
+
+#[test]
+fn reflect_long_names() {
+    let _surface = match context() {
+        Some(surface) => surface,
+        None => return
+    };
+
+    // Longer than any fixed size buffer would allow
+    let attribute: String = iter::repeat('a').take(300).collect();
+    let uniform: String = iter::repeat('u').take(300).collect();
+
+    let vert = format!(
+        "#version 140\nin vec3 {0};\nuniform float {1}[2];\nvoid main() {{\n    gl_Position = vec4({0} * {1}[1], 1.0);\n}}\n",
+        attribute, uniform
+    );
+
+    let program = Program::new(&vert, FRAG).unwrap();
+
+    let info = program.attributes().iter().find(|info| info.name == attribute).unwrap();
+    assert_eq!(uniforms::type_name(info.kind), "vec3");
+
+    let info = program.uniforms().iter().find(|info| info.name.starts_with(&uniform[..])).unwrap();
+    assert_eq!(info.name, format!("{}[0]", uniform));
+    assert_eq!(info.size, 2);
+    assert!(info.location >= 0);
+    assert_eq!(info.block, None);
+}
+
+#[test]
+fn reflect_uniform_blocks() {
+    let _surface = match context() {
+        Some(surface) => surface,
+        None => return
+    };
+
+    let frag = "#version 140
+uniform Material {
+    vec4 color;
+    float strength;
+};
+
+out vec4 out_color;
+
+void main() {
+    out_color = color * strength;
+}
+";
+
+    let program = Program::new(MATRIX_VERT, frag).unwrap();
+    let blocks = program.uniform_blocks();
+
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].name, "Material");
+    assert!(blocks[0].data_size >= 20);
+
+    let mut names = blocks[0].uniforms.clone();
+    names.sort();
+    assert_eq!(names, vec!["color", "strength"]);
+
+    for info in program.uniforms() {
+        assert_eq!(info.block, Some(blocks[0].index));
+        assert_eq!(info.location, -1);
+    }
+}
+
+#[test]
+fn matrix_attributes_take_one_location_per_column() {
+    let _surface = match context() {
+        Some(surface) => surface,
+        None => return
+    };
+
+    let program = Program::with_layout::<MatrixVertex>(MATRIX_VERT, FRAG).unwrap();
+
+    let info = program.attributes().iter().find(|info| info.name == "model").unwrap();
+    assert_eq!(uniforms::type_name(info.kind), "mat4");
+    assert_eq!(info.location, 4);
+
+    program.check_layout::<MatrixVertex>().unwrap();
+
+    // The missing columns are reported, rather than the
+    // matrix being mistaken for a scalar
+    match program.check_layout::<ColumnVertex>() {
+        Err(Error::VertexLayout(problems)) => {
+            assert!(!problems.contains("location 4"), "{}", problems);
+            assert!(problems.contains("model (location 5) is not provided"), "{}", problems);
+            assert!(problems.contains("model (location 7) is not provided"), "{}", problems);
+        },
+        other => panic!("unexpected result {:?}", other.map(|_| ()))
+    }
+}
@@ -0,0 +1,140 @@
+extern crate rendust;
+extern crate cgmath;
+
+use cgmath::InnerSpace;
+
+use rendust::math::Vec3;
+use rendust::mesh::shapes::{self, Shape};
+
+fn every_shape() -> Vec<(&'static str, Shape)> {
+    vec![
+        ("plane", shapes::plane(2.0, 1.0, 4, 2)),
+        ("grid", shapes::grid(4.0, 4.0, 8, 8, |x, z| (x * z).sin())),
+        ("cuboid", shapes::cuboid(1.0, 2.0, 3.0, 2)),
+        ("uv_sphere", shapes::uv_sphere(1.5, 16, 8)),
+        ("icosphere", shapes::icosphere(1.5, 2)),
+        ("cylinder", shapes::cylinder(0.5, 2.0, 12, 3)),
+        ("cone", shapes::cone(0.5, 2.0, 12, 3)),
+        ("capsule", shapes::capsule(0.5, 1.0, 12, 4)),
+        ("torus", shapes::torus(1.0, 0.25, 24, 12))
+    ]
+}
+
+fn position(shape: &Shape, index: u32) -> Vec3 {
+    Vec3::from(shape.vertices[index as usize].position)
+}
+
+#[test]
+fn valid_geometry() {
+    for (name, shape) in every_shape() {
+        assert!(shape.indices.len() % 3 == 0, "{}", name);
+        assert!(shape.indices.iter().all(|&i| (i as usize) < shape.vertices.len()), "{}", name);
+
+        for vertex in shape.vertices.iter() {
+            assert!((Vec3::from(vertex.normal).magnitude() - 1.0).abs() < 1e-4, "{}: {:?}", name, vertex);
+            assert!(vertex.texcoords.iter().all(|t| (0.0..1.5).contains(t)), "{}: {:?}", name, vertex);
+        }
+    }
+}
+
+#[test]
+fn winding_matches_normals() {
+    for (name, shape) in every_shape() {
+        for triangle in shape.indices.chunks(3) {
+            let (a, b, c) = (position(&shape, triangle[0]), position(&shape, triangle[1]), position(&shape, triangle[2]));
+            let face = (b - a).cross(c - a);
+
+            // No degenerate triangles are left
+            assert!(face.magnitude() > 1e-6, "{}: {:?}", name, triangle);
+
+            let normals = triangle.iter()
+                .map(|&i| Vec3::from(shape.vertices[i as usize].normal))
+                .fold(Vec3::new(0.0, 0.0, 0.0), |sum, normal| sum + normal);
+
+            assert!(face.dot(normals) > 0.0, "{}: {:?}", name, triangle);
+        }
+    }
+}
+
+#[test]
+fn plane() {
+    let plane = shapes::plane(2.0, 1.0, 4, 2);
+
+    assert_eq!(plane.vertices.len(), 5 * 3);
+    assert_eq!(plane.indices.len(), 4 * 2 * 6);
+
+    // The top left corner of the texture is at the back left
+    assert_eq!(plane.vertices[0].position, [-1.0, 0.0, -0.5]);
+    assert_eq!(plane.vertices[0].texcoords, [0.0, 0.0]);
+    assert_eq!(plane.vertices[14].position, [1.0, 0.0, 0.5]);
+    assert_eq!(plane.vertices[14].texcoords, [1.0, 1.0]);
+
+    assert!(plane.vertices.iter().all(|vertex| vertex.normal == [0.0, 1.0, 0.0]));
+}
+
+#[test]
+fn grid_slopes() {
+    let grid = shapes::grid(2.0, 2.0, 4, 4, |x, _| x);
+    let half = 0.5f32.sqrt();
+
+    for vertex in grid.vertices.iter() {
+        assert_eq!(vertex.position[1], vertex.position[0]);
+        assert!((Vec3::from(vertex.normal) - Vec3::new(-half, half, 0.0)).magnitude() < 1e-5);
+    }
+}
+
+#[test]
+fn cuboid() {
+    let cuboid = shapes::cuboid(1.0, 2.0, 3.0, 2).color(1.0, 0.0, 0.0, 1.0);
+
+    assert_eq!(cuboid.vertices.len(), 6 * 9);
+    assert_eq!(cuboid.indices.len(), 6 * 4 * 6);
+
+    for vertex in cuboid.vertices.iter() {
+        let p = vertex.position;
+        assert!(p[0].abs() <= 0.5 && p[1].abs() <= 1.0 && p[2].abs() <= 1.5);
+        assert_eq!(vertex.color, [1.0, 0.0, 0.0, 1.0]);
+    }
+}
+
+#[test]
+fn spheres() {
+    // Triangles collapsed at the poles are left out
+    let sphere = shapes::uv_sphere(1.5, 8, 4);
+    assert_eq!(sphere.indices.len() / 3, 8 * (4 * 2 - 2));
+
+    // Each subdivision splits every triangle into four
+    let icosphere = shapes::icosphere(1.5, 2);
+    assert_eq!(icosphere.indices.len() / 3, 20 * 16);
+
+    for vertex in sphere.vertices.iter().chain(icosphere.vertices.iter()) {
+        let p = Vec3::from(vertex.position);
+
+        assert!((p.magnitude() - 1.5).abs() < 1e-4);
+        assert!((p / 1.5 - Vec3::from(vertex.normal)).magnitude() < 1e-4);
+    }
+
+    // The front of both spheres is at the middle of the texture
+    let front = |shape: &Shape| shape.vertices.iter()
+        .filter(|vertex| vertex.position[2] > 1.4)
+        .all(|vertex| (vertex.texcoords[0] - 0.5).abs() < 0.1);
+
+    assert!(front(&sphere));
+    assert!(front(&icosphere));
+}
+
+#[test]
+fn capsule_and_torus() {
+    let capsule = shapes::capsule(0.5, 1.0, 12, 4);
+    let top = capsule.vertices.iter().map(|vertex| vertex.position[1]).fold(0.0, f32::max);
+    assert!((top - 1.0).abs() < 1e-6);
+
+    let torus = shapes::torus(1.0, 0.25, 24, 12);
+
+    for vertex in torus.vertices.iter() {
+        let p = vertex.position;
+        let center = Vec3::new(p[0], 0.0, p[2]).normalize();
+
+        assert!(((Vec3::from(p) - center).magnitude() - 0.25).abs() < 1e-5);
+    }
+}
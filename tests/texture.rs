@@ -0,0 +1,130 @@
+extern crate rendust;
+
+use std::env;
+use std::rc::Rc;
+
+use rendust::context::Context;
+use rendust::image::Image;
+use rendust::material::Material;
+use rendust::math;
+use rendust::mesh::{Mesh, PrimitiveType, Vertex, Texture, TextureFormat, TextureOptions, Wrap, Filter};
+use rendust::window::Headless;
+
+const SIZE: u32 = 16;
+
+// Skipped like the golden image tests when RENDUST_SKIP_GOLDEN is set
+fn context() -> Option<Headless> {
+    match Headless::new(SIZE, SIZE) {
+        Ok(surface) => Some(surface),
+        Err(_) if env::var_os("RENDUST_SKIP_GOLDEN").is_some() => None,
+        Err(err) => panic!("no headless context available: {:?}, set RENDUST_SKIP_GOLDEN=1 to skip", err)
+    }
+}
+
+// Render a quad covering the whole surface, showing the texture as is
+// through the emission of a black material. Texture coordinates go from
+// `min` at the bottom left corner to `max` at the top right one
+fn render_texture(surface: &Headless, texture: Texture, min: f32, max: f32) -> Image {
+    let ctx = Context::new().unwrap();
+
+    ctx.set_projection(math::mat4_identity());
+    ctx.set_view(math::mat4_identity());
+    ctx.set_model(math::mat4_identity());
+
+    let quad = Mesh::new(PrimitiveType::Quads, &[
+        Vertex::new(-1.0, -1.0, 0.0).normal(0.0, 0.0, 1.0).texcoords(min, min),
+        Vertex::new( 1.0, -1.0, 0.0).normal(0.0, 0.0, 1.0).texcoords(max, min),
+        Vertex::new( 1.0,  1.0, 0.0).normal(0.0, 0.0, 1.0).texcoords(max, max),
+        Vertex::new(-1.0,  1.0, 0.0).normal(0.0, 0.0, 1.0).texcoords(min, max),
+    ], None);
+
+    let material = Material::new()
+        .base_color(0.0, 0.0, 0.0, 1.0)
+        .emissive(1.0, 1.0, 1.0)
+        .emissive_texture(Rc::new(texture));
+
+    rendust::set_clear_color(0.0, 0.0, 0.0, 1.0);
+    rendust::clear();
+
+    ctx.set_material(&material);
+    quad.render();
+    ctx.clear_material();
+
+    surface.capture_frame()
+}
+
+// A checkerboard of single black and white texels, four times
+// larger than the surface
+fn checkerboard(options: TextureOptions) -> Texture {
+    let size = SIZE * 4;
+    let mut data = Vec::new();
+
+    for y in 0..size {
+        for x in 0..size {
+            let value = if (x + y) % 2 == 0 { 255 } else { 0 };
+            data.extend_from_slice(&[value, value, value, 255]);
+        }
+    }
+
+    Texture::with_options(size, size, &data, options)
+}
+
+#[test]
+fn minification() {
+    let surface = match context() {
+        Some(surface) => surface,
+        None => return
+    };
+
+    // Each pixel samples the texel at its center, always a white one,
+    // unless a smaller mipmap averages the checkerboard to gray
+    let value = |options: TextureOptions| {
+        render_texture(&surface, checkerboard(options), 0.0, 1.0).pixel(SIZE / 2, SIZE / 2)[0]
+    };
+
+    let gray = |options: TextureOptions| {
+        let value = value(options);
+        value > 30 && value < 225
+    };
+
+    assert_eq!(value(TextureOptions::new().filter(Filter::Nearest)), 255);
+    assert!(gray(TextureOptions::new().filter(Filter::Nearest).mipmaps(true)));
+
+    // Trilinear filtering generates mipmaps even when they are not requested,
+    // otherwise the texture would be incomplete and sample black
+    let options = TextureOptions::new().filter(Filter::Trilinear).mipmaps(false);
+    assert!(options.has_mipmaps());
+    assert!(gray(options));
+}
+
+#[test]
+fn wrapping() {
+    let surface = match context() {
+        Some(surface) => surface,
+        None => return
+    };
+
+    let white = |options: TextureOptions| Texture::with_options(1, 1, &[255, 255, 255, 255], options.filter(Filter::Nearest));
+
+    // The left and bottom halves are outside of the texture,
+    // and the rows of the frame go from the top to the bottom
+    let frame = render_texture(&surface, white(TextureOptions::new()), -1.0, 1.0);
+    assert_eq!(frame.pixel(2, SIZE - 2), [255, 255, 255, 255]);
+
+    let options = TextureOptions::new().wrap(Wrap::ClampToBorder).border_color(1.0, 0.0, 0.0, 1.0);
+    let frame = render_texture(&surface, white(options), -1.0, 1.0);
+    assert_eq!(frame.pixel(2, SIZE - 2), [255, 0, 0, 255]);
+    assert_eq!(frame.pixel(SIZE - 2, 2), [255, 255, 255, 255]);
+}
+
+#[test]
+#[should_panic(expected = "texture data does not match its dimensions")]
+fn float_texture_data_too_short() {
+    Texture::from_floats(2, 2, TextureFormat::Rgba32F, &[0.0; 15]);
+}
+
+#[test]
+#[should_panic(expected = "would be clamped")]
+fn float_texture_needs_float_format() {
+    Texture::from_floats(1, 1, TextureFormat::Rgba8, &[2.0, 0.0, 0.0, 1.0]);
+}
@@ -0,0 +1,41 @@
+extern crate rendust;
+extern crate gl;
+
+use rendust::math::{Vec3, Mat3, Mat4};
+use rendust::uniforms::{self, Uniform};
+
+#[test]
+fn scalar_types() {
+    assert!(f32::accepts(gl::FLOAT));
+    assert!(!f32::accepts(gl::INT));
+
+    assert!(bool::accepts(gl::BOOL));
+    assert!(u32::accepts(gl::UNSIGNED_INT));
+}
+
+#[test]
+fn integers_set_samplers() {
+    assert!(i32::accepts(gl::INT));
+    assert!(i32::accepts(gl::SAMPLER_2D));
+    assert!(i32::accepts(gl::SAMPLER_CUBE));
+    assert!(<[i32]>::accepts(gl::SAMPLER_2D_SHADOW));
+    assert!(!i32::accepts(gl::FLOAT));
+}
+
+#[test]
+fn vectors_and_matrices() {
+    assert!(Vec3::accepts(gl::FLOAT_VEC3));
+    assert!(<[f32; 3]>::accepts(gl::FLOAT_VEC3));
+    assert!(!Vec3::accepts(gl::FLOAT_VEC4));
+
+    assert!(Mat3::accepts(gl::FLOAT_MAT3));
+    assert!(!Mat3::accepts(gl::FLOAT_MAT4));
+    assert!(<[Mat4]>::accepts(gl::FLOAT_MAT4));
+}
+
+#[test]
+fn glsl_type_names() {
+    assert_eq!(uniforms::type_name(gl::FLOAT_VEC4), "vec4");
+    assert_eq!(uniforms::type_name(gl::FLOAT_MAT3), "mat3");
+    assert_eq!(uniforms::type_name(gl::SAMPLER_2D_SHADOW), "sampler2DShadow");
+}